@@ -204,10 +204,15 @@ impl SwitchHelper {
 
     pub fn has_fall_through(s: &Stmt) -> bool {
         let case = Self::to_case_clause(s);
-        case.body.last().map_or(false, |x| match x {
-            Stmt::Branch(b) => b.token == Token::FALLTHROUGH,
-            _ => false,
-        })
+        // trailing empty statements don't count, as in the type checker
+        case.body
+            .iter()
+            .rev()
+            .find(|x| !matches!(x, Stmt::Empty(_)))
+            .map_or(false, |x| match x {
+                Stmt::Branch(b) => b.token == Token::FALLTHROUGH,
+                _ => false,
+            })
     }
 }
 
@@ -1,11 +1,12 @@
 use slotmap::KeyData;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::iter::FromIterator;
 
 use super::branch::*;
 use super::call::CallHelper;
 use super::emit::*;
+use super::escape::EscapeAnalysis;
 use super::interface::IfaceMapping;
 use super::package::PkgHelper;
 use super::types::{SelectionType, TypeCache, TypeLookup};
@@ -18,14 +19,17 @@ use goscript_vm::value::*;
 use goscript_vm::zero_val;
 
 use goscript_parser::ast::*;
+use goscript_parser::errors::ErrorList;
 use goscript_parser::objects::Objects as AstObjects;
 use goscript_parser::objects::*;
 use goscript_parser::position::Pos;
 use goscript_parser::token::Token;
 use goscript_parser::visitor::{walk_decl, walk_expr, walk_stmt, ExprVisitor, StmtVisitor};
+use goscript_parser::FileSet;
+use goscript_parser::Parser;
 use goscript_types::{
-    identical_ignore_tags, Builtin, OperandMode, PackageKey as TCPackageKey, TCObjects, Type,
-    TypeInfo, TypeKey as TCTypeKey,
+    identical_ignore_tags, Builtin, ObjKey as TCObjKey, OperandMode, PackageKey as TCPackageKey,
+    TCObjects, Type, TypeInfo, TypeKey as TCTypeKey,
 };
 
 macro_rules! current_func_mut {
@@ -78,6 +82,8 @@ pub struct CodeGen<'a> {
     ast_objs: &'a AstObjects,
     tc_objs: &'a TCObjects,
     dummy_gcv: &'a mut GcoVec,
+    fset: &'a FileSet,
+    errors: &'a ErrorList,
     t: TypeLookup<'a>,
     iface_mapping: &'a mut IfaceMapping,
     call_helper: &'a mut CallHelper,
@@ -88,6 +94,12 @@ pub struct CodeGen<'a> {
     func_stack: Vec<FunctionKey>,
     func_t_stack: Vec<TCTypeKey>, // for casting return values to interfaces
     blank_ident: IdentKey,
+    // maps instances of generic functions to the names they are declared with
+    instances: HashMap<TCObjKey, IdentKey>,
+    // pointer variables whose address doesn't escape, *p is compiled as the
+    // variable they point to, and the &x they are defined with is not taken
+    deref_aliases: HashMap<TCObjKey, Expr>,
+    elided_refs: HashSet<NodeId>,
 }
 
 impl<'a> CodeGen<'a> {
@@ -96,6 +108,8 @@ impl<'a> CodeGen<'a> {
         asto: &'a AstObjects,
         tco: &'a TCObjects,
         dummy_gcv: &'a mut GcoVec,
+        fset: &'a FileSet,
+        errors: &'a ErrorList,
         ti: &'a TypeInfo,
         type_cache: &'a mut TypeCache,
         mapping: &'a mut IfaceMapping,
@@ -107,11 +121,21 @@ impl<'a> CodeGen<'a> {
         bk: IdentKey,
     ) -> CodeGen<'a> {
         let unsafe_ptr_meta = vmo.metadata.unsafe_ptr.clone();
+        let instances = ti
+            .instances
+            .iter()
+            .map(|f| {
+                let name = asto.fdecls[*f].name;
+                (ti.defs[&name].unwrap(), name)
+            })
+            .collect();
         CodeGen {
             objects: vmo,
             ast_objs: asto,
             tc_objs: tco,
             dummy_gcv: dummy_gcv,
+            fset: fset,
+            errors: errors,
             t: TypeLookup::new(tco, ti, type_cache, unsafe_ptr_meta),
             iface_mapping: mapping,
             call_helper: call_helper,
@@ -121,6 +145,9 @@ impl<'a> CodeGen<'a> {
             func_stack: Vec::new(),
             func_t_stack: Vec::new(),
             blank_ident: bk,
+            instances: instances,
+            deref_aliases: HashMap::new(),
+            elided_refs: HashSet::new(),
         }
     }
 
@@ -128,6 +155,10 @@ impl<'a> CodeGen<'a> {
         &mut self.pkg_helper
     }
 
+    fn add_error(&self, pos: Pos, msg: String) {
+        self.errors.add(self.fset.position(pos), msg, false, false);
+    }
+
     fn resolve_any_ident(&mut self, ident: &IdentKey, expr: Option<&Expr>) -> EntIndex {
         let mode = expr.map_or(&OperandMode::Value, |x| self.t.get_expr_mode(x));
         match mode {
@@ -136,7 +167,18 @@ impl<'a> CodeGen<'a> {
                 let tctype = lookup.underlying_tc(lookup.get_use_tc_type(*ident));
                 match lookup.basic_type_from_tc(tctype, self.objects) {
                     Some(meta) => EntIndex::TypeMeta(meta),
-                    None => self.resolve_var_ident(ident),
+                    None => {
+                        let obj = self.t.type_info().uses[ident];
+                        match self.tc_objs.lobjs[obj].pkg() {
+                            Some(_) => self.resolve_var_ident(ident),
+                            // error and any are declared in the universe
+                            None => {
+                                let t = self.t.get_use_tc_type(*ident);
+                                let meta = self.t.meta_from_tc(t, self.objects, self.dummy_gcv);
+                                EntIndex::TypeMeta(meta)
+                            }
+                        }
+                    }
                 }
             }
             OperandMode::Value => {
@@ -163,27 +205,52 @@ impl<'a> CodeGen<'a> {
             .func_stack
             .clone()
             .iter()
+            .enumerate()
             .skip(1) // skip package constructor
             .rev()
             .skip(1) // skip itself
-            .find_map(|ifunc| {
+            .find_map(|(i, ifunc)| {
                 let f = &mut self.objects.functions[*ifunc];
                 let index = f.entity_index(&entity_key).map(|x| *x);
                 if let Some(ind) = index {
                     let desc =
                         ValueDesc::new(*ifunc, ind.into(), self.t.get_use_value_type(*ident), true);
-                    Some(desc)
+                    Some((i, desc))
                 } else {
                     None
                 }
             });
-        if let Some(uv) = upvalue {
+        if let Some((i, uv)) = upvalue {
+            // the functions in between also capture it, so that the closures
+            // created in them can get it even if the defining frame is gone
+            // or belongs to another goroutine
+            let len = self.func_stack.len();
+            for ifunc in self.func_stack[i + 1..len - 1].iter() {
+                self.objects.functions[*ifunc].try_add_upvalue(&entity_key, uv.clone());
+            }
             let func = current_func_mut!(self);
             let index = func.try_add_upvalue(&entity_key, uv);
             return index;
         }
-        // 3. must be package member
-        EntIndex::PackageMember(self.pkg_key, (*ident).into())
+        // 3. must be package member, an instance of a generic function is
+        // a member named after its type arguments
+        let obj = self.t.get_use_object(*ident);
+        let name = self.instances.get(&obj).unwrap_or(ident);
+        EntIndex::PackageMember(self.pkg_key, (*name).into())
+    }
+
+    /// The variable p points to if *p can be compiled as it
+    fn deref_alias(&self, p: &Expr) -> Option<Expr> {
+        match Parser::unparen(p) {
+            Expr::Ident(ikey) => self
+                .t
+                .type_info()
+                .uses
+                .get(ikey)
+                .and_then(|obj| self.deref_aliases.get(obj))
+                .cloned(),
+            _ => None,
+        }
     }
 
     fn add_local_or_resolve_ident(
@@ -205,11 +272,6 @@ impl<'a> CodeGen<'a> {
             let ident_key = Some(def_ident_unique_key!(self, *ikey));
             let index = func.add_local(ident_key);
             func.add_local_zero(zero_val);
-            if func.is_ctor() {
-                let pkg_key = func.package;
-                let pkg = &mut self.objects.packages[pkg_key];
-                pkg.add_var_mapping(ident.name.clone(), index.into());
-            }
             let t = self.t.get_def_tc_type(*ikey);
             (index, Some(t), pos)
         } else {
@@ -220,10 +282,19 @@ impl<'a> CodeGen<'a> {
     }
 
     fn gen_def_var(&mut self, vs: &ValueSpec) {
+        let is_ctor = current_func!(self).is_ctor();
         let lhs = vs
             .names
             .iter()
             .map(|n| -> (LeftHandSide, Option<TCTypeKey>, usize) {
+                // package level vars are stored to the package members right away,
+                // so that the functions called by later initializers can see them
+                if is_ctor && !self.ast_objs.idents[*n].is_blank() {
+                    let index = EntIndex::PackageMember(self.pkg_key, (*n).into());
+                    let t = self.t.get_def_tc_type(*n);
+                    let pos = self.ast_objs.idents[*n].pos;
+                    return (LeftHandSide::Primitive(index), Some(t), pos);
+                }
                 let (index, t, pos) = self.add_local_or_resolve_ident(n, true);
                 (LeftHandSide::Primitive(index), t, pos)
             })
@@ -274,14 +345,8 @@ impl<'a> CodeGen<'a> {
                         let ind = &ind_expr.as_ref().index;
                         let pos = ind_expr.as_ref().l_brack;
 
-                        let mut index_const = None;
+                        let index_const = self.get_index_imm(obj, ind);
                         let mut index_typ = None;
-                        if let Some(const_val) = self.t.get_tc_const_value(ind.id()) {
-                            let (ival, _) = const_val.to_int().int_as_i64();
-                            if let Ok(i) = OpIndex::try_from(ival) {
-                                index_const = Some(i);
-                            }
-                        }
                         if index_const.is_none() {
                             self.visit_expr(ind);
                             index_typ = Some(self.t.get_expr_value_type(ind));
@@ -340,6 +405,15 @@ impl<'a> CodeGen<'a> {
                             }
                         }
                     }
+                    Expr::Star(sexpr) if self.deref_alias(&sexpr.expr).is_some() => {
+                        let ident = *self
+                            .deref_alias(&sexpr.expr)
+                            .unwrap()
+                            .try_as_ident()
+                            .unwrap();
+                        let (idx, t, _) = self.add_local_or_resolve_ident(&ident, false);
+                        (LeftHandSide::Primitive(idx), t, sexpr.star)
+                    }
                     Expr::Star(sexpr) => {
                         self.visit_expr(&sexpr.expr);
                         (
@@ -493,6 +567,10 @@ impl<'a> CodeGen<'a> {
             RightHandSide::Range(r) => {
                 // the range statement
                 self.visit_expr(r);
+                if self.t.get_expr_value_type(r) == ValueType::Named {
+                    let pos = Some(r.pos(&self.ast_objs));
+                    current_func_emitter!(self).emit_unwrap(-1, pos);
+                }
                 let tkv = self.t.get_range_tc_types(r);
                 let types = [
                     Some(self.t.value_type_from_tc(tkv[0])),
@@ -533,6 +611,11 @@ impl<'a> CodeGen<'a> {
         assert_eq!(lhs.len(), types.len());
         let total_val = types.len() as OpIndex;
         let total_stack_space = (total_lhs_stack_space + total_val) as OpIndex;
+        // the rhs index of a store is encoded in 8 bits
+        if !Instruction::in_8bit_range(-total_stack_space) {
+            self.add_error(lhs[0].2, "too many values in assignment".to_owned());
+            return None;
+        }
         let mut current_indexing_deref_index = -if lhs_on_stack_top {
             total_lhs_stack_space
         } else {
@@ -683,7 +766,33 @@ impl<'a> CodeGen<'a> {
         }
     }
 
-    fn gen_switch_body(&mut self, body: &BlockStmt, tag_type: ValueType) {
+    /// iface_case_vars returns, for each clause of a type switch, the metadata of the
+    /// implicitly declared variable if it has an interface type.
+    fn iface_case_vars(&mut self, body: &BlockStmt) -> Vec<Option<GosMetadata>> {
+        body.list
+            .iter()
+            .map(|stmt| {
+                let obj = self.t.get_implicit_object(&stmt.id());
+                let t = self.tc_objs.lobjs[obj].typ().unwrap();
+                match self.t.underlying_value_type_from_tc(t) {
+                    ValueType::Interface => {
+                        Some(self.t.meta_from_tc(t, self.objects, self.dummy_gcv))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// gen_switch_body generates the clauses of a switch statement. For a type switch
+    /// with interface typed case variables, iface_vars has the local holding the
+    /// value switched on, the case variable and the metadata of each case variable.
+    fn gen_switch_body(
+        &mut self,
+        body: &BlockStmt,
+        tag_type: ValueType,
+        iface_vars: Option<(EntIndex, EntIndex, Vec<Option<GosMetadata>>)>,
+    ) {
         let mut helper = SwitchHelper::new();
         let mut has_default = false;
         for (i, stmt) in body.list.iter().enumerate() {
@@ -716,6 +825,22 @@ impl<'a> CodeGen<'a> {
             } else {
                 helper.tags.patch_case(func, i, func.next_code_index());
             }
+            if let Some((value, var, metas)) = &iface_vars {
+                if let Some(meta) = metas[i] {
+                    // the case variable is an interface, convert the value to it
+                    let pos = Some(stmt.pos(&self.ast_objs));
+                    let mut emitter = current_func_emitter!(self);
+                    emitter.emit_load(value.clone(), None, ValueType::Interface, pos);
+                    let index = emitter.add_const(None, GosValue::Metadata(meta));
+                    emitter
+                        .f
+                        .emit_code_with_flag_imm(Opcode::TYPE_ASSERT, true, index.into(), pos);
+                    emitter.emit_pop(1, pos);
+                    let lhs = LeftHandSide::Primitive(var.clone());
+                    emitter.emit_store(&lhs, -1, None, None, ValueType::Interface, pos);
+                    emitter.emit_pop(1, pos);
+                }
+            }
             for s in cc.body.iter() {
                 self.visit_stmt(s);
             }
@@ -774,6 +899,10 @@ impl<'a> CodeGen<'a> {
         };
         self.func_stack.push(fkey);
         self.func_t_stack.push(tc_type);
+        for (p, (x, id)) in EscapeAnalysis::analyze(body, self.ast_objs, self.t.type_info()) {
+            self.deref_aliases.insert(p, x);
+            self.elided_refs.insert(id);
+        }
         // process function body
         self.visit_stmt_block(body);
 
@@ -802,12 +931,21 @@ impl<'a> CodeGen<'a> {
                     Builtin::Append => Opcode::APPEND,
                     Builtin::Copy => Opcode::COPY,
                     Builtin::Delete => Opcode::DELETE,
+                    Builtin::Clear => Opcode::CLEAR,
+                    Builtin::Min => Opcode::MIN,
+                    Builtin::Max => Opcode::MAX,
                     Builtin::Close => Opcode::CLOSE,
                     Builtin::Panic => Opcode::PANIC,
                     Builtin::Recover => Opcode::RECOVER,
                     Builtin::Assert => Opcode::ASSERT,
                     Builtin::Ffi => Opcode::FFI,
-                    _ => unimplemented!(),
+                    // print, println and trace
+                    _ => {
+                        let name = self.tc_objs.universe().builtins()[&builtin].name;
+                        let pos = func_expr.pos(&self.ast_objs);
+                        self.add_error(pos, format!("{} is not supported", name));
+                        return;
+                    }
                 };
                 for e in params.iter() {
                     self.visit_expr(e);
@@ -829,12 +967,38 @@ impl<'a> CodeGen<'a> {
                     ),
                     false => (None, None),
                 };
+                // the variadic params of APPEND are packed as the elem type,
+                // which is not the type of the last param if they were cast to interfaces
+                let param_last_t = match opcode {
+                    Opcode::APPEND if params.len() > 1 => {
+                        let t = self.t.get_expr_tc_type(&params[0]);
+                        let elem = self.tc_objs.types[self.t.underlying_tc(t)]
+                            .try_as_slice()
+                            .unwrap()
+                            .elem();
+                        match self.t.underlying_value_type_from_tc(elem) {
+                            ValueType::Interface => Some(ValueType::Interface),
+                            _ => param_last_t,
+                        }
+                    }
+                    _ => param_last_t,
+                };
                 let bf = self.tc_objs.universe().builtins()[&builtin];
                 let param_count = params.len() as OpIndex;
-                let special_case = (opcode == Opcode::APPEND || opcode == Opcode::COPY)
+                // append([]byte, string...) and copy([]byte, string)
+                let special_case = ((opcode == Opcode::APPEND && ellipsis)
+                    || opcode == Opcode::COPY)
                     && param_last_t.map_or(false, |x| x == ValueType::Str);
                 let (t_variadic, count) = match special_case {
                     true => (Some(ValueType::FlagC), Some(0)), // special case,
+                    // min, max and assert take their arguments unpacked
+                    false
+                        if opcode == Opcode::MIN
+                            || opcode == Opcode::MAX
+                            || opcode == Opcode::ASSERT =>
+                    {
+                        (Some(ValueType::FlagA), Some(param_count))
+                    }
                     false => match bf.variadic {
                         true => match ellipsis {
                             true => (Some(ValueType::FlagB), Some(0)), // do not pack params if there is ellipsis
@@ -907,6 +1071,7 @@ impl<'a> CodeGen<'a> {
                                 let iface_index = self.iface_mapping.get_index(
                                     &(tct_to, Some(tct_from)),
                                     &mut self.t,
+                                    self.tc_objs,
                                     self.objects,
                                     self.dummy_gcv,
                                 );
@@ -1032,17 +1197,44 @@ impl<'a> CodeGen<'a> {
         let t1 = self.t.get_expr_value_type(index);
         self.visit_expr(expr);
         let pos = Some(expr.pos(&self.ast_objs));
-        if let Some(const_val) = self.t.get_tc_const_value(index.id()) {
-            let (ival, _) = const_val.to_int().int_as_i64();
-            if let Ok(i) = OpIndex::try_from(ival) {
-                current_func_emitter!(self).emit_load_index_imm(i, t0, comma_ok, pos);
-                return;
-            }
+        if let Some(i) = self.get_index_imm(expr, index) {
+            current_func_emitter!(self).emit_load_index_imm(i, t0, comma_ok, pos);
+            return;
         }
         self.visit_expr(index);
         current_func_emitter!(self).emit_load_index(t0, t1, comma_ok, pos);
     }
 
+    /// Returns the index as an immediate if it's a constant that fits.
+    /// Maps are looked up with an Int key when the index is an immediate,
+    /// so maps with other key types always use the index on the stack
+    fn get_index_imm(&mut self, container: &Expr, index: &Expr) -> Option<OpIndex> {
+        let container_t = self.t.get_expr_tc_type(container);
+        if self.t.underlying_value_type_from_tc(container_t) == ValueType::Map
+            && self.t.get_expr_value_type(index) != ValueType::Int
+        {
+            return None;
+        }
+        let const_val = self.t.get_tc_const_value(index.id())?;
+        match const_val.to_int().int_as_i64() {
+            (ival, true) => OpIndex::try_from(ival).ok(),
+            _ => None,
+        }
+    }
+
+    /// Pushes the channel and the value converted to the element type
+    fn gen_send_operands(&mut self, sstmt: &SendStmt) -> ValueType {
+        self.visit_expr(&sstmt.chan);
+        self.visit_expr(&sstmt.val);
+        let chan_type = self.t.get_expr_tc_type(&sstmt.chan);
+        let elem = self.tc_objs.types[self.t.underlying_tc(chan_type)]
+            .try_as_chan()
+            .unwrap()
+            .elem();
+        let val_type = self.t.get_expr_tc_type(&sstmt.val);
+        self.try_cast_to_iface(Some(elem), Some(val_type), -1, sstmt.arrow)
+    }
+
     fn try_cast_to_iface(
         &mut self,
         lhs: Option<TCTypeKey>,
@@ -1055,10 +1247,13 @@ impl<'a> CodeGen<'a> {
             if self.t.underlying_value_type_from_tc(t0) == ValueType::Interface {
                 let to_cast_typ = match rhs {
                     Some(t1) => {
-                        let vt1 = self.t.underlying_value_type_from_tc(t1);
-                        match vt1 != ValueType::Interface && vt1 != ValueType::Nil {
-                            true => Some(self.t.value_type_from_tc(t1)),
-                            false => None,
+                        match self.t.underlying_value_type_from_tc(t1) {
+                            ValueType::Nil => None,
+                            // the methods of another interface have to be rebound
+                            ValueType::Interface if identical_ignore_tags(t0, t1, self.tc_objs) => {
+                                None
+                            }
+                            _ => Some(self.t.value_type_from_tc(t1)),
                         }
                     }
                     None => Some(ValueType::Slice), // it must be a variadic parameter
@@ -1067,6 +1262,7 @@ impl<'a> CodeGen<'a> {
                     let index = self.iface_mapping.get_index(
                         &(t0, rhs),
                         &mut self.t,
+                        self.tc_objs,
                         self.objects,
                         self.dummy_gcv,
                     );
@@ -1080,6 +1276,19 @@ impl<'a> CodeGen<'a> {
                     );
                     ret_type = Some(ValueType::Interface);
                 }
+            } else if let Some(t1) = rhs {
+                // untyped nil is a Nil value, but nil slices and maps are objects
+                let ut0 = self.t.underlying_value_type_from_tc(t0);
+                if self.t.value_type_from_tc(t1) == ValueType::Nil
+                    && (ut0 == ValueType::Slice || ut0 == ValueType::Map)
+                {
+                    let meta = self.t.meta_from_tc(t0, self.objects, self.dummy_gcv);
+                    let t = self.t.value_type_from_tc(t0);
+                    let mut emitter = current_func_emitter!(self);
+                    let index = emitter.add_const(None, GosValue::Metadata(meta));
+                    emitter.emit_cast(t, ValueType::Nil, None, rhs_index, index.into(), Some(pos));
+                    ret_type = Some(t);
+                }
             }
         }
         ret_type.unwrap_or(self.t.value_type_from_tc(rhs.unwrap()))
@@ -1101,11 +1310,9 @@ impl<'a> CodeGen<'a> {
         }
         if !ellipsis {
             if let Some(t) = variadic {
-                if self.t.underlying_value_type_from_tc(t) == ValueType::Interface {
-                    for (i, p) in param_types.iter().enumerate().skip(non_variadic_count) {
-                        let rhs_index = i as OpIndex - params.len() as OpIndex;
-                        self.try_cast_to_iface(Some(t), Some(p.0), rhs_index, p.1);
-                    }
+                for (i, p) in param_types.iter().enumerate().skip(non_variadic_count) {
+                    let rhs_index = i as OpIndex - params.len() as OpIndex;
+                    self.try_cast_to_iface(Some(t), Some(p.0), rhs_index, p.1);
                 }
             }
         }
@@ -1137,7 +1344,27 @@ impl<'a> CodeGen<'a> {
 
     fn visit_composite_expr(&mut self, expr: &Expr, tctype: TCTypeKey) {
         match expr {
-            Expr::CompositeLit(clit) => self.gen_composite_literal(clit, tctype),
+            // the type can only be elided if it's the same as the element type,
+            // otherwise, e.g. when the element type is an interface, the literal
+            // has its own type
+            Expr::CompositeLit(clit) if clit.typ.is_none() => {
+                let utype = self.tc_objs.types[tctype].underlying_val(&self.tc_objs);
+                match utype.try_as_pointer() {
+                    // &T{...} with the &T elided, as in []*T{{...}}
+                    Some(detail) => {
+                        let base = detail.base();
+                        self.gen_composite_literal(clit, base);
+                        let typ = self.t.value_type_from_tc(base);
+                        current_func_mut!(self).emit_inst(
+                            Opcode::REF_LOCAL,
+                            [Some(typ), None, None],
+                            Some(-1),
+                            Some(clit.l_brace),
+                        );
+                    }
+                    None => self.gen_composite_literal(clit, tctype),
+                }
+            }
             _ => self.visit_expr(expr),
         }
         let t = self.t.get_expr_tc_type(expr);
@@ -1286,17 +1513,38 @@ impl<'a> CodeGen<'a> {
             drop(func);
             let pkg = &mut self.objects.packages[pkg_key];
             let ident = &self.ast_objs.idents[*ikey];
-            pkg.add_member(ident.name.clone(), cst);
+            let kind = match &cst {
+                GosValue::Metadata(_) => MemberKind::Type,
+                _ => MemberKind::Const,
+            };
+            pkg.add_member(ident.name.clone(), cst, kind);
         }
         index
     }
 
+    /// A method of a generic type lists the type parameters in its receiver,
+    /// unlike the instances of it, it has no type.
+    fn is_generic_method(&self, decl: &FuncDecl) -> bool {
+        let recv = match &decl.recv {
+            Some(r) => r,
+            None => return false,
+        };
+        let typ = &self.ast_objs.fields[recv.list[0]].typ;
+        match Parser::deref(typ) {
+            Expr::Index(_) | Expr::IndexList(_) => {
+                let obj = self.t.type_info().defs[&decl.name].unwrap();
+                !self.instances.contains_key(&obj)
+            }
+            _ => false,
+        }
+    }
+
     fn add_pkg_var_member(&mut self, pkey: PackageKey, names: &Vec<IdentKey>) {
         for n in names.iter() {
             let ident = &self.ast_objs.idents[*n];
             let meta = self.t.gen_def_type_meta(*n, self.objects, self.dummy_gcv);
             let val = zero_val!(meta, self.objects, self.dummy_gcv);
-            self.objects.packages[pkey].add_member(ident.name.clone(), val);
+            self.objects.packages[pkey].add_member(ident.name.clone(), val, MemberKind::Var);
         }
     }
 
@@ -1310,6 +1558,7 @@ impl<'a> CodeGen<'a> {
         self.objects.packages[pkey].add_member(
             String::new(),
             GosValue::new_static_closure(fkey, &self.objects.functions),
+            MemberKind::Func,
         );
         self.pkg_key = pkey;
         self.func_stack.push(fkey);
@@ -1324,6 +1573,9 @@ impl<'a> CodeGen<'a> {
                 self.visit_decl(d)
             }
         }
+        for f in self.t.type_info().instances.clone().iter() {
+            self.visit_stmt_decl_func(f);
+        }
         for v in vars.iter() {
             self.gen_def_var(v);
         }
@@ -1497,9 +1749,18 @@ impl<'a> ExprVisitor for CodeGen<'a> {
     }
 
     fn visit_expr_index(&mut self, _: &Expr, expr: &Expr, index: &Expr) {
+        // the instantiation of a generic function
+        let tc_type = self.t.get_expr_tc_type(expr);
+        if let Type::Signature(_) = &self.tc_objs.types[tc_type] {
+            return self.visit_expr(expr);
+        }
         self.gen_map_index(expr, index, false);
     }
 
+    fn visit_expr_index_list(&mut self, _: &Expr, expr: &Expr, _: &[Expr]) {
+        self.visit_expr(expr);
+    }
+
     fn visit_expr_slice(
         &mut self,
         _: &Expr,
@@ -1515,17 +1776,25 @@ impl<'a> ExprVisitor for CodeGen<'a> {
             None => current_func_emitter!(self).emit_push_imm(ValueType::Int, 0, pos),
             Some(e) => self.visit_expr(e),
         }
-        match high {
-            None => current_func_emitter!(self).emit_push_imm(ValueType::Int, -1, pos),
-            Some(e) => self.visit_expr(e),
-        }
-        match max {
-            None => current_func_mut!(self).emit_code_with_type(Opcode::SLICE, t, pos),
+        // FlagA tells the VM that high is omitted and the pushed -1 means len
+        let t1 = match high {
+            None => {
+                current_func_emitter!(self).emit_push_imm(ValueType::Int, -1, pos);
+                Some(ValueType::FlagA)
+            }
             Some(e) => {
                 self.visit_expr(e);
-                current_func_mut!(self).emit_code_with_type(Opcode::SLICE_FULL, t, pos);
+                None
             }
-        }
+        };
+        let op = match max {
+            None => Opcode::SLICE,
+            Some(e) => {
+                self.visit_expr(e);
+                Opcode::SLICE_FULL
+            }
+        };
+        current_func_mut!(self).emit_inst(op, [Some(t), t1, None], None, pos);
     }
 
     fn visit_expr_type_assert(&mut self, _: &Expr, expr: &Expr, typ: &Option<Expr>) {
@@ -1547,16 +1816,24 @@ impl<'a> ExprVisitor for CodeGen<'a> {
                 let mut emitter = current_func_emitter!(self);
                 emitter.emit_load(EntIndex::TypeMeta(m), None, ValueType::Metadata, pos);
             }
-            _ => {
-                self.visit_expr(expr);
-                let t = self.t.get_expr_value_type(expr);
-                current_func_mut!(self).emit_code_with_type(Opcode::DEREF, t, pos);
-            }
+            _ => match self.deref_alias(expr) {
+                Some(x) => self.visit_expr(&x),
+                None => {
+                    self.visit_expr(expr);
+                    let t = self.t.get_expr_value_type(expr);
+                    current_func_mut!(self).emit_code_with_type(Opcode::DEREF, t, pos);
+                }
+            },
         }
     }
 
     fn visit_expr_unary(&mut self, this: &Expr, expr: &Expr, op: &Token) {
         let pos = Some(expr.pos(&self.ast_objs));
+        if op == &Token::AND && self.elided_refs.contains(&this.id()) {
+            // the pointer is never read, see deref_alias
+            current_func_mut!(self).emit_code(Opcode::PUSH_NIL, pos);
+            return;
+        }
         if op == &Token::AND {
             match expr {
                 Expr::Ident(ikey) => {
@@ -1683,10 +1960,11 @@ impl<'a> ExprVisitor for CodeGen<'a> {
                         pos,
                     );
                 }
-                _ => {
-                    dbg!(&expr);
-                    unimplemented!()
-                }
+                Expr::Paren(pexpr) => self.visit_expr_unary(this, &pexpr.expr, op),
+                // &*p is p
+                Expr::Star(sexpr) => self.visit_expr(&sexpr.expr),
+                // the operand is checked to be addressable
+                _ => unreachable!(),
             }
             return;
         }
@@ -1767,6 +2045,24 @@ impl<'a> ExprVisitor for CodeGen<'a> {
             } else {
                 (t0, t0_inner)
             };
+            // comparing an interface with a non-interface value, convert the
+            // latter to the interface type first
+            let mut t0 = t0;
+            if code == Opcode::EQL || code == Opcode::NEQ {
+                let (lt, rt) = (
+                    self.t.get_expr_tc_type(left),
+                    self.t.get_expr_tc_type(right),
+                );
+                let lvt = self.t.underlying_value_type_from_tc(lt);
+                let rvt = self.t.underlying_value_type_from_tc(rt);
+                let is_value = |t: ValueType| t != ValueType::Interface && t != ValueType::Nil;
+                if lvt == ValueType::Interface && is_value(rvt) {
+                    self.try_cast_to_iface(Some(lt), Some(rt), -1, pos.unwrap());
+                } else if rvt == ValueType::Interface && is_value(lvt) {
+                    self.try_cast_to_iface(Some(rt), Some(lt), -2, pos.unwrap());
+                    t0 = ValueType::Interface;
+                }
+            }
             let mut emitter = current_func_emitter!(self);
             if compare {
                 // don't unwrap named operands of comparisons
@@ -1777,9 +2073,8 @@ impl<'a> ExprVisitor for CodeGen<'a> {
         }
     }
 
-    fn visit_expr_key_value(&mut self, e: &Expr, _key: &Expr, _val: &Expr) {
-        dbg!(e);
-        unimplemented!();
+    fn visit_expr_key_value(&mut self, _e: &Expr, _key: &Expr, _val: &Expr) {
+        unreachable!(); // handled in visit_expr_composit_lit
     }
 
     fn visit_expr_array_type(&mut self, this: &Expr, _: &Option<Expr>, _: &Expr) {
@@ -1807,7 +2102,7 @@ impl<'a> ExprVisitor for CodeGen<'a> {
     }
 
     fn visit_bad_expr(&mut self, _: &Expr, _e: &BadExpr) {
-        unreachable!();
+        unreachable!(); // there is no code generation if there are parse errors
     }
 }
 
@@ -1829,6 +2124,8 @@ impl<'a> StmtVisitor for CodeGen<'a> {
                 Spec::Import(_) => {
                     //handled elsewhere
                 }
+                // generic types are generated per instance
+                Spec::Type(ts) if ts.type_params.is_some() => {}
                 Spec::Type(ts) => {
                     let m = self
                         .t
@@ -1855,6 +2152,10 @@ impl<'a> StmtVisitor for CodeGen<'a> {
             return;
             // unimplemented!()
         }
+        // generic functions and methods of generic types are generated per instance
+        if self.ast_objs.ftypes[decl.typ].type_params.is_some() || self.is_generic_method(decl) {
+            return;
+        }
         let tc_type = self.t.get_def_tc_type(decl.name);
         let stmt = decl.body.as_ref().unwrap();
         let fkey = self.gen_func_def(tc_type, decl.typ, decl.recv.clone(), stmt);
@@ -1873,7 +2174,7 @@ impl<'a> StmtVisitor for CodeGen<'a> {
             match name.as_str() {
                 "init" => pkg.add_init_func(cls),
                 _ => {
-                    pkg.add_member(name.clone(), cls);
+                    pkg.add_member(name.clone(), cls, MemberKind::Func);
                 }
             };
         }
@@ -1892,9 +2193,7 @@ impl<'a> StmtVisitor for CodeGen<'a> {
     }
 
     fn visit_stmt_send(&mut self, sstmt: &SendStmt) {
-        self.visit_expr(&sstmt.chan);
-        self.visit_expr(&sstmt.val);
-        let t = self.t.get_expr_value_type(&sstmt.val);
+        let t = self.gen_send_operands(sstmt);
         current_func_mut!(self).emit_code_with_type(Opcode::SEND, t, Some(sstmt.arrow));
     }
 
@@ -2063,7 +2362,7 @@ impl<'a> StmtVisitor for CodeGen<'a> {
             }
         };
 
-        self.gen_switch_body(&*sstmt.body, tag_type);
+        self.gen_switch_body(&*sstmt.body, tag_type, None);
 
         self.branch_helper
             .leave_block(current_func_mut!(self), None);
@@ -2087,6 +2386,7 @@ impl<'a> StmtVisitor for CodeGen<'a> {
             _ => unreachable!(),
         };
 
+        let mut iface_vars = None;
         if let Some(_) = ident_expr {
             let implicit_entities = tstmt
                 .body
@@ -2097,22 +2397,39 @@ impl<'a> StmtVisitor for CodeGen<'a> {
                     KeyData::from(obj)
                 })
                 .collect();
+            let metas = self.iface_case_vars(&*tstmt.body);
             let func = current_func_mut!(self);
             let index = func.add_implicit_local(implicit_entities);
             func.add_local_zero(GosValue::new_nil());
+            // keep the interface value for the cases that need it
+            let value = match metas.iter().any(|x| x.is_some()) {
+                true => {
+                    let value = func.add_local(None);
+                    func.add_local_zero(GosValue::new_nil());
+                    Some(value)
+                }
+                false => None,
+            };
             self.visit_expr(v);
-            let func = current_func_mut!(self);
-            func.emit_code_with_flag_imm(Opcode::TYPE, true, index.into(), pos);
+            let mut emitter = current_func_emitter!(self);
+            if let Some(value) = &value {
+                let lhs = LeftHandSide::Primitive(value.clone());
+                emitter.emit_store(&lhs, -1, None, None, ValueType::Interface, pos);
+            }
+            emitter
+                .f
+                .emit_code_with_flag_imm(Opcode::TYPE, true, index.into(), pos);
+            iface_vars = value.map(|v| (v, index, metas));
         } else {
             self.visit_expr(v);
             current_func_mut!(self).emit_code(Opcode::TYPE, pos);
         }
 
-        self.gen_switch_body(&*tstmt.body, ValueType::Metadata);
+        self.gen_switch_body(&*tstmt.body, ValueType::Metadata, iface_vars);
     }
 
     fn visit_stmt_comm(&mut self, _cclause: &CommClause) {
-        unimplemented!();
+        unreachable!(); // handled in visit_stmt_select
     }
 
     fn visit_stmt_select(&mut self, sstmt: &SelectStmt) {
@@ -2153,9 +2470,7 @@ impl<'a> StmtVisitor for CodeGen<'a> {
             let (typ, pos) = match &c.comm {
                 Some(comm) => match comm {
                     Stmt::Send(send_stmt) => {
-                        self.visit_expr(&send_stmt.chan);
-                        self.visit_expr(&send_stmt.val);
-                        let t = self.t.get_expr_value_type(&send_stmt.val);
+                        let t = self.gen_send_operands(send_stmt);
                         (CommType::Send(t), send_stmt.arrow)
                     }
                     Stmt::Assign(ass_key) => {
@@ -2289,10 +2604,10 @@ impl<'a> StmtVisitor for CodeGen<'a> {
     fn visit_empty_stmt(&mut self, _e: &EmptyStmt) {}
 
     fn visit_bad_stmt(&mut self, _b: &BadStmt) {
-        unreachable!();
+        unreachable!(); // there is no code generation if there are parse errors
     }
 
     fn visit_bad_decl(&mut self, _b: &BadDecl) {
-        unreachable!();
+        unreachable!(); // there is no code generation if there are parse errors
     }
 }
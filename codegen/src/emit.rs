@@ -452,6 +452,12 @@ impl<'a> Emitter<'a> {
         t1_inner: Option<ValueType>,
         pos: Option<usize>,
     ) {
+        // named strings are not copyable, they can't be unwrapped and
+        // wrapped again in place, the VM adds them as they are
+        let (t0_inner, t1_inner) = match t0_inner {
+            Some(ValueType::Str) => (None, None),
+            _ => (t0_inner, t1_inner),
+        };
         let t1 = match t1_inner {
             Some(t) => {
                 self.emit_unwrap(-1, pos);
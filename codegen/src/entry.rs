@@ -24,6 +24,8 @@ pub struct EntryGen<'a> {
     objects: Pin<Box<VMObjects>>,
     ast_objs: &'a AstObjects,
     tc_objs: &'a TCObjects,
+    fset: &'a FileSet,
+    errors: &'a ErrorList,
     dummy_gcv: GcoVec,
     packages: Vec<PackageKey>,
     iface_mapping: IfaceMapping,
@@ -33,11 +35,19 @@ pub struct EntryGen<'a> {
 }
 
 impl<'a> EntryGen<'a> {
-    pub fn new(asto: &'a AstObjects, tco: &'a TCObjects, bk: IdentKey) -> EntryGen<'a> {
+    pub fn new(
+        asto: &'a AstObjects,
+        tco: &'a TCObjects,
+        fset: &'a FileSet,
+        el: &'a ErrorList,
+        bk: IdentKey,
+    ) -> EntryGen<'a> {
         EntryGen {
             objects: Box::pin(VMObjects::new()),
             ast_objs: asto,
             tc_objs: tco,
+            fset: fset,
+            errors: el,
             dummy_gcv: GcoVec::new(),
             packages: Vec::new(),
             iface_mapping: IfaceMapping::new(),
@@ -84,7 +94,7 @@ impl<'a> EntryGen<'a> {
         checker_result: &HashMap<TCPackageKey, TypeInfo>,
         main_pkg: TCPackageKey,
         main_ident: IdentKey,
-    ) -> ByteCode {
+    ) -> Result<ByteCode, usize> {
         let mut main_pkg_idx = None;
         for (&tcpkg, _) in checker_result.iter() {
             // create vm packages and store the indices
@@ -107,6 +117,8 @@ impl<'a> EntryGen<'a> {
                 self.ast_objs,
                 self.tc_objs,
                 &mut self.dummy_gcv,
+                self.fset,
+                self.errors,
                 &ti,
                 &mut type_cache,
                 &mut self.iface_mapping,
@@ -120,6 +132,9 @@ impl<'a> EntryGen<'a> {
             cgen.gen_with_files(&ti.ast_files, *tcpkg, i as OpIndex);
             pkg_pairs.append_from_util(cgen.pkg_helper());
         }
+        if self.errors.len() > 0 {
+            return Err(self.errors.len());
+        }
         let index = main_pkg_idx.unwrap();
         let entry = self.gen_entry_func(
             self.packages[index as usize],
@@ -130,12 +145,12 @@ impl<'a> EntryGen<'a> {
         pkg_pairs.patch_index(self.ast_objs, &mut self.objects);
         call_helper.patch_call(&mut self.objects);
         branch_helper.patch_go_tos(&mut self.objects.functions);
-        ByteCode::new(
+        Ok(ByteCode::new(
             self.objects,
             self.packages,
             self.iface_mapping.result(),
             entry,
-        )
+        ))
     }
 }
 
@@ -160,7 +175,7 @@ pub fn parse_check_gen(
     } else {
         let blank_ident = asto.idents.insert(Ident::blank(0));
         let main_ident = asto.idents.insert(Ident::with_str(0, "main"));
-        let gen = EntryGen::new(asto, tco, blank_ident);
-        Ok(gen.gen(results, main_pkg.unwrap(), main_ident))
+        let gen = EntryGen::new(asto, tco, fset, el, blank_ident);
+        gen.gen(results, main_pkg.unwrap(), main_ident)
     }
 }
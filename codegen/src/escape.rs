@@ -0,0 +1,268 @@
+/// A conservative escape analysis for the pointers to variables
+///
+/// Taking the address of a local variable boxes it into an upvalue, which is
+/// allocated for every call of the function. A pointer variable defined as
+/// `p := &x` or `var p = &x` doesn't let the address escape if all it's ever
+/// used for is `*p` in the function that defines it, i.e. it's not reassigned,
+/// not passed, stored or returned, and not captured by a closure. Such a `*p`
+/// is just another name of x, codegen reads and writes x directly instead, and
+/// the address of x is never taken for p.
+///
+use goscript_parser::ast::*;
+use goscript_parser::objects::Objects as AstObjects;
+use goscript_parser::token::Token;
+use goscript_types::{ObjKey as TCObjKey, TypeInfo};
+use std::collections::{HashMap, HashSet};
+
+pub struct EscapeAnalysis<'a> {
+    ast_objs: &'a AstObjects,
+    ti: &'a TypeInfo,
+    // pointer variables defined as &x, and x and &x
+    candidates: HashMap<TCObjKey, (Expr, NodeId)>,
+    escaped: HashSet<TCObjKey>,
+    // the depth of the function literals being walked
+    closure_depth: usize,
+}
+
+impl<'a> EscapeAnalysis<'a> {
+    /// Returns the pointer variables defined in the body whose address
+    /// doesn't escape, mapped to the x they point to and the id of the &x
+    /// they are defined with.
+    pub fn analyze(
+        body: &BlockStmt,
+        ast_objs: &'a AstObjects,
+        ti: &'a TypeInfo,
+    ) -> HashMap<TCObjKey, (Expr, NodeId)> {
+        let mut ea = EscapeAnalysis {
+            ast_objs: ast_objs,
+            ti: ti,
+            candidates: HashMap::new(),
+            escaped: HashSet::new(),
+            closure_depth: 0,
+        };
+        ea.walk_stmts(&body.list);
+        let escaped = ea.escaped;
+        ea.candidates
+            .into_iter()
+            .filter(|(k, _)| !escaped.contains(k))
+            .collect()
+    }
+
+    /// Records name as a candidate if it's defined as &x
+    fn try_add_candidate(&mut self, name: &Expr, val: &Expr) -> bool {
+        if self.closure_depth > 0 {
+            return false;
+        }
+        let obj = match name {
+            Expr::Ident(ikey) => match self.ti.defs.get(ikey) {
+                Some(Some(obj)) => *obj,
+                _ => return false,
+            },
+            _ => return false,
+        };
+        let ref_expr = strip_paren(val);
+        match ref_expr {
+            Expr::Unary(uexpr) if uexpr.op == Token::AND => match strip_paren(&uexpr.expr) {
+                x @ Expr::Ident(_) => {
+                    self.candidates.insert(obj, (x.clone(), ref_expr.id()));
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn walk_stmts(&mut self, stmts: &Vec<Stmt>) {
+        for s in stmts.iter() {
+            self.walk_stmt(s);
+        }
+    }
+
+    fn walk_opt_stmt(&mut self, stmt: &Option<Stmt>) {
+        if let Some(s) = stmt {
+            self.walk_stmt(s);
+        }
+    }
+
+    fn walk_opt_expr(&mut self, expr: &Option<Expr>) {
+        if let Some(e) = expr {
+            self.walk_expr(e);
+        }
+    }
+
+    fn walk_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Decl(decl) => {
+                if let Decl::Gen(gdecl) = decl.as_ref() {
+                    if gdecl.token == Token::VAR {
+                        for skey in gdecl.specs.iter() {
+                            if let Spec::Value(vspec) = &self.ast_objs.specs[*skey] {
+                                self.walk_value_spec(vspec);
+                            }
+                        }
+                    }
+                }
+            }
+            Stmt::Labeled(lkey) => {
+                let lstmt = &self.ast_objs.l_stmts[*lkey];
+                self.walk_stmt(&lstmt.stmt);
+            }
+            Stmt::Expr(expr) => self.walk_expr(expr),
+            Stmt::Send(sstmt) => {
+                self.walk_expr(&sstmt.chan);
+                self.walk_expr(&sstmt.val);
+            }
+            Stmt::IncDec(idstmt) => self.walk_expr(&idstmt.expr),
+            Stmt::Assign(akey) => {
+                let astmt = &self.ast_objs.a_stmts[*akey];
+                let pairs = astmt.token == Token::DEFINE && astmt.lhs.len() == astmt.rhs.len();
+                for (i, l) in astmt.lhs.iter().enumerate() {
+                    if !(pairs && self.try_add_candidate(l, &astmt.rhs[i])) {
+                        self.walk_expr(l);
+                        if pairs {
+                            self.walk_expr(&astmt.rhs[i]);
+                        }
+                    }
+                }
+                if !pairs {
+                    self.walk_exprs(&astmt.rhs);
+                }
+            }
+            Stmt::Go(gostmt) => self.walk_expr(&gostmt.call),
+            Stmt::Defer(dstmt) => self.walk_expr(&dstmt.call),
+            Stmt::Return(rstmt) => self.walk_exprs(&rstmt.results),
+            Stmt::Block(bstmt) => self.walk_stmts(&bstmt.list),
+            Stmt::If(ifstmt) => {
+                self.walk_opt_stmt(&ifstmt.init);
+                self.walk_expr(&ifstmt.cond);
+                self.walk_stmts(&ifstmt.body.list);
+                self.walk_opt_stmt(&ifstmt.els);
+            }
+            Stmt::Case(cclause) => {
+                if let Some(list) = &cclause.list {
+                    self.walk_exprs(list);
+                }
+                self.walk_stmts(&cclause.body);
+            }
+            Stmt::Switch(sstmt) => {
+                self.walk_opt_stmt(&sstmt.init);
+                self.walk_opt_expr(&sstmt.tag);
+                self.walk_stmts(&sstmt.body.list);
+            }
+            Stmt::TypeSwitch(tstmt) => {
+                self.walk_opt_stmt(&tstmt.init);
+                self.walk_stmt(&tstmt.assign);
+                self.walk_stmts(&tstmt.body.list);
+            }
+            Stmt::Comm(cclause) => {
+                self.walk_opt_stmt(&cclause.comm);
+                self.walk_stmts(&cclause.body);
+            }
+            Stmt::Select(sstmt) => self.walk_stmts(&sstmt.body.list),
+            Stmt::For(fstmt) => {
+                self.walk_opt_stmt(&fstmt.init);
+                self.walk_opt_expr(&fstmt.cond);
+                self.walk_opt_stmt(&fstmt.post);
+                self.walk_stmts(&fstmt.body.list);
+            }
+            Stmt::Range(rstmt) => {
+                self.walk_opt_expr(&rstmt.key);
+                self.walk_opt_expr(&rstmt.val);
+                self.walk_expr(&rstmt.expr);
+                self.walk_stmts(&rstmt.body.list);
+            }
+            Stmt::Bad(_) | Stmt::Empty(_) | Stmt::Branch(_) => {}
+        }
+    }
+
+    fn walk_value_spec(&mut self, vspec: &ValueSpec) {
+        if vspec.names.len() == vspec.values.len() {
+            for (name, val) in vspec.names.iter().zip(vspec.values.iter()) {
+                if !self.try_add_candidate(&Expr::Ident(*name), val) {
+                    self.walk_expr(val);
+                }
+            }
+        } else {
+            self.walk_exprs(&vspec.values);
+        }
+    }
+
+    fn walk_exprs(&mut self, exprs: &Vec<Expr>) {
+        for e in exprs.iter() {
+            self.walk_expr(e);
+        }
+    }
+
+    fn walk_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Ident(ikey) => {
+                // any use other than *p lets the address escape
+                if let Some(obj) = self.ti.uses.get(ikey) {
+                    self.escaped.insert(*obj);
+                }
+            }
+            Expr::Star(sexpr) => match strip_paren(&sexpr.expr) {
+                Expr::Ident(_) if self.closure_depth == 0 => {}
+                e => self.walk_expr(e),
+            },
+            Expr::Unary(uexpr) => match strip_paren(&uexpr.expr) {
+                // &*p is p
+                Expr::Star(sexpr) if uexpr.op == Token::AND => self.walk_expr(&sexpr.expr),
+                e => self.walk_expr(e),
+            },
+            Expr::FuncLit(flit) => {
+                self.closure_depth += 1;
+                self.walk_stmts(&flit.body.list);
+                self.closure_depth -= 1;
+            }
+            Expr::CompositeLit(clit) => self.walk_exprs(&clit.elts),
+            Expr::Paren(pexpr) => self.walk_expr(&pexpr.expr),
+            Expr::Selector(sexpr) => self.walk_expr(&sexpr.expr),
+            Expr::Index(iexpr) => {
+                self.walk_expr(&iexpr.expr);
+                self.walk_expr(&iexpr.index);
+            }
+            Expr::IndexList(iexpr) => {
+                self.walk_expr(&iexpr.expr);
+                self.walk_exprs(&iexpr.indices);
+            }
+            Expr::Slice(sexpr) => {
+                self.walk_expr(&sexpr.expr);
+                self.walk_opt_expr(&sexpr.low);
+                self.walk_opt_expr(&sexpr.high);
+                self.walk_opt_expr(&sexpr.max);
+            }
+            Expr::TypeAssert(texpr) => self.walk_expr(&texpr.expr),
+            Expr::Call(cexpr) => {
+                self.walk_expr(&cexpr.func);
+                self.walk_exprs(&cexpr.args);
+            }
+            Expr::Binary(bexpr) => {
+                self.walk_expr(&bexpr.expr_a);
+                self.walk_expr(&bexpr.expr_b);
+            }
+            Expr::KeyValue(kvexpr) => {
+                self.walk_expr(&kvexpr.key);
+                self.walk_expr(&kvexpr.val);
+            }
+            // types and literals don't use variables
+            Expr::Bad(_)
+            | Expr::Ellipsis(_)
+            | Expr::BasicLit(_)
+            | Expr::Array(_)
+            | Expr::Struct(_)
+            | Expr::Func(_)
+            | Expr::Interface(_)
+            | Expr::Map(_)
+            | Expr::Chan(_) => {}
+        }
+    }
+}
+
+fn strip_paren(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(pexpr) => strip_paren(&pexpr.expr),
+        _ => expr,
+    }
+}
@@ -1,5 +1,5 @@
 use super::types::TypeLookup;
-use goscript_types::TypeKey as TCTypeKey;
+use goscript_types::{identical, TCObjects, TypeKey as TCTypeKey};
 use goscript_vm::gc::GcoVec;
 use goscript_vm::instruction::*;
 use goscript_vm::metadata::*;
@@ -9,6 +9,11 @@ use std::collections::HashMap;
 pub struct IfaceMapping {
     ifaces: Vec<(GosMetadata, Option<Vec<IfaceBinding>>)>,
     iface_indices: HashMap<(TCTypeKey, Option<TCTypeKey>), OpIndex>,
+    // the checker creates a new type for every occurrence of an unnamed type
+    // like *T or interface{}, they are mapped to the first identical one so
+    // that the same conversion shares the same entry
+    canonical: HashMap<TCTypeKey, TCTypeKey>,
+    canonical_keys: Vec<TCTypeKey>,
 }
 
 impl IfaceMapping {
@@ -16,6 +21,8 @@ impl IfaceMapping {
         IfaceMapping {
             ifaces: vec![],
             iface_indices: HashMap::new(),
+            canonical: HashMap::new(),
+            canonical_keys: vec![],
         }
     }
 
@@ -27,19 +34,50 @@ impl IfaceMapping {
         &mut self,
         i_s: &(TCTypeKey, Option<TCTypeKey>),
         lookup: &mut TypeLookup,
+        tc_objs: &TCObjects,
         objs: &mut VMObjects,
         dummy_gcv: &mut GcoVec,
     ) -> OpIndex {
         if let Some(i) = self.iface_indices.get(i_s) {
             return *i;
         }
-        let mapping = IfaceMapping::get_binding_info(i_s, lookup, objs, dummy_gcv);
-        let index = self.ifaces.len() as OpIndex;
-        self.ifaces.push(mapping);
+        let key = (
+            self.canonical_key(i_s.0, tc_objs),
+            i_s.1.map(|t| self.canonical_key(t, tc_objs)),
+        );
+        let index = match self.iface_indices.get(&key) {
+            Some(i) => *i,
+            None => {
+                let mapping = IfaceMapping::get_binding_info(&key, lookup, objs, dummy_gcv);
+                let index = self.ifaces.len() as OpIndex;
+                self.ifaces.push(mapping);
+                self.iface_indices.insert(key, index);
+                index
+            }
+        };
         self.iface_indices.insert(*i_s, index);
         index
     }
 
+    fn canonical_key(&mut self, t: TCTypeKey, tc_objs: &TCObjects) -> TCTypeKey {
+        if let Some(c) = self.canonical.get(&t) {
+            return *c;
+        }
+        let c = match self
+            .canonical_keys
+            .iter()
+            .find(|k| identical(**k, t, tc_objs))
+        {
+            Some(k) => *k,
+            None => {
+                self.canonical_keys.push(t);
+                t
+            }
+        };
+        self.canonical.insert(t, c);
+        c
+    }
+
     fn get_binding_info(
         i_s: &(TCTypeKey, Option<TCTypeKey>),
         lookup: &mut TypeLookup,
@@ -1,6 +1,7 @@
 mod branch;
 mod call;
 mod emit;
+mod escape;
 mod interface;
 mod package;
 mod types;
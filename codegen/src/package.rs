@@ -122,8 +122,7 @@ impl<'a> PkgHelper<'a> {
         let mut orders = HashMap::new();
         for (i, init) in ti.init_order.iter().enumerate() {
             for okey in init.lhs.iter() {
-                let name = self.tc_objs.lobjs[*okey].name();
-                orders.insert(name, i);
+                orders.insert(*okey, i);
             }
         }
 
@@ -139,8 +138,10 @@ impl<'a> PkgHelper<'a> {
                                 match spec {
                                     Spec::Value(v) => {
                                         names.extend(v.names.iter());
-                                        let name = &self.ast_objs.idents[v.names[0]].name;
-                                        if let Some(order) = orders.get(name) {
+                                        // keyed by object rather than by name, as there
+                                        // may be multiple blank vars
+                                        let okey = ti.defs[&v.names[0]].unwrap();
+                                        if let Some(order) = orders.get(&okey) {
                                             decls.push((v.clone(), order));
                                         }
                                     }
@@ -241,7 +241,7 @@ impl<'a> TypeLookup<'a> {
     #[inline]
     pub fn get_range_tc_types(&mut self, e: &Expr) -> [TCTypeKey; 3] {
         let typ = self.ti.types.get(&e.id()).unwrap().typ;
-        self.range_tc_types(typ)
+        self.range_tc_types(self.underlying_tc(typ))
     }
 
     #[inline]
@@ -336,7 +336,8 @@ impl<'a> TypeLookup<'a> {
                     vm_objs.metadata.mint32
                 }
                 BasicType::Int64 => vm_objs.metadata.mint64,
-                BasicType::Uint | BasicType::Uintptr => vm_objs.metadata.muint,
+                BasicType::Uint => vm_objs.metadata.muint,
+                BasicType::Uintptr => vm_objs.metadata.muint_ptr,
                 BasicType::Uint8 | BasicType::Byte => vm_objs.metadata.muint8,
                 BasicType::Uint16 => vm_objs.metadata.muint16,
                 BasicType::Uint32 => vm_objs.metadata.muint32,
@@ -444,7 +445,7 @@ impl<'a> TypeLookup<'a> {
         match &self.tc_objs.types[typ] {
             Type::Basic(_) => self.basic_type_from_tc(typ, vm_objs).unwrap(),
             Type::Array(detail) => {
-                let elem = self.meta_from_tc_impl(detail.elem(), vm_objs, dummy_gcv);
+                let elem = self.meta_from_tc(detail.elem(), vm_objs, dummy_gcv);
                 GosMetadata::new_array(elem, detail.len().unwrap() as usize, &mut vm_objs.metas)
             }
             Type::Slice(detail) => {
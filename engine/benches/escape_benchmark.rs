@@ -0,0 +1,60 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+extern crate goscript_engine as engine;
+
+// counts the allocations to show what the escape analysis saves
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn run(path: &str) -> usize {
+    let cfg = engine::Config {
+        work_dir: Some("./".to_owned()),
+        base_path: Some("../std/".to_owned()),
+        trace_parser: false,
+        trace_checker: false,
+        trace_vm: true,
+        ..Default::default()
+    };
+    let mut engine = engine::Engine::new(cfg);
+    let err_cnt = engine.run(path);
+    assert!(err_cnt == 0);
+    err_cnt
+}
+
+fn allocations(path: &str) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    run(path);
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let local = "./tests/demo/escape_local.gos";
+    let boxed = "./tests/demo/escape_boxed.gos";
+    println!(
+        "allocations: escape_local {}, escape_boxed {}",
+        allocations(local),
+        allocations(boxed)
+    );
+    c.bench_function("escape_local", |b| b.iter(|| run(local)));
+    c.bench_function("escape_boxed", |b| b.iter(|| run(boxed)));
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -9,6 +9,7 @@ fn run(path: &str, trace: bool) -> usize {
         trace_parser: trace,
         trace_checker: trace,
         trace_vm: true,
+        ..Default::default()
     };
     let mut engine = engine::Engine::new(cfg);
     engine.run(path)
@@ -0,0 +1,30 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+extern crate goscript_engine as engine;
+
+fn run(path: &str) -> usize {
+    let cfg = engine::Config {
+        work_dir: Some("./".to_owned()),
+        base_path: Some("../std/".to_owned()),
+        trace_parser: false,
+        trace_checker: false,
+        trace_vm: true,
+        ..Default::default()
+    };
+    let mut engine = engine::Engine::new(cfg);
+    let err_cnt = engine.run(path);
+    assert!(err_cnt == 0);
+    err_cnt
+}
+
+// building a string with += takes linear time, with s = s + ... quadratic
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("str_append");
+    group.sample_size(10);
+    group.bench_function("append", |b| b.iter(|| run("./tests/demo/str_append.gos")));
+    group.bench_function("concat", |b| b.iter(|| run("./tests/demo/str_concat.gos")));
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -2,7 +2,12 @@ extern crate goscript_codegen as cg;
 extern crate goscript_parser as fe;
 extern crate goscript_types as types;
 extern crate goscript_vm as vm;
-use super::std::{bits, fmt2, reflect, sync};
+use super::std::{atomic, bits, fmt2, host, list, reflect, runtime, strings, sync, time};
+use std::collections::HashMap;
+use vm::metadata::{GosMetadata, Metadata};
+use vm::objects::{MemberKind, PackageVal};
+use vm::value::{GosValue, RuntimeResult};
+use vm::vm::{Coroutine, ScriptError};
 
 pub struct Config {
     // working directory
@@ -15,11 +20,147 @@ pub struct Config {
     pub trace_checker: bool,
     // proint debug info for vm
     pub trace_vm: bool,
+    // false: returns when main returns, like Go, other goroutines are abandoned
+    // true: returns when all goroutines are finished or blocked
+    pub wait_for_goroutines: bool,
+    // the number of instructions a run or a call can execute before it's
+    // aborted with ScriptError::Budget, unlimited if None
+    pub instruction_budget: Option<u64>,
+    // the number of heap objects (slices, arrays, maps, structs and closures)
+    // that can be alive at the same time before the program is aborted with
+    // ScriptError::Memory, unlimited if None
+    pub max_heap_objects: Option<usize>,
+    // the number of nested calls a goroutine can make before a stack overflow
+    // panic is raised, which can be recovered, unlimited if None
+    pub max_call_depth: Option<usize>,
+}
+
+impl Default for Config {
+    /// No paths and no tracing, the run waits for all goroutines and has no
+    /// limits, which is how the engine behaved before these options existed
+    fn default() -> Config {
+        Config {
+            work_dir: None,
+            base_path: None,
+            trace_parser: false,
+            trace_checker: false,
+            trace_vm: false,
+            wait_for_goroutines: true,
+            instruction_budget: None,
+            max_heap_objects: None,
+            max_call_depth: None,
+        }
+    }
+}
+
+/// How serious a diagnostic is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The program can't be compiled
+    Error,
+    /// An error the type checker goes on after, like an unused variable,
+    /// the program can't be compiled either
+    SoftError,
+}
+
+/// A problem found in the program by the parser, the type checker or the
+/// code generator
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub filename: String,
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub msg: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.filename, self.line, self.column, self.msg
+        )
+    }
+}
+
+/// The reason a program failed to compile, the diagnostics are in the order
+/// they are found in the source
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompileError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CompileError {
+    fn new(el: &fe::errors::ErrorList) -> CompileError {
+        CompileError {
+            diagnostics: diagnostics(el),
+        }
+    }
+}
+
+fn diagnostics(el: &fe::errors::ErrorList) -> Vec<Diagnostic> {
+    el.borrow()
+        .iter()
+        .map(|e| Diagnostic {
+            filename: e.pos.filename.to_string(),
+            line: e.pos.line,
+            column: e.pos.column,
+            severity: match e.soft {
+                true => Severity::SoftError,
+                false => Severity::Error,
+            },
+            msg: e.msg.clone(),
+        })
+        .collect()
+}
+
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for d in self.diagnostics.iter() {
+            writeln!(f, "{}", d)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// The result of parsing and type checking a program without generating
+/// code, for tools like linters. Positions in the diagnostics and the AST
+/// are resolved with fset.
+pub struct AnalysisResult {
+    pub fset: fe::FileSet,
+    pub ast_objs: fe::objects::Objects,
+    pub tc_objs: types::TCObjects,
+    // the type info of the main package and all the packages it imports
+    pub packages: HashMap<types::PackageKey, types::TypeInfo>,
+    // None if the main package couldn't be parsed
+    pub main_pkg: Option<types::PackageKey>,
+    // empty if the program type checks
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl AnalysisResult {
+    /// Returns the type info of the main package
+    pub fn main_info(&self) -> Option<&types::TypeInfo> {
+        self.main_pkg.and_then(|p| self.packages.get(&p))
+    }
 }
 
 pub struct Engine {
     config: Config,
     ffi: vm::ffi::FfiFactory,
+    // in-memory source files by name, see register_source
+    sources: HashMap<String, String>,
+    // the program compiled by load
+    program: Option<Program>,
+}
+
+struct Program {
+    code: vm::vm::ByteCode,
+    gcv: vm::gc::GcoVec,
+    fs: fe::FileSet,
 }
 
 impl Engine {
@@ -28,6 +169,8 @@ impl Engine {
         Engine {
             config: config,
             ffi: ffi,
+            sources: HashMap::new(),
+            program: None,
         }
     }
 
@@ -36,34 +179,235 @@ impl Engine {
         bits::Bits::register(self);
         sync::Mutex::register(self);
         sync::RWMutex::register(self);
+        atomic::Atomic::register(self);
         reflect::Reflect::register(self);
+        strings::Strings::register(self);
+        list::List::register(self);
+        runtime::Runtime::register(self);
+        time::Time::register(self);
+        host::Host::register(self);
     }
 
+    /// Compiles and runs the program, returns the number of compile errors,
+    /// or 1 if the program is aborted by an error, like an unrecovered panic
     pub fn run(&mut self, path: &str) -> usize {
-        self.register_std();
+        match self.load(path) {
+            Ok(()) => {
+                let p = self.program.as_ref().unwrap();
+                let vm = self.vm(p);
+                match vm.run(self.config.wait_for_goroutines) {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        // panics are printed by the vm together with their call stack
+                        if self.config.trace_vm && !matches!(e, ScriptError::Panic(_)) {
+                            println!("{}", e);
+                        }
+                        1
+                    }
+                }
+            }
+            Err(e) => e.diagnostics.len(),
+        }
+    }
 
-        let config = types::Config {
-            work_dir: self.config.work_dir.clone(),
-            base_path: self.config.base_path.clone(),
-            trace_parser: self.config.trace_parser,
-            trace_checker: self.config.trace_checker,
+    /// Parses and type checks the program without generating code, the
+    /// results are returned even if there are errors.
+    pub fn analyze(&self, path: &str) -> AnalysisResult {
+        let config = self.types_config();
+        let mut fset = fe::FileSet::new();
+        let mut ast_objs = fe::objects::Objects::new();
+        let mut tc_objs = types::TCObjects::new();
+        let mut packages = HashMap::new();
+        let el = &mut fe::errors::ErrorList::new();
+        let main_pkg = {
+            let pkgs = &mut HashMap::new();
+            let importer = &mut types::Importer::new(
+                &config,
+                &mut fset,
+                pkgs,
+                &mut packages,
+                &mut ast_objs,
+                &mut tc_objs,
+                el,
+                0,
+            );
+            let key = types::ImportKey::new(path, "./");
+            importer.import(&key).ok()
         };
+        el.sort();
+        AnalysisResult {
+            fset,
+            ast_objs,
+            tc_objs,
+            packages,
+            main_pkg,
+            diagnostics: diagnostics(el),
+        }
+    }
+
+    /// Compiles the program without running it, so that its functions can be
+    /// called with call_function.
+    pub fn load(&mut self, path: &str) -> Result<(), CompileError> {
+        self.register_std();
+
+        let config = self.types_config();
         let mut fs = fe::FileSet::new();
         let el = &mut fe::errors::ErrorList::new();
         let code = cg::entry::parse_check_gen(path, &config, &mut fs, el);
-        if let Ok(bc) = code {
-            let vm = vm::vm::GosVM::new(bc, &self.ffi, Some(&fs));
-            vm.run();
-            0
-        } else {
-            if self.config.trace_vm {
+        match code {
+            Ok(bc) => {
+                self.program = Some(Program {
+                    code: bc,
+                    gcv: vm::gc::GcoVec::new(),
+                    fs: fs,
+                });
+                Ok(())
+            }
+            Err(_) => {
                 el.sort();
-                print!("{}", el);
+                if self.config.trace_vm {
+                    print!("{}", el);
+                }
+                Err(CompileError::new(el))
             }
-            code.unwrap_err()
         }
     }
 
+    /// Calls the function `name` of the package `pkg` in the loaded program,
+    /// the package is initialized by the first call. Panics in Go code,
+    /// mismatched arguments and exceeding the limits in Config are returned
+    /// as errors.
+    pub fn call_function(
+        &self,
+        pkg: &str,
+        name: &str,
+        args: Vec<GosValue>,
+    ) -> Result<Vec<GosValue>, ScriptError> {
+        let p = self.loaded().map_err(ScriptError::Call)?;
+        self.vm(p).call_function(pkg, name, args)
+    }
+
+    /// Starts calling the function `name` of the package `pkg` in the loaded
+    /// program as a coroutine the host drives with Coroutine::resume. It runs
+    /// until the function returns or a goroutine calls host.Yield, which
+    /// returns the value of the next resume.
+    pub fn start_function(
+        &self,
+        pkg: &str,
+        name: &str,
+        args: Vec<GosValue>,
+    ) -> Result<Coroutine<'_>, ScriptError> {
+        let p = self.loaded().map_err(ScriptError::Call)?;
+        self.vm(p).start_function(pkg, name, args)
+    }
+
+    /// Returns the metadata of the type `name` declared in the package `pkg` of
+    /// the loaded program, for building values with new_struct.
+    pub fn type_meta(&self, pkg: &str, name: &str) -> RuntimeResult<GosMetadata> {
+        let p = self.package(pkg)?;
+        let member = p.get_member_index(name).map(|i| p.member(*i).clone());
+        match member {
+            Some(GosValue::Metadata(m)) => Ok(m.into_value_category()),
+            _ => Err(format!("{}.{} is not a type", pkg, name)),
+        }
+    }
+
+    /// Returns the names and kinds of the members declared in the package
+    /// `pkg` of the loaded program, exported or not, methods and init
+    /// functions are not members.
+    pub fn package_members(&self, pkg: &str) -> RuntimeResult<Vec<(String, MemberKind)>> {
+        let members = self.package(pkg)?.members();
+        Ok(members
+            .into_iter()
+            .map(|(name, kind)| (name.to_owned(), kind))
+            .collect())
+    }
+
+    /// Returns the metadata of the predeclared types like int and string
+    pub fn metadata(&self) -> RuntimeResult<&Metadata> {
+        Ok(&self.loaded()?.code.objects.metadata)
+    }
+
+    /// Creates a slice of the values with the element type elem
+    pub fn new_slice(&mut self, elem: GosMetadata, vals: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let p = self.loaded_mut()?;
+        GosValue::slice_of(elem, vals, &mut p.code.objects, &p.gcv)
+    }
+
+    /// Creates a map of the key-value pairs with the key type kmeta and the
+    /// value type vmeta
+    pub fn new_map(
+        &mut self,
+        kmeta: GosMetadata,
+        vmeta: GosMetadata,
+        pairs: Vec<(GosValue, GosValue)>,
+    ) -> RuntimeResult<GosValue> {
+        let p = self.loaded_mut()?;
+        GosValue::map_of(kmeta, vmeta, pairs, &mut p.code.objects, &p.gcv)
+    }
+
+    /// Creates a struct of the type meta, usually from type_meta, with the
+    /// values of all its fields in order
+    pub fn new_struct(&self, meta: GosMetadata, fields: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let p = self.loaded()?;
+        GosValue::struct_of(meta, fields, &p.code.objects, &p.gcv)
+    }
+
+    fn types_config(&self) -> types::Config {
+        types::Config {
+            work_dir: self.config.work_dir.clone(),
+            base_path: self.config.base_path.clone(),
+            trace_parser: self.config.trace_parser,
+            trace_checker: self.config.trace_checker,
+            sources: self.sources.clone(),
+        }
+    }
+
+    fn vm<'a>(&'a self, p: &'a Program) -> vm::vm::GosVM<'a> {
+        let (budget, max_objects) = (self.config.instruction_budget, self.config.max_heap_objects);
+        let max_depth = self.config.max_call_depth;
+        vm::vm::GosVM::new(
+            &p.code,
+            &p.gcv,
+            &self.ffi,
+            Some(&p.fs),
+            budget,
+            max_objects,
+            max_depth,
+        )
+    }
+
+    fn package(&self, name: &str) -> RuntimeResult<&PackageVal> {
+        self.loaded()?
+            .code
+            .objects
+            .packages
+            .iter()
+            .find(|(_, p)| p.name() == name)
+            .map(|(_, p)| p)
+            .ok_or_else(|| format!("package {} not found", name))
+    }
+
+    fn loaded(&self) -> RuntimeResult<&Program> {
+        self.program
+            .as_ref()
+            .ok_or_else(|| "no program loaded".to_owned())
+    }
+
+    fn loaded_mut(&mut self) -> RuntimeResult<&mut Program> {
+        self.program
+            .as_mut()
+            .ok_or_else(|| "no program loaded".to_owned())
+    }
+
+    /// Registers an in-memory source file, which is found by the import
+    /// resolver before the file system. The name is the import path of a
+    /// single-file package, or a file in the package named by its directory,
+    /// e.g. "lib/a.gos" and "lib/b.gos" make up the package "lib".
+    pub fn register_source(&mut self, name: &str, src: &str) {
+        self.sources.insert(name.to_owned(), src.to_owned());
+    }
+
     pub fn register_extension(&mut self, name: &'static str, ctor: Box<vm::ffi::Ctor>) {
         self.ffi.register(name, ctor);
     }
@@ -1,3 +1,3 @@
 pub use goscript_pmacro::*;
-pub use goscript_vm::ffi::{Ffi, FfiCallCtx, FfiCtorResult};
+pub use goscript_vm::ffi::{user_data, Ffi, FfiCallCtx, FfiCtorResult, GoCall, Sleep};
 pub use goscript_vm::value::RuntimeResult;
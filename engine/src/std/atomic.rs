@@ -0,0 +1,62 @@
+extern crate self as goscript_engine;
+use crate::ffi::*;
+use goscript_vm::value::{GosValue, RuntimeResult};
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+// An ffi call is never interrupted by the scheduler, so a plain
+// read-modify-write through the pointer is atomic to the other goroutines.
+macro_rules! int64_pointee {
+    ($arg0:expr, $ctx:expr) => {{
+        match $arg0 {
+            GosValue::Pointer(p) => {
+                let v = *p.deref(&$ctx.stack, &$ctx.vm_objs.packages).as_int64();
+                Ok((p, v))
+            }
+            _ => Err("invalid memory address or nil pointer dereference".to_owned()),
+        }
+    }};
+}
+
+#[derive(Ffi)]
+pub struct Atomic {}
+
+#[ffi_impl(rename = "sync.atomic")]
+impl Atomic {
+    pub fn new(_v: Vec<GosValue>) -> Atomic {
+        Atomic {}
+    }
+
+    fn ffi_add_int64(&self, ctx: &mut FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let (p, old) = int64_pointee!(&args[0], ctx)?;
+        let new = GosValue::Int64(old.wrapping_add(*args[1].as_int64()));
+        p.set_value(new.clone(), ctx.stack, &ctx.vm_objs.packages, &ctx.gcv);
+        Ok(new)
+    }
+
+    fn ffi_load_int64(&self, ctx: &mut FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let (_, v) = int64_pointee!(&args[0], ctx)?;
+        Ok(GosValue::Int64(v))
+    }
+
+    fn ffi_store_int64(&self, ctx: &mut FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<()> {
+        let (p, _) = int64_pointee!(&args[0], ctx)?;
+        p.set_value(args[1].clone(), ctx.stack, &ctx.vm_objs.packages, &ctx.gcv);
+        Ok(())
+    }
+
+    fn ffi_compare_and_swap_int64(
+        &self,
+        ctx: &mut FfiCallCtx,
+        args: Vec<GosValue>,
+    ) -> RuntimeResult<GosValue> {
+        let (p, v) = int64_pointee!(&args[0], ctx)?;
+        let swapped = v == *args[1].as_int64();
+        if swapped {
+            p.set_value(args[2].clone(), ctx.stack, &ctx.vm_objs.packages, &ctx.gcv);
+        }
+        Ok(GosValue::Bool(swapped))
+    }
+}
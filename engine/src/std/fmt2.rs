@@ -1,4 +1,5 @@
 extern crate self as goscript_engine;
+use super::format;
 use crate::ffi::*;
 use goscript_vm::value::GosValue;
 use std::cell::RefCell;
@@ -16,25 +17,31 @@ impl Fmt2 {
     }
 
     fn ffi_println(&self, args: Vec<GosValue>) {
-        let vec = args[0].as_slice().0.get_vec();
-        let strs: Vec<String> = vec
-            .iter()
-            .map(|x| {
-                if x.is_nil() {
-                    "<nil>".to_owned()
-                } else {
-                    match x.iface_underlying() {
-                        Some(v) => v.to_string(),
-                        None => "<ffi>".to_owned(),
-                    }
-                }
-            })
-            .collect();
-        println!("{}", strs.join(", "));
+        println!("{}", Fmt2::format_line(&args));
+    }
+
+    fn ffi_sprintln(&self, args: Vec<GosValue>) -> GosValue {
+        GosValue::new_str(Fmt2::format_line(&args) + "\n")
     }
 
     fn ffi_printf(&self, args: Vec<GosValue>) {
-        let _vec = args[0].as_slice().0.get_vec();
-        unimplemented!();
+        print!("{}", Fmt2::format(&args));
+    }
+
+    fn ffi_sprintf(&self, args: Vec<GosValue>) -> GosValue {
+        GosValue::new_str(Fmt2::format(&args))
+    }
+
+    /// args[0] is the slice of the results of the String methods, args[1]
+    /// is the slice of operands
+    fn format_line(args: &[GosValue]) -> String {
+        format::sprintln(&format::operands(&args[0], &args[1]))
+    }
+
+    /// args[0] is the format string, args[1] is the slice of the results of
+    /// the String methods, args[2] is the slice of operands
+    fn format(args: &[GosValue]) -> String {
+        let format = args[0].as_str();
+        format::sprintf(format.as_str(), &format::operands(&args[1], &args[2]))
     }
 }
@@ -0,0 +1,307 @@
+//! The formatting core of the Print family of fmt2, in one place so that
+//! every entry point formats the same way.
+use goscript_vm::objects::PointerObj;
+use goscript_vm::value::GosValue;
+use std::fmt;
+
+/// Unpacks the operands from the interfaces, methods holds the results of
+/// their String or Error methods, it's nil if none of them has one
+pub fn operands(methods: &GosValue, operands: &GosValue) -> Vec<Operand> {
+    let methods = match methods.is_nil() {
+        true => vec![],
+        false => methods.as_slice().0.get_vec(),
+    };
+    operands
+        .as_slice()
+        .0
+        .get_vec()
+        .iter()
+        .enumerate()
+        .map(|(i, x)| Operand {
+            val: match x.is_nil() {
+                true => None,
+                false => x.iface_underlying(),
+            },
+            method: methods
+                .get(i)
+                .filter(|m| !m.is_nil())
+                .and_then(|m| m.iface_underlying()),
+        })
+        .collect()
+}
+
+/// Formats each operand as with %v, and as in Go they are always separated
+/// by a single space
+pub fn sprintln(operands: &[Operand]) -> String {
+    let spec = FmtSpec::default();
+    let strs: Vec<String> = operands
+        .iter()
+        .map(|x| fmt_value(&spec, 'v', x).unwrap())
+        .collect();
+    strs.join(" ")
+}
+
+/// A value to be formatted, and the result of its String or Error method
+pub struct Operand {
+    pub val: Option<GosValue>,
+    pub method: Option<GosValue>,
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.val {
+            Some(v) => write!(f, "{}", v),
+            None => f.write_str("<nil>"),
+        }
+    }
+}
+
+/// Flags, width and precision of a single verb, as in Go's fmt package
+#[derive(Clone, Copy, Default)]
+struct FmtSpec {
+    minus: bool,
+    plus: bool,
+    sharp: bool,
+    zero: bool,
+    space: bool,
+    width: Option<usize>,
+    prec: Option<usize>,
+}
+
+impl FmtSpec {
+    /// Pads s with spaces (or zeros if the zero flag is set) up to the width
+    fn pad(&self, s: &str) -> String {
+        self.pad_with(s, self.zero)
+    }
+
+    fn pad_with(&self, s: &str, zero: bool) -> String {
+        let len = s.chars().count();
+        let width = self.width.unwrap_or(0);
+        if len >= width {
+            return s.to_owned();
+        }
+        let fill = (if zero { "0" } else { " " }).repeat(width - len);
+        match self.minus {
+            true => format!("{}{}", s, fill),
+            false => format!("{}{}", fill, s),
+        }
+    }
+
+    /// Formats an integer the way Go's fmtInteger does
+    fn fmt_integer(&self, val: i128, base: u32, verb: char) -> String {
+        let negative = val < 0;
+        let u = val.unsigned_abs();
+        let mut prec = 0;
+        if let Some(p) = self.prec {
+            if p == 0 && u == 0 {
+                return self.pad_with("", false);
+            }
+            prec = p;
+        } else if self.zero {
+            if let Some(w) = self.width {
+                prec = w;
+                if negative || self.plus || self.space {
+                    prec = prec.saturating_sub(1); // leave room for sign
+                }
+            }
+        }
+
+        let mut digits = match base {
+            2 => format!("{:b}", u),
+            8 => format!("{:o}", u),
+            16 if verb == 'X' => format!("{:X}", u),
+            16 => format!("{:x}", u),
+            _ => u.to_string(),
+        };
+        if digits.len() < prec {
+            digits = "0".repeat(prec - digits.len()) + &digits;
+        }
+
+        let mut prefix = String::new();
+        if self.sharp {
+            match base {
+                2 => prefix.push_str("0b"),
+                8 if !digits.starts_with('0') => prefix.push('0'),
+                16 if verb == 'X' => prefix.push_str("0X"),
+                16 => prefix.push_str("0x"),
+                _ => {}
+            }
+        }
+        if verb == 'O' {
+            prefix = "0o".to_owned();
+        }
+
+        let sign = if negative {
+            "-"
+        } else if self.plus {
+            "+"
+        } else if self.space {
+            " "
+        } else {
+            ""
+        };
+        // zero padding has already been handled as precision
+        self.pad_with(&format!("{}{}{}", sign, prefix, digits), false)
+    }
+
+    /// Formats an integer as a Unicode code point, invalid code points
+    /// are replaced with U+FFFD
+    fn fmt_char(&self, val: i128) -> String {
+        let c = u32::try_from(val)
+            .ok()
+            .and_then(char::from_u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        self.pad(&c.to_string())
+    }
+}
+
+fn int_val(v: &GosValue) -> Option<i128> {
+    match v {
+        GosValue::Int(i) => Some(*i as i128),
+        GosValue::Int8(i) => Some(*i as i128),
+        GosValue::Int16(i) => Some(*i as i128),
+        GosValue::Int32(i) => Some(*i as i128),
+        GosValue::Int64(i) => Some(*i as i128),
+        GosValue::Uint(i) => Some(*i as i128),
+        GosValue::UintPtr(i) => Some(*i as i128),
+        GosValue::Uint8(i) => Some(*i as i128),
+        GosValue::Uint16(i) => Some(*i as i128),
+        GosValue::Uint32(i) => Some(*i as i128),
+        GosValue::Uint64(i) => Some(*i as i128),
+        _ => None,
+    }
+}
+
+fn fmt_value(spec: &FmtSpec, verb: char, operand: &Operand) -> Option<String> {
+    // as in Go, the String method is used for the verbs that accept a string
+    if let (Some(m), 'v' | 's') = (&operand.method, verb) {
+        return Some(spec.pad(&m.to_string()));
+    }
+    let val = match &operand.val {
+        Some(v) => v.unwrap_named_ref(),
+        None => {
+            return match verb {
+                'v' => Some(spec.pad_with("<nil>", false)),
+                _ => None,
+            }
+        }
+    };
+    if let Some(i) = int_val(val) {
+        return match verb {
+            'v' | 'd' => Some(spec.fmt_integer(i, 10, verb)),
+            'b' => Some(spec.fmt_integer(i, 2, verb)),
+            'o' | 'O' => Some(spec.fmt_integer(i, 8, verb)),
+            'x' | 'X' => Some(spec.fmt_integer(i, 16, verb)),
+            'c' => Some(spec.fmt_char(i)),
+            _ => None,
+        };
+    }
+    match (verb, val) {
+        // as in Go, a pointer to a composite value is printed as &{...}
+        // at the top level, but not inside other values
+        ('v', GosValue::Pointer(p)) => {
+            let s = match p.as_ref() {
+                PointerObj::Struct(s, _) => format!("&{}", s.0.borrow()),
+                PointerObj::Array(a, _) => format!("&{}", a.0),
+                PointerObj::Slice(s, _) => format!("&{}", s.0),
+                PointerObj::Map(m, _) => format!("&{}", m.0),
+                _ => val.to_string(),
+            };
+            Some(spec.pad(&s))
+        }
+        ('v', _) | ('s', GosValue::Str(_)) | ('t', GosValue::Bool(_)) => {
+            Some(spec.pad(&val.to_string()))
+        }
+        ('p', GosValue::Pointer(p)) => {
+            let spec = FmtSpec {
+                sharp: true,
+                prec: None,
+                ..*spec
+            };
+            Some(spec.fmt_integer(p.addr() as i128, 16, 'x'))
+        }
+        ('p', GosValue::Nil(_)) => Some(spec.pad_with("0x0", false)),
+        ('f' | 'F', GosValue::Float32(_) | GosValue::Float64(_)) => {
+            let f = match val {
+                GosValue::Float32(f) => f.into_inner() as f64,
+                _ => *val.as_float64(),
+            };
+            let mut s = format!("{:.*}", spec.prec.unwrap_or(6), f);
+            if spec.plus && f >= 0.0 {
+                s.insert(0, '+');
+            }
+            Some(spec.pad(&s))
+        }
+        _ => None,
+    }
+}
+
+/// Formats operands according to the format specifier, following Go's
+/// conventions for errors: %!verb(value) for a bad verb, %!verb(MISSING)
+/// for a missing operand and %!(EXTRA ...) for unused operands.
+pub fn sprintf(format: &str, operands: &[Operand]) -> String {
+    let mut result = String::new();
+    let mut chars = format.chars().peekable();
+    let mut arg_num = 0;
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+
+        let mut spec = FmtSpec::default();
+        while let Some(&c) = chars.peek() {
+            match c {
+                '-' => spec.minus = true,
+                '+' => spec.plus = true,
+                '#' => spec.sharp = true,
+                '0' => spec.zero = true,
+                ' ' => spec.space = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        // as in Go, '-' overrides '0'
+        spec.zero = spec.zero && !spec.minus;
+        let parse_num = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut num: Option<usize> = None;
+            while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+                num = Some(num.unwrap_or(0) * 10 + d as usize);
+                chars.next();
+            }
+            num
+        };
+        spec.width = parse_num(&mut chars);
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            spec.prec = Some(parse_num(&mut chars).unwrap_or(0));
+        }
+
+        let verb = match chars.next() {
+            Some(v) => v,
+            None => {
+                result.push_str("%!(NOVERB)");
+                break;
+            }
+        };
+        if verb == '%' {
+            result.push('%');
+            continue;
+        }
+        if arg_num >= operands.len() {
+            result.push_str(&format!("%!{}(MISSING)", verb));
+            continue;
+        }
+        let operand = &operands[arg_num];
+        arg_num += 1;
+        match fmt_value(&spec, verb, operand) {
+            Some(s) => result.push_str(&s),
+            None => result.push_str(&format!("%!{}({})", verb, operand.to_string())),
+        }
+    }
+    if arg_num < operands.len() {
+        let extra: Vec<String> = operands[arg_num..].iter().map(|x| x.to_string()).collect();
+        result.push_str(&format!("%!(EXTRA {})", extra.join(", ")));
+    }
+    result
+}
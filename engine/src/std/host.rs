@@ -0,0 +1,41 @@
+extern crate self as goscript_engine;
+use crate::ffi::*;
+use goscript_vm::objects::IfaceUnderlying;
+use goscript_vm::value::{GosValue, RuntimeResult};
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+#[derive(Ffi)]
+pub struct Host {}
+
+#[ffi_impl]
+impl Host {
+    pub fn new(_v: Vec<GosValue>) -> Host {
+        Host {}
+    }
+
+    /// passes the value in the interface args[0] to the host, and returns
+    /// the value the host resumes with in an interface
+    fn ffi_yield(
+        &self,
+        ctx: &mut FfiCallCtx,
+        args: Vec<GosValue>,
+    ) -> Pin<Box<dyn Future<Output = RuntimeResult<Vec<GosValue>>> + '_>> {
+        let v = match &args[0] {
+            GosValue::Nil(_) => GosValue::new_nil(),
+            iface => iface.iface_underlying().unwrap_or(GosValue::new_nil()),
+        };
+        let meta = ctx.vm_objs.metadata.empty_iface;
+        let yielded = (ctx.yield_to_host)(v);
+        Box::pin(async move {
+            let resumed = yielded?.resumed().await?;
+            let iface = match resumed {
+                GosValue::Nil(_) => GosValue::Nil(meta),
+                v => GosValue::new_iface(meta, IfaceUnderlying::Gos(v, None)),
+            };
+            Ok(vec![iface])
+        })
+    }
+}
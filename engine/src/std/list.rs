@@ -0,0 +1,197 @@
+extern crate self as goscript_engine;
+use crate::ffi::*;
+use goscript_vm::value::{GosValue, PointerObj, RuntimeResult, UserData};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+#[derive(Ffi)]
+pub struct List {}
+
+#[ffi_impl(rename = "container.list")]
+impl List {
+    pub fn new(_v: Vec<GosValue>) -> List {
+        List {}
+    }
+
+    fn ffi_new_list(&self, _args: Vec<GosValue>) -> GosValue {
+        GosValue::new_pointer(PointerObj::UserData(Rc::new(ListInner::new())))
+    }
+
+    fn ffi_len(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        Ok(GosValue::Int(List::inner(&args)?.len()))
+    }
+
+    fn ffi_push_front(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let id = List::inner(&args)?.push(args[1].clone(), true);
+        Ok(GosValue::Int(id as isize))
+    }
+
+    fn ffi_push_back(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let id = List::inner(&args)?.push(args[1].clone(), false);
+        Ok(GosValue::Int(id as isize))
+    }
+
+    fn ffi_remove(&self, args: Vec<GosValue>) -> RuntimeResult<()> {
+        let inner = List::inner(&args)?;
+        inner.remove(inner.id(&args[1])?);
+        Ok(())
+    }
+
+    fn ffi_front(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let inner = List::inner(&args)?;
+        Ok(inner.value(inner.ends.get().map(|(front, _)| front)))
+    }
+
+    fn ffi_back(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let inner = List::inner(&args)?;
+        Ok(inner.value(inner.ends.get().map(|(_, back)| back)))
+    }
+
+    fn ffi_next(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let inner = List::inner(&args)?;
+        let next = inner.link(inner.id(&args[1])?).next;
+        Ok(inner.value(next))
+    }
+
+    fn ffi_prev(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let inner = List::inner(&args)?;
+        let prev = inner.link(inner.id(&args[1])?).prev;
+        Ok(inner.value(prev))
+    }
+
+    fn inner(args: &[GosValue]) -> RuntimeResult<&ListInner> {
+        user_data::<ListInner>(&args[0])
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Link {
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// A doubly linked list, the nodes are addressed by their ids, which are
+/// reused after the nodes are removed.
+/// The elements refer back to the list, like channels, GC doesn't see
+/// through the user data so such cycles are not collected for now.
+struct ListInner {
+    values: RefCell<Vec<Option<GosValue>>>,
+    links: RefCell<Vec<Link>>,
+    free: RefCell<Vec<usize>>,
+    // (front, back)
+    ends: Cell<Option<(usize, usize)>>,
+}
+
+impl UserData for ListInner {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    // a list is only equal to itself
+    fn eq(&self, other: &dyn UserData) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<ListInner>()
+            .map_or(false, |o| std::ptr::eq(self, o))
+    }
+}
+
+impl ListInner {
+    fn new() -> ListInner {
+        ListInner {
+            values: RefCell::new(vec![]),
+            links: RefCell::new(vec![]),
+            free: RefCell::new(vec![]),
+            ends: Cell::new(None),
+        }
+    }
+
+    fn len(&self) -> isize {
+        (self.values.borrow().len() - self.free.borrow().len()) as isize
+    }
+
+    /// Returns the id in v if it's the id of a node in the list
+    fn id(&self, v: &GosValue) -> RuntimeResult<usize> {
+        let id = *v.as_int();
+        match usize::try_from(id)
+            .ok()
+            .filter(|i| self.values.borrow().get(*i).map_or(false, |v| v.is_some()))
+        {
+            Some(i) => Ok(i),
+            None => Err(format!("container/list: no element with id {}", id)),
+        }
+    }
+
+    fn link(&self, id: usize) -> Link {
+        self.links.borrow()[id]
+    }
+
+    fn value(&self, id: Option<usize>) -> GosValue {
+        id.and_then(|i| self.values.borrow()[i].clone())
+            .unwrap_or(GosValue::new_nil())
+    }
+
+    fn push(&self, val: GosValue, front: bool) -> usize {
+        let link = match (self.ends.get(), front) {
+            (None, _) => Link {
+                prev: None,
+                next: None,
+            },
+            (Some((f, _)), true) => Link {
+                prev: None,
+                next: Some(f),
+            },
+            (Some((_, b)), false) => Link {
+                prev: Some(b),
+                next: None,
+            },
+        };
+        let id = match self.free.borrow_mut().pop() {
+            Some(id) => {
+                self.values.borrow_mut()[id] = Some(val);
+                self.links.borrow_mut()[id] = link;
+                id
+            }
+            None => {
+                self.values.borrow_mut().push(Some(val));
+                self.links.borrow_mut().push(link);
+                self.values.borrow().len() - 1
+            }
+        };
+        let mut links = self.links.borrow_mut();
+        let ends = match (self.ends.get(), front) {
+            (None, _) => (id, id),
+            (Some((f, b)), true) => {
+                links[f].prev = Some(id);
+                (id, b)
+            }
+            (Some((f, b)), false) => {
+                links[b].next = Some(id);
+                (f, id)
+            }
+        };
+        self.ends.set(Some(ends));
+        id
+    }
+
+    fn remove(&self, id: usize) {
+        let mut links = self.links.borrow_mut();
+        let Link { prev, next } = links[id];
+        let (mut front, mut back) = self.ends.get().unwrap();
+        match prev {
+            Some(p) => links[p].next = next,
+            None => front = next.unwrap_or(front),
+        }
+        match next {
+            Some(n) => links[n].prev = prev,
+            None => back = prev.unwrap_or(back),
+        }
+        self.ends
+            .set((prev.is_some() || next.is_some()).then(|| (front, back)));
+        self.values.borrow_mut()[id] = None;
+        self.free.borrow_mut().push(id);
+    }
+}
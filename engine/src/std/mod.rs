@@ -1,4 +1,11 @@
+pub mod atomic;
 pub mod bits;
 pub mod fmt2;
+pub mod format;
+pub mod host;
+pub mod list;
 pub mod reflect;
+pub mod runtime;
+pub mod strings;
 pub mod sync;
+pub mod time;
@@ -1,7 +1,7 @@
 extern crate self as goscript_engine;
 use crate::ffi::*;
 use goscript_vm::instruction::ValueType;
-use goscript_vm::metadata::GosMetadata;
+use goscript_vm::metadata::{GosMetadata, MetaCategory, MetadataType, SigMetadata};
 use goscript_vm::objects::*;
 use goscript_vm::value::{GosValue, IfaceUnderlying, PointerObj, UserData};
 use std::any::Any;
@@ -11,18 +11,6 @@ use std::mem;
 use std::pin::Pin;
 use std::rc::Rc;
 
-macro_rules! arg_as {
-    ($arg:expr, $t:ty) => {{
-        match $arg {
-            GosValue::Pointer(p) => match p as &PointerObj {
-                PointerObj::UserData(ud) => Ok(ud.as_any().downcast_ref::<$t>().unwrap()),
-                _ => Err("reflect: expect UserData".to_owned()),
-            },
-            _ => Err("reflect: expect pointer".to_owned()),
-        }
-    }};
-}
-
 macro_rules! err_wrong_type {
     () => {
         Err("reflect: wrong type".to_owned())
@@ -53,7 +41,7 @@ fn wrap_ptr_std_val(p: Box<PointerObj>) -> GosValue {
 
 #[inline]
 fn unwrap_set_args(args: &Vec<GosValue>) -> RuntimeResult<(&StdValue, GosValue)> {
-    Ok((arg_as!(&args[0], StdValue)?, args[1].clone()))
+    Ok((user_data::<StdValue>(&args[0])?, args[1].clone()))
 }
 
 #[inline]
@@ -105,83 +93,171 @@ impl Reflect {
     }
 
     fn ffi_type_of(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<Vec<GosValue>> {
-        let v = arg_as!(&args[0], StdValue)?;
+        let v = user_data::<StdValue>(&args[0])?;
         let (t, k) = StdType::type_of(&v.val(ctx), ctx);
         Ok(vec![t, k])
     }
 
+    fn ffi_value_kind(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let v = user_data::<StdValue>(&args[0])?;
+        Ok(GosValue::Uint(StdType::kind_of(&v.val(ctx), ctx) as usize))
+    }
+
+    fn ffi_type_num_in(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let sig = user_data::<StdType>(&args[0])?.sig(ctx)?;
+        Ok(GosValue::Int(sig.params.len() as isize))
+    }
+
+    fn ffi_type_num_out(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let sig = user_data::<StdType>(&args[0])?.sig(ctx)?;
+        Ok(GosValue::Int(sig.results.len() as isize))
+    }
+
+    fn ffi_type_in(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<Vec<GosValue>> {
+        let sig = user_data::<StdType>(&args[0])?.sig(ctx)?;
+        match usize::try_from(*args[1].as_int())
+            .ok()
+            .and_then(|i| sig.params.get(i))
+        {
+            Some(m) => Ok(StdType::type_of_meta(*m, ctx)),
+            None => err_index_oor!(),
+        }
+    }
+
+    fn ffi_type_out(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<Vec<GosValue>> {
+        let sig = user_data::<StdType>(&args[0])?.sig(ctx)?;
+        match usize::try_from(*args[1].as_int())
+            .ok()
+            .and_then(|i| sig.results.get(i))
+        {
+            Some(m) => Ok(StdType::type_of_meta(*m, ctx)),
+            None => err_index_oor!(),
+        }
+    }
+
+    fn ffi_type_is_variadic(
+        &self,
+        ctx: &FfiCallCtx,
+        args: Vec<GosValue>,
+    ) -> RuntimeResult<GosValue> {
+        let sig = user_data::<StdType>(&args[0])?.sig(ctx)?;
+        Ok(GosValue::Bool(sig.variadic.is_some()))
+    }
+
+    fn ffi_type_assignable_to(
+        &self,
+        ctx: &FfiCallCtx,
+        args: Vec<GosValue>,
+    ) -> RuntimeResult<GosValue> {
+        let t = user_data::<StdType>(&args[0])?.meta;
+        let u = user_data::<StdType>(&args[1])?.meta;
+        Ok(GosValue::Bool(t.assignable_to(&u, &ctx.vm_objs.metas)))
+    }
+
+    fn ffi_zero(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        Ok(user_data::<StdType>(&args[0])?.zero(ctx))
+    }
+
+    fn ffi_new(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        Ok(user_data::<StdType>(&args[0])?.new_ptr(ctx))
+    }
+
+    fn ffi_interface_val(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        Ok(user_data::<StdValue>(&args[0])?.interface_val(ctx))
+    }
+
+    fn ffi_make_slice(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        user_data::<StdType>(&args[0])?.make_slice(ctx, *args[1].as_int(), *args[2].as_int())
+    }
+
+    fn ffi_append(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let vals = args[1]
+            .as_slice()
+            .0
+            .get_vec()
+            .iter()
+            .map(|x| user_data::<StdValue>(x).map(|v| v.val(ctx)))
+            .collect::<RuntimeResult<Vec<GosValue>>>()?;
+        user_data::<StdValue>(&args[0])?.append(ctx, vals)
+    }
+
     fn ffi_bool_val(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.bool_val(ctx)
+        user_data::<StdValue>(&args[0])?.bool_val(ctx)
     }
 
     fn ffi_int_val(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.int_val(ctx)
+        user_data::<StdValue>(&args[0])?.int_val(ctx)
     }
 
     fn ffi_uint_val(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.uint_val(ctx)
+        user_data::<StdValue>(&args[0])?.uint_val(ctx)
     }
 
     fn ffi_float_val(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.float_val(ctx)
+        user_data::<StdValue>(&args[0])?.float_val(ctx)
     }
 
     fn ffi_bytes_val(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.bytes_val(ctx)
+        user_data::<StdValue>(&args[0])?.bytes_val(ctx)
     }
 
     fn ffi_elem(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.elem(ctx)
+        user_data::<StdValue>(&args[0])?.elem(ctx)
     }
 
     fn ffi_num_field(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.num_field(ctx)
+        user_data::<StdValue>(&args[0])?.num_field(ctx)
     }
 
     fn ffi_field(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.field(ctx, &args[1])
+        user_data::<StdValue>(&args[0])?.field(ctx, &args[1])
     }
 
     fn ffi_index(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.index(ctx, &args[1])
+        user_data::<StdValue>(&args[0])?.index(ctx, &args[1])
     }
 
     fn ffi_is_nil(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
         Ok(GosValue::Bool(
-            arg_as!(&args[0], StdValue)?.val(ctx).equals_nil(),
+            user_data::<StdValue>(&args[0])?.val(ctx).equals_nil(),
         ))
     }
 
     fn ffi_len(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdValue)?.len(ctx)
+        user_data::<StdValue>(&args[0])?.len(ctx)
     }
 
     fn ffi_map_range_init(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        Ok(StdMapIter::map_range(ctx, arg_as!(&args[0], StdValue)?))
+        Ok(StdMapIter::map_range(ctx, user_data::<StdValue>(&args[0])?))
     }
 
     fn ffi_map_range_next(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        Ok(arg_as!(&args[0], StdMapIter)?.next())
+        Ok(user_data::<StdMapIter>(&args[0])?.next())
     }
 
     fn ffi_map_range_key(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdMapIter)?.key()
+        user_data::<StdMapIter>(&args[0])?.key()
     }
 
     fn ffi_map_range_value(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        arg_as!(&args[0], StdMapIter)?.value()
+        user_data::<StdMapIter>(&args[0])?.value()
     }
 
     fn ffi_can_addr(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        Ok(GosValue::Bool(arg_as!(&args[0], StdValue)?.can_addr()))
+        Ok(GosValue::Bool(user_data::<StdValue>(&args[0])?.can_addr()))
     }
 
     fn ffi_can_set(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
-        Ok(GosValue::Bool(arg_as!(&args[0], StdValue)?.can_set(ctx)))
+        Ok(GosValue::Bool(
+            user_data::<StdValue>(&args[0])?.can_set(ctx),
+        ))
     }
 
-    fn ffi_set(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<()> {
-        arg_as!(&args[0], StdValue)?.set(ctx, arg_as!(&args[1], StdValue)?.val(ctx))
+    fn ffi_set(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let from = user_data::<StdValue>(&args[1])?;
+        Ok(GosValue::Bool(
+            user_data::<StdValue>(&args[0])?.assign(ctx, from)?,
+        ))
     }
 
     fn ffi_set_bool(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<()> {
@@ -224,6 +300,39 @@ impl Reflect {
         to.set_pointer(ctx, val)
     }
 
+    fn ffi_method_by_name(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        user_data::<StdValue>(&args[0])?.method_by_name(ctx, args[1].as_str().as_str())
+    }
+
+    fn ffi_num_out(&self, ctx: &FfiCallCtx, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let val = user_data::<StdValue>(&args[0])?.val(ctx);
+        match val.unwrap_named_ref() {
+            GosValue::Closure(c) => {
+                let sig = ctx.vm_objs.metas[c.0.borrow().meta.as_non_ptr()].as_signature();
+                Ok(GosValue::Int(sig.results.len() as isize))
+            }
+            _ => err_wrong_type!(),
+        }
+    }
+
+    /// calls the func args[0] with the arguments args[1] in a new goroutine,
+    /// the results are put in args[2]
+    fn ffi_call(
+        &self,
+        ctx: &mut FfiCallCtx,
+        args: Vec<GosValue>,
+    ) -> Pin<Box<dyn Future<Output = RuntimeResult<Vec<GosValue>>> + '_>> {
+        let call = StdValue::call(ctx, &args[0], &args[1]);
+        let outs = args[2].as_slice().clone();
+        Box::pin(async move {
+            let results = call?.results().await?;
+            for (i, v) in results.into_iter().enumerate() {
+                outs.0.set(i, wrap_std_val(v));
+            }
+            Ok(vec![])
+        })
+    }
+
     fn ffi_swap(&self, args: Vec<GosValue>) -> RuntimeResult<()> {
         let mut iter = args.into_iter();
         let arg0 = iter.next();
@@ -281,6 +390,50 @@ impl StdValue {
         }
     }
 
+    /// The exported method of the value with the name bound to the value, nil
+    /// if it's not in the method set of the value.
+    fn method_by_name(&self, ctx: &FfiCallCtx, name: &str) -> RuntimeResult<GosValue> {
+        let val = self.val(ctx);
+        let meta = val.meta(ctx.vm_objs, ctx.stack);
+        let binding = match meta {
+            GosMetadata::NonPtr(_, _) | GosMetadata::Ptr1(_, _)
+                if name.starts_with(char::is_uppercase) =>
+            {
+                meta.get_iface_binding(&name.to_owned(), &ctx.vm_objs.metas)
+            }
+            _ => None,
+        };
+        match binding {
+            // the method set of T does not contain the methods of *T
+            Some(IfaceBinding::Struct(m, None))
+                if matches!(meta, GosMetadata::NonPtr(_, _)) && m.borrow().pointer_recv =>
+            {
+                Ok(GosValue::Nil(ctx.vm_objs.metadata.unsafe_ptr))
+            }
+            Some(b) => IfaceUnderlying::Gos(val, Some(vec![b.into()]))
+                .bind_method(0, &ctx.vm_objs.functions, ctx.gcv)
+                .map(wrap_std_val),
+            None => Ok(GosValue::Nil(ctx.vm_objs.metadata.unsafe_ptr)),
+        }
+    }
+
+    /// Starts calling the func f with the Values in args
+    fn call(ctx: &FfiCallCtx, f: &GosValue, args: &GosValue) -> RuntimeResult<GoCall> {
+        let cls = match user_data::<StdValue>(f)?.val(ctx).unwrap_named() {
+            GosValue::Closure(c) => c,
+            GosValue::Nil(_) => return Err("reflect: call of nil function".to_owned()),
+            _ => return Err("reflect: call of non-function".to_owned()),
+        };
+        let args = args
+            .as_slice()
+            .0
+            .get_vec()
+            .iter()
+            .map(|x| user_data::<StdValue>(x).map(|v| v.val(ctx)))
+            .collect::<RuntimeResult<Vec<GosValue>>>()?;
+        (ctx.call_go)(cls, args).map_err(|e| format!("reflect: Call: {}", e))
+    }
+
     fn settable_meta(&self, ctx: &FfiCallCtx) -> RuntimeResult<GosMetadata> {
         match self {
             Self::Pointer(p) => Ok(p.point_to_meta(ctx.vm_objs, ctx.stack)),
@@ -288,6 +441,15 @@ impl StdValue {
         }
     }
 
+    /// The value as an interface{}, a copy of it if it's a struct or an array
+    fn interface_val(&self, ctx: &FfiCallCtx) -> GosValue {
+        let val = self.val(ctx);
+        match val.unwrap_named_ref() {
+            GosValue::Interface(_) => val.unwrap_named_ref().clone(),
+            _ => GosValue::new_empty_iface(&ctx.vm_objs.metadata, val.copy_semantic(ctx.gcv)),
+        }
+    }
+
     fn bool_val(&self, ctx: &FfiCallCtx) -> RuntimeResult<GosValue> {
         let val = self.val(ctx);
         match val.unwrap_named_ref() {
@@ -427,6 +589,39 @@ impl StdValue {
         .map(|x| GosValue::Int(x as isize))
     }
 
+    /// Like the builtin append, the values are assigned to the element type
+    fn append(&self, ctx: &FfiCallCtx, vals: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let val = self.val(ctx);
+        let (slice, named) = match &val {
+            GosValue::Named(n) => (&n.0, Some(n.1)),
+            _ => (&val, None),
+        };
+        let slice = match slice {
+            GosValue::Slice(s) => &s.0,
+            _ => return Err("reflect.Append: not a slice".to_owned()),
+        };
+        let metas = &ctx.vm_objs.metas;
+        let elem = match &metas[slice.meta.as_non_ptr()] {
+            MetadataType::SliceOrArray(m, _) => *m,
+            _ => unreachable!(),
+        };
+        let vals = vals
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| {
+                v.assign_to(&elem, ctx.vm_objs, ctx.gcv)
+                    .map_err(|e| format!("reflect.Append: element {}: {}", i, e))
+            })
+            .collect::<RuntimeResult<Vec<GosValue>>>()?;
+        let mut result = slice.clone();
+        result.append(&SliceObj::with_data(vals, slice.meta), metas, ctx.gcv);
+        let result = GosValue::slice_with_obj(result, ctx.gcv);
+        Ok(wrap_std_val(match named {
+            Some(m) => GosValue::Named(Box::new((result, m))),
+            None => result,
+        }))
+    }
+
     fn can_addr(&self) -> bool {
         match self {
             Self::Value(_) => false,
@@ -478,6 +673,42 @@ impl StdValue {
         }
     }
 
+    /// Sets the value to a copy of the value of from like Value.Set, returns
+    /// false if it's not assignable to the type of the value
+    fn assign(&self, ctx: &FfiCallCtx, from: &StdValue) -> RuntimeResult<bool> {
+        let p = match self {
+            Self::Pointer(_) if self.can_set(ctx) => self.settable_meta(ctx)?,
+            Self::Pointer(p) if matches!(p as &PointerObj, PointerObj::StructField(_, _)) => {
+                return Err(
+                    "reflect: reflect.Value.Set using value obtained using unexported field"
+                        .to_owned(),
+                )
+            }
+            _ => return Err("reflect: reflect.Value.Set using unaddressable value".to_owned()),
+        };
+        let metas = &ctx.vm_objs.metas;
+        let val = from.val(ctx).copy_semantic(ctx.gcv);
+        let val_meta = val.meta(ctx.vm_objs, ctx.stack);
+        let val = if val_meta.identical(&p, metas) {
+            val
+        } else if !val_meta.assignable_to(&p, metas) {
+            return Ok(false);
+        } else if p.underlying(metas).value_type(metas) == ValueType::Interface {
+            let b = val_meta.iface_bindings(&p, metas).unwrap();
+            let b = b.into_iter().map(|x| x.into()).collect();
+            GosValue::new_iface_of_type(p, IfaceUnderlying::Gos(val, Some(b)), metas)
+        } else {
+            // the underlying types are identical, and one of the types is not named
+            match p {
+                GosMetadata::NonPtr(k, _) if matches!(metas[k], MetadataType::Named(_, _)) => {
+                    GosValue::Named(Box::new((val, p)))
+                }
+                _ => val.unwrap_named(),
+            }
+        };
+        self.set(ctx, val).map(|_| true)
+    }
+
     fn set_bool(&self, ctx: &FfiCallCtx, val: GosValue) -> RuntimeResult<()> {
         match self.settable_meta(ctx)?.value_type(&ctx.vm_objs.metas) {
             ValueType::Bool => self.set(ctx, val),
@@ -598,7 +829,90 @@ impl StdType {
     fn type_of(val: &GosValue, ctx: &FfiCallCtx) -> (GosValue, GosValue) {
         let m = val.meta(ctx.vm_objs, ctx.stack);
         let typ = StdType::new(m, &ctx.vm_objs.metas);
-        let kind = match m
+        (
+            GosValue::new_pointer(PointerObj::UserData(Rc::new(typ))),
+            GosValue::Uint(StdType::kind_of(val, ctx) as usize),
+        )
+    }
+
+    /// The type and the kind of the type, for the types of the parameters and
+    /// the results of a func type
+    fn type_of_meta(m: GosMetadata, ctx: &FfiCallCtx) -> Vec<GosValue> {
+        let typ = StdType::new(m, &ctx.vm_objs.metas);
+        vec![
+            GosValue::new_pointer(PointerObj::UserData(Rc::new(typ))),
+            GosValue::Uint(StdType::kind_of_meta(&m, ctx) as usize),
+        ]
+    }
+
+    fn sig<'a>(&self, ctx: &FfiCallCtx<'a>) -> RuntimeResult<&'a SigMetadata> {
+        let metas = &ctx.vm_objs.metas;
+        match self.meta.underlying(metas) {
+            GosMetadata::NonPtr(k, MetaCategory::Default) => match &metas[k] {
+                MetadataType::Signature(sig) => Ok(sig),
+                _ => err_wrong_type!(),
+            },
+            _ => err_wrong_type!(),
+        }
+    }
+
+    /// The zero value of the type, which is not settable
+    fn zero(&self, ctx: &FfiCallCtx) -> GosValue {
+        let val = self.meta.zero_val(&ctx.vm_objs.metas, ctx.gcv);
+        GosValue::new_pointer(PointerObj::UserData(Rc::new(StdValue::Value(val))))
+    }
+
+    /// A pointer to a new zero value of the type, like Go's new(T)
+    fn new_ptr(&self, ctx: &FfiCallCtx) -> GosValue {
+        let val = self.meta.zero_val(&ctx.vm_objs.metas, ctx.gcv);
+        let ptr = PointerObj::try_new_local(&val)
+            .unwrap_or_else(|| PointerObj::UpVal(UpValue::new_closed(val)));
+        wrap_std_val(GosValue::new_pointer(ptr))
+    }
+
+    /// A new slice of the type with len zero values, like Go's make
+    fn make_slice(&self, ctx: &FfiCallCtx, len: isize, cap: isize) -> RuntimeResult<GosValue> {
+        let metas = &ctx.vm_objs.metas;
+        let umeta = self.meta.underlying(metas);
+        let elem = match umeta {
+            GosMetadata::NonPtr(k, MetaCategory::Default) => match &metas[k] {
+                MetadataType::SliceOrArray(m, _) => *m,
+                _ => return Err("reflect.MakeSlice of non-slice type".to_owned()),
+            },
+            _ => return Err("reflect.MakeSlice of non-slice type".to_owned()),
+        };
+        if len < 0 {
+            return Err("reflect.MakeSlice: negative len".to_owned());
+        } else if cap < len {
+            return Err("reflect.MakeSlice: len > cap".to_owned());
+        }
+        let zero = elem.zero_val(metas, ctx.gcv);
+        let slice = GosValue::new_slice(len as usize, cap as usize, umeta, Some(&zero), ctx.gcv);
+        Ok(wrap_std_val(match self.meta == umeta {
+            true => slice,
+            false => GosValue::Named(Box::new((slice, self.meta))),
+        }))
+    }
+
+    fn kind_of(val: &GosValue, ctx: &FfiCallCtx) -> GosKind {
+        let m = val.meta(ctx.vm_objs, ctx.stack);
+        match m
+            .underlying(&ctx.vm_objs.metas)
+            .value_type(&ctx.vm_objs.metas)
+        {
+            // a nil pointer is a Nil value
+            ValueType::Pointer => match val.unwrap_named_ref() {
+                GosValue::Pointer(p) if matches!(p as &PointerObj, PointerObj::UserData(_)) => {
+                    GosKind::UnsafePointer
+                }
+                _ => GosKind::Ptr,
+            },
+            _ => StdType::kind_of_meta(&m, ctx),
+        }
+    }
+
+    fn kind_of_meta(m: &GosMetadata, ctx: &FfiCallCtx) -> GosKind {
+        match m
             .underlying(&ctx.vm_objs.metas)
             .value_type(&ctx.vm_objs.metas)
         {
@@ -623,22 +937,13 @@ impl StdType {
             ValueType::Closure => GosKind::Func,
             ValueType::Interface => GosKind::Interface,
             ValueType::Map => GosKind::Map,
-            ValueType::Pointer => {
-                let ptr: &PointerObj = &*val.as_pointer();
-                match ptr {
-                    PointerObj::UserData(_) => GosKind::UnsafePointer,
-                    _ => GosKind::Ptr,
-                }
-            }
+            ValueType::Pointer if *m == ctx.vm_objs.metadata.unsafe_ptr => GosKind::UnsafePointer,
+            ValueType::Pointer => GosKind::Ptr,
             ValueType::Slice => GosKind::Slice,
             ValueType::Str => GosKind::String,
             ValueType::Struct => GosKind::Struct,
             _ => GosKind::Invalid,
-        };
-        (
-            GosValue::new_pointer(PointerObj::UserData(Rc::new(typ))),
-            GosValue::Uint(kind as usize),
-        )
+        }
     }
 }
 
@@ -0,0 +1,35 @@
+extern crate self as goscript_engine;
+use crate::ffi::*;
+use futures_lite::future;
+use goscript_vm::gc::gc;
+use goscript_vm::value::GosValue;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+#[derive(Ffi)]
+pub struct Runtime {}
+
+#[ffi_impl]
+impl Runtime {
+    pub fn new(_v: Vec<GosValue>) -> Runtime {
+        Runtime {}
+    }
+
+    fn ffi_gc(&self, ctx: &FfiCallCtx, _args: Vec<GosValue>) {
+        gc(ctx.gcv);
+    }
+
+    fn ffi_heap_objects(&self, ctx: &FfiCallCtx, _args: Vec<GosValue>) -> GosValue {
+        GosValue::Uint64(ctx.gcv.live_count() as u64)
+    }
+
+    fn ffi_num_goroutine(&self, ctx: &FfiCallCtx, _args: Vec<GosValue>) -> GosValue {
+        GosValue::Int(ctx.num_goroutines as isize)
+    }
+
+    async fn ffi_gosched(&self, _args: Vec<GosValue>) {
+        future::yield_now().await;
+    }
+}
@@ -0,0 +1,97 @@
+extern crate self as goscript_engine;
+use crate::ffi::*;
+use goscript_vm::value::{GosValue, PointerObj, RuntimeResult, UserData};
+use std::any::Any;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+#[derive(Ffi)]
+pub struct Strings {}
+
+#[ffi_impl]
+impl Strings {
+    pub fn new(_v: Vec<GosValue>) -> Strings {
+        Strings {}
+    }
+
+    fn ffi_new_replacer(&self, args: Vec<GosValue>) -> GosValue {
+        let oldnew: Vec<String> = args[0]
+            .as_slice()
+            .0
+            .get_vec()
+            .iter()
+            .map(|x| x.as_str().as_str().to_owned())
+            .collect();
+        let replacer = Replacer::new(oldnew);
+        GosValue::new_pointer(PointerObj::UserData(Rc::new(replacer)))
+    }
+
+    fn ffi_replace(&self, args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let replacer = user_data::<Replacer>(&args[0])?;
+        Ok(GosValue::new_str(
+            replacer.replace(args[1].as_str().as_str()),
+        ))
+    }
+}
+
+/// The old/new pairs of a strings.Replacer
+struct Replacer {
+    pairs: Vec<(String, String)>,
+    // the replacement of the empty string, if any
+    empty: Option<String>,
+}
+
+impl UserData for Replacer {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Replacer {
+    fn new(oldnew: Vec<String>) -> Replacer {
+        let mut pairs: Vec<(String, String)> = vec![];
+        let mut empty = None;
+        for pair in oldnew.chunks(2) {
+            let (old, new) = (&pair[0], &pair[1]);
+            // if an old string appears more than once, the first pair wins
+            if old.is_empty() {
+                empty.get_or_insert_with(|| new.clone());
+            } else if pairs.iter().all(|(o, _)| o != old) {
+                pairs.push((old.clone(), new.clone()));
+            }
+        }
+        Replacer { pairs, empty }
+    }
+
+    /// Replaces non-overlapping matches in a single left-to-right pass,
+    /// at each position the longest matching old string wins
+    fn replace(&self, s: &str) -> String {
+        let mut result = String::with_capacity(s.len());
+        let mut rest = s;
+        loop {
+            let found = self
+                .pairs
+                .iter()
+                .filter(|(old, _)| rest.starts_with(old.as_str()))
+                .max_by_key(|(old, _)| old.len());
+            if let Some((old, new)) = found {
+                result.push_str(new);
+                rest = &rest[old.len()..];
+                continue;
+            }
+            if let Some(new) = &self.empty {
+                result.push_str(new);
+            }
+            match rest.chars().next() {
+                Some(c) => {
+                    result.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+                None => break,
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,99 @@
+extern crate self as goscript_engine;
+use crate::ffi::*;
+use goscript_vm::value::{GosValue, PointerObj, RuntimeResult, UserData};
+use std::any::Any;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// the origin of the monotonic clock
+static START: OnceLock<Instant> = OnceLock::new();
+
+fn monotonic() -> Duration {
+    START.get_or_init(Instant::now).elapsed()
+}
+
+// the instant the monotonic clock reads the nanoseconds in v
+fn deadline(v: &GosValue) -> Instant {
+    let nanos = (*v.as_int64()).max(0) as u64;
+    let start = *START.get_or_init(Instant::now);
+    start
+        .checked_add(Duration::from_nanos(nanos))
+        .unwrap_or_else(|| start + Duration::from_secs(u32::MAX as u64))
+}
+
+#[derive(Ffi)]
+pub struct Time {}
+
+#[ffi_impl]
+impl Time {
+    pub fn new(_v: Vec<GosValue>) -> Time {
+        Time {}
+    }
+
+    /// returns the wall clock as seconds and nanoseconds since the Unix epoch,
+    /// and the monotonic clock in nanoseconds
+    fn ffi_now(&self, _args: Vec<GosValue>) -> Vec<GosValue> {
+        let wall = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        vec![
+            GosValue::Int64(wall.as_secs() as i64),
+            GosValue::Int32(wall.subsec_nanos() as i32),
+            GosValue::Int64(monotonic().as_nanos() as i64),
+        ]
+    }
+
+    fn ffi_runtime_nano(&self, _args: Vec<GosValue>) -> GosValue {
+        GosValue::Int64(monotonic().as_nanos() as i64)
+    }
+
+    /// parks the goroutine until the monotonic clock reaches args[0], the
+    /// other goroutines keep running meanwhile
+    fn ffi_sleep_until(
+        &self,
+        ctx: &mut FfiCallCtx,
+        args: Vec<GosValue>,
+    ) -> Pin<Box<dyn Future<Output = RuntimeResult<Vec<GosValue>>> + '_>> {
+        let sleep = (ctx.sleep_until)(deadline(&args[0]));
+        Box::pin(async move {
+            sleep.wait().await;
+            Ok(vec![])
+        })
+    }
+
+    /// starts a sleep until the monotonic clock reaches args[0], which can be
+    /// cancelled before it's waited for
+    fn ffi_new_sleep(&self, ctx: &mut FfiCallCtx, args: Vec<GosValue>) -> GosValue {
+        let sleep = (ctx.sleep_until)(deadline(&args[0]));
+        GosValue::new_pointer(PointerObj::UserData(Rc::new(StdSleep(sleep))))
+    }
+
+    fn ffi_wait_sleep(
+        &self,
+        _ctx: &mut FfiCallCtx,
+        args: Vec<GosValue>,
+    ) -> Pin<Box<dyn Future<Output = RuntimeResult<Vec<GosValue>>> + '_>> {
+        let sleep = user_data::<StdSleep>(&args[0]).map(|s| s.0.clone());
+        Box::pin(async move {
+            sleep?.wait().await;
+            Ok(vec![])
+        })
+    }
+
+    fn ffi_cancel_sleep(&self, args: Vec<GosValue>) -> RuntimeResult<()> {
+        user_data::<StdSleep>(&args[0])?.0.cancel();
+        Ok(())
+    }
+}
+
+struct StdSleep(Sleep);
+
+impl UserData for StdSleep {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
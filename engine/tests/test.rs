@@ -1,17 +1,47 @@
 #[macro_use]
 extern crate time_test;
+extern crate goscript_codegen as cg;
 extern crate goscript_engine as engine;
+extern crate goscript_parser as fe;
+extern crate goscript_types as types;
+extern crate goscript_vm as vm;
+use goscript_pmacro::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use vm::ffi::{Ffi, FfiCallCtx, FfiCtorResult, GoCall};
+use vm::objects::MemberKind;
+use vm::value::{GosValue, RuntimeResult};
+use vm::vm::{CoroutineState, ScriptError};
 
 fn run(path: &str, trace: bool) -> usize {
-    let cfg = engine::Config {
+    run_with(path, trace, true)
+}
+
+fn run_with(path: &str, trace: bool, wait_for_goroutines: bool) -> usize {
+    let mut engine = engine::Engine::new(config(trace, wait_for_goroutines));
+    engine.run(path)
+}
+
+// writes src to a temporary file and runs it
+fn run_src(name: &str, src: &str) -> usize {
+    let path = format!("{}/{}", env!("CARGO_TARGET_TMPDIR"), name);
+    std::fs::write(&path, src).unwrap();
+    run(&path, false)
+}
+
+fn config(trace: bool, wait_for_goroutines: bool) -> engine::Config {
+    engine::Config {
         work_dir: Some("./".to_owned()),
         base_path: Some("../std/".to_owned()),
         trace_parser: trace,
         trace_checker: trace,
         trace_vm: true,
-    };
-    let mut engine = engine::Engine::new(cfg);
-    engine.run(path)
+        wait_for_goroutines: wait_for_goroutines,
+        ..Default::default()
+    }
 }
 
 #[test]
@@ -100,6 +130,539 @@ fn test_closure4() {
     assert!(err_cnt == 0);
 }
 
+#[test]
+fn test_closure5() {
+    let err_cnt = run("./tests/group1/closure5.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_recursion() {
+    let err_cnt = run("./tests/group1/recursion.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_const() {
+    let err_cnt = run("./tests/group1/const.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_branch() {
+    let err_cnt = run("./tests/group1/branch.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_atomic() {
+    let err_cnt = run("./tests/group1/atomic.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_escape() {
+    let err_cnt = run("./tests/group1/escape.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_builtin_go121() {
+    let err_cnt = run("./tests/group1/builtin_go121.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_alias() {
+    let err_cnt = run("./tests/group1/alias.gos", true);
+    assert!(err_cnt == 0);
+
+    // a defined type is not interchangeable with its underlying type
+    let src = "package main\n\ntype MyInt int\n\nfunc main() {\n    var a MyInt = 3\n    var b int = a\n    _ = b\n}\n";
+    assert_eq!(run_src("defined_type.gos", src), 1);
+}
+
+#[test]
+fn test_elem_method() {
+    let err_cnt = run("./tests/group1/elem_method.gos", true);
+    assert!(err_cnt == 0);
+
+    // map elements are not addressable, so pointer methods can't be called on them
+    let src = "package main\n\ntype C struct{ n int }\n\nfunc (c *C) Inc() { c.n++ }\n\nfunc main() {\n    m := map[int]C{}\n    m[0].Inc()\n}\n";
+    assert_eq!(run_src("map_elem_method.gos", src), 1);
+}
+
+#[test]
+fn test_slice_growth() {
+    let err_cnt = run("./tests/group1/slice_growth.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_iface_copy() {
+    let err_cnt = run("./tests/group1/iface_copy.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_value_copy() {
+    let err_cnt = run("./tests/group1/value_copy.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_analyze() {
+    let engine = engine::Engine::new(config(false, false));
+    let mut result = engine.analyze("./tests/embed/analyze.gos");
+    assert!(result.diagnostics.is_empty());
+    // qualify the types by package name rather than path
+    result.tc_objs.fmt_qualifier = Box::new(|p| p.name().clone().unwrap().into());
+
+    // the top-level declarations of the package and their inferred types
+    let info = result.main_info().unwrap();
+    assert!(!info.ast_files.is_empty());
+    let pkg = &result.tc_objs.pkgs[result.main_pkg.unwrap()];
+    let scope = &result.tc_objs.scopes[*pkg.scope()];
+    let mut decls: Vec<(String, String)> = scope
+        .elems()
+        .iter()
+        .map(|(name, okey)| {
+            let typ = result.tc_objs.lobjs[*okey].typ().unwrap();
+            let typ = types::Displayer::new(&typ, None, Some(&result.tc_objs));
+            (name.clone(), typ.to_string())
+        })
+        .collect();
+    decls.sort();
+    let expected = vec![
+        ("Max", "untyped int"),
+        ("Point", "main.Point"),
+        ("lookup", "map[string]int"),
+        ("main", "func()"),
+        ("names", "[]string"),
+        ("ratio", "untyped float"),
+        ("scale", "func(p main.Point, k float64) (main.Point, bool)"),
+        ("total", "int"),
+    ];
+    let expected: Vec<(String, String)> = expected
+        .into_iter()
+        .map(|(n, t)| (n.to_owned(), t.to_owned()))
+        .collect();
+    assert_eq!(decls, expected);
+
+    // the results are available even if the program doesn't type check
+    let src = "package main\n\nvar x int = \"a\"\n\nfunc main() {\n}\n";
+    let path = format!("{}/analyze_err.gos", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::write(&path, src).unwrap();
+    let result = engine.analyze(&path);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].line, 3);
+    assert!(result.main_info().is_some());
+}
+
+#[test]
+fn test_register_source() {
+    let mut engine = engine::Engine::new(config(false, false));
+    // a package of two files, and a single-file one importing it
+    engine.register_source(
+        "greet/hello.gos",
+        "package greet\n\nfunc Hello(name string) string {\n    return prefix + name\n}\n",
+    );
+    engine.register_source(
+        "greet/prefix.gos",
+        "package greet\n\nconst prefix = \"hi \"\n",
+    );
+    engine.register_source(
+        "app.gos",
+        r#"package main
+
+import (
+    "fmt2"
+    "greet"
+)
+
+func Greet(name string) string {
+    return greet.Hello(name)
+}
+
+func main() {
+    fmt2.Println(greet.Hello("main"))
+}
+"#,
+    );
+    assert!(engine.load("app.gos").is_ok());
+    let re = engine.call_function("main", "Greet", vec![GosValue::new_str("gos".to_owned())]);
+    assert_eq!(re.unwrap(), vec![GosValue::new_str("hi gos".to_owned())]);
+
+    // the diagnostics are reported against the registered names
+    engine.register_source("broken.gos", "package main\n\nfunc main() {\n    x\n}\n");
+    let err = engine.load("broken.gos").unwrap_err();
+    assert!(!err.diagnostics.is_empty());
+    for d in err.diagnostics.iter() {
+        assert_eq!((d.filename.as_str(), d.line), ("broken.gos", 4));
+    }
+}
+
+#[test]
+fn test_multi_pkg() {
+    let err_cnt = run("./tests/group1/multi_pkg.gos", false);
+    assert!(err_cnt == 0);
+
+    // the host can call into the imported packages too
+    let mut engine = engine::Engine::new(config(false, false));
+    assert!(engine.load("./tests/group1/multi_pkg.gos").is_ok());
+    let re = engine.call_function("pkgb", "Exported", vec![]);
+    assert_eq!(re.unwrap(), vec![GosValue::Int(420)]);
+}
+
+#[test]
+fn test_unused() {
+    // unused imports and local variables are errors, like in Go
+    let mut engine = engine::Engine::new(config(false, false));
+    engine.register_source(
+        "unused.gos",
+        r#"package main
+
+import "fmt2"
+import s "strings"
+
+func main() {
+    x := 1
+    var y int
+    a, b := 1, 2
+    _ = a
+}
+"#,
+    );
+    let err = engine.load("unused.gos").unwrap_err();
+    let msgs: Vec<(usize, &str, engine::Severity)> = err
+        .diagnostics
+        .iter()
+        .map(|d| (d.line, d.msg.as_str(), d.severity))
+        .collect();
+    let soft = engine::Severity::SoftError;
+    assert_eq!(
+        msgs,
+        vec![
+            (3, "fmt2 imported but not used", soft),
+            (4, "strings imported but not used as s", soft),
+            (7, "x declared but not used", soft),
+            (8, "y declared but not used", soft),
+            (9, "b declared but not used", soft),
+        ]
+    );
+
+    // blank imports and variables only assigned to _ are fine
+    engine.register_source(
+        "used.gos",
+        r#"package main
+
+import _ "strings"
+
+var unusedGlobal int
+
+func main() {
+    x := 1
+    _ = x
+    var _ = 2
+    _, b := 1, 2
+    _ = b
+}
+"#,
+    );
+    assert!(engine.load("used.gos").is_ok());
+}
+
+#[test]
+fn test_iota_flags() {
+    let err_cnt = run("./tests/group1/iota_flags.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_select_break() {
+    let err_cnt = run("./tests/group1/select_break.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_select_recv_target() {
+    let err_cnt = run("./tests/group1/select_recv_target.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_select_comma_ok() {
+    let err_cnt = run("./tests/group1/select_comma_ok.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_coroutine() {
+    let mut engine = engine::Engine::new(config(false, false));
+    assert!(engine.load("./tests/embed/coroutine.gos").is_ok());
+
+    // the first resume starts the call, the next ones answer the yields
+    let mut co = engine
+        .start_function("main", "Script", vec![GosValue::Int(1)])
+        .unwrap();
+    let state = co.resume(GosValue::new_nil()).unwrap();
+    assert_eq!(state, CoroutineState::Yielded(GosValue::Int(1)));
+    let state = co.resume(GosValue::Int(10)).unwrap();
+    assert_eq!(
+        state,
+        CoroutineState::Yielded(GosValue::new_str("second".to_owned()))
+    );
+    let state = co.resume(GosValue::new_str("abc".to_owned())).unwrap();
+    assert_eq!(state, CoroutineState::Returned(vec![GosValue::Int(13)]));
+    assert!(co.resume(GosValue::new_nil()).is_err());
+
+    // a goroutine can yield too, nil passes through both ways
+    let mut co = engine.start_function("main", "Worker", vec![]).unwrap();
+    let state = co.resume(GosValue::new_nil()).unwrap();
+    assert!(matches!(state, CoroutineState::Yielded(GosValue::Nil(_))));
+    let state = co.resume(GosValue::new_nil()).unwrap();
+    assert_eq!(
+        state,
+        CoroutineState::Returned(vec![GosValue::new_str("nil".to_owned())])
+    );
+
+    // the value a resume answers with has to match what the script expects
+    let mut co = engine
+        .start_function("main", "Script", vec![GosValue::Int(1)])
+        .unwrap();
+    co.resume(GosValue::new_nil()).unwrap();
+    assert!(matches!(
+        co.resume(GosValue::Bool(true)),
+        Err(ScriptError::Panic(_))
+    ));
+
+    // yielding is a panic if the call is not a coroutine
+    let re = engine.call_function("main", "Script", vec![GosValue::Int(1)]);
+    assert_eq!(
+        re,
+        Err(ScriptError::Panic(
+            "cannot yield, not running as a coroutine".to_owned()
+        ))
+    );
+}
+
+#[derive(Ffi)]
+pub struct Gate {
+    opened: std::cell::Cell<bool>,
+    // the number of goroutines parked in wait
+    waiting: std::cell::Cell<isize>,
+}
+
+#[ffi_impl]
+impl Gate {
+    pub fn new(_v: Vec<GosValue>) -> Gate {
+        Gate {
+            opened: std::cell::Cell::new(false),
+            waiting: std::cell::Cell::new(0),
+        }
+    }
+
+    fn ffi_open(&self, _args: Vec<GosValue>) {
+        self.opened.set(true);
+    }
+
+    fn ffi_waiting(&self, _args: Vec<GosValue>) -> GosValue {
+        GosValue::Int(self.waiting.get())
+    }
+
+    /// parks the goroutine until the gate is opened, returns the number of
+    /// times it was polled
+    async fn ffi_wait(&self, _args: Vec<GosValue>) -> RuntimeResult<GosValue> {
+        let mut polls = 0;
+        self.waiting.set(self.waiting.get() + 1);
+        while !self.opened.get() {
+            if polls == 1000 {
+                return Err("the gate was never opened".to_owned());
+            }
+            polls += 1;
+            futures_lite::future::yield_now().await;
+        }
+        self.waiting.set(self.waiting.get() - 1);
+        Ok(GosValue::Int(polls))
+    }
+
+    /// yields args[0] times, then returns it and true
+    async fn ffi_poll(&self, args: Vec<GosValue>) -> Vec<GosValue> {
+        let n = *args[0].as_int();
+        for _ in 0..n {
+            futures_lite::future::yield_now().await;
+        }
+        vec![GosValue::Int(n), GosValue::Bool(true)]
+    }
+}
+
+#[test]
+fn test_async_ffi() {
+    let mut engine = engine::Engine::new(config(false, false));
+    Gate::register(&mut engine);
+    assert_eq!(engine.run("./tests/embed/async_ffi.gos"), 0);
+}
+
+#[derive(Ffi)]
+pub struct Calc {
+    calls: std::cell::Cell<isize>,
+}
+
+#[ffi_impl]
+impl Calc {
+    pub fn new(_v: Vec<GosValue>) -> Calc {
+        Calc {
+            calls: std::cell::Cell::new(0),
+        }
+    }
+
+    fn ffi_add(&self, a: GosValue, b: GosValue) -> GosValue {
+        self.calls.set(self.calls.get() + 1);
+        GosValue::Int(*a.as_int() + *b.as_int())
+    }
+
+    fn ffi_reset(&self) {
+        self.calls.set(0);
+    }
+
+    fn ffi_count(&self, _args: Vec<GosValue>) -> GosValue {
+        GosValue::Int(self.calls.get())
+    }
+}
+
+#[test]
+fn test_ffi_arity() {
+    let mut engine = engine::Engine::new(config(false, false));
+    Calc::register(&mut engine);
+    assert!(engine.load("./tests/embed/ffi_arity.gos").is_ok());
+    let call = |name| engine.call_function("main", name, vec![]).unwrap();
+    assert_eq!(call("Good"), vec![GosValue::Int(4)]);
+
+    // calls not matching the methods are recoverable panics
+    let recovered = |name| match &call(name)[0] {
+        GosValue::Interface(i) => i
+            .borrow()
+            .underlying_value()
+            .map(|m| m.as_str().as_str().to_owned()),
+        _ => None,
+    };
+    let msg = |m: &str| Some(m.to_owned());
+    assert_eq!(
+        recovered("Add"),
+        msg("ffi method add takes 2 arguments, got 1")
+    );
+    assert_eq!(
+        recovered("Reset"),
+        msg("ffi method reset returned 0 values, expected 1")
+    );
+    // methods taking a Vec take any number of arguments
+    assert_eq!(recovered("Count"), None);
+    assert_eq!(recovered("Missing"), msg("no ffi method named missing"));
+}
+
+#[test]
+fn test_str_slice() {
+    let err_cnt = run("./tests/group1/str_slice.gos", false);
+    assert!(err_cnt == 0);
+
+    // substrings share the data of the string instead of copying it
+    let mut engine = engine::Engine::new(config(false, false));
+    engine.register_source(
+        "substr.gos",
+        r#"package main
+
+func Substr(s string, i, j int) string {
+    return s[i:j]
+}
+
+func main() {
+}
+"#,
+    );
+    assert!(engine.load("substr.gos").is_ok());
+    let s = GosValue::new_str("x".repeat(1 << 20));
+    let args = vec![s.clone(), GosValue::Int(10), GosValue::Int(20)];
+    let rets = engine.call_function("main", "Substr", args).unwrap();
+    let sub = rets[0].as_str().as_str();
+    assert_eq!(sub.len(), 10);
+    assert_eq!(sub.as_ptr(), s.as_str().as_str()[10..].as_ptr());
+}
+
+#[test]
+fn test_str_index() {
+    let err_cnt = run("./tests/group1/str_index.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_str_append() {
+    let err_cnt = run("./tests/group1/str_append.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_number_literals() {
+    let err_cnt = run("./tests/group1/number_literals.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_raw_string() {
+    let err_cnt = run("./tests/group1/raw_string.gos", false);
+    assert!(err_cnt == 0);
+
+    // carriage returns are dropped from raw strings
+    let src = "package main\n\nfunc main() {\n    s := `a\r\nb`\n    assert(s == \"a\\nb\")\n}\n";
+    assert_eq!(run_src("raw_string_cr.gos", src), 0);
+}
+
+#[test]
+fn test_rune_literals() {
+    let err_cnt = run("./tests/group1/rune_literals.gos", false);
+    assert!(err_cnt == 0);
+
+    // malformed rune literals are rejected rather than crashing the scanner
+    let src = |lit| format!("package main\n\nfunc main() {{\n    _ = {}\n}}\n", lit);
+    assert!(run_src("rune_empty.gos", &src("''")) > 0);
+    assert!(run_src("rune_two.gos", &src("'ab'")) > 0);
+    assert!(run_src("rune_octal.gos", &src("'\\400'")) > 0);
+    assert!(run_src("rune_surrogate.gos", &src("'\\uD800'")) > 0);
+}
+
+#[test]
+fn test_range_target() {
+    let err_cnt = run("./tests/group1/range_target.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_send_block() {
+    let err_cnt = run("./tests/group1/send_block.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_select_send() {
+    let err_cnt = run("./tests/group1/select_send.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_select_timeout() {
+    let err_cnt = run("./tests/group1/select_timeout.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_timer_stop() {
+    let start = std::time::Instant::now();
+    let err_cnt = run_with("./tests/group1/timer_stop.gos", false, true);
+    assert!(err_cnt == 0);
+    // the program waits for its goroutines, the ones of the stopped timers
+    // must not wait for their deadlines an hour away
+    assert!(start.elapsed() < std::time::Duration::from_secs(60));
+}
+
 #[test]
 fn test_func1() {
     let err_cnt = run("./tests/group1/func1.gos", true);
@@ -130,6 +693,12 @@ fn test_map1() {
     assert!(err_cnt == 0);
 }
 
+#[test]
+fn test_map2() {
+    let err_cnt = run("./tests/group1/map2.gos", false);
+    assert!(err_cnt == 0);
+}
+
 #[test]
 fn test_pkg() {
     let err_cnt = run("./tests/group1/pkg.gos", true);
@@ -178,6 +747,18 @@ fn test_interface2() {
     assert!(err_cnt == 0);
 }
 
+#[test]
+fn test_interface_embed() {
+    let err_cnt = run("./tests/group1/interface_embed.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_empty_iface() {
+    let err_cnt = run("./tests/group1/empty_iface.gos", true);
+    assert!(err_cnt == 0);
+}
+
 #[test]
 fn test_initorder() {
     let err_cnt = run("./tests/group1/initorder.gos", true);
@@ -190,6 +771,24 @@ fn test_switch() {
     assert!(err_cnt == 0);
 }
 
+#[test]
+fn test_generics() {
+    let err_cnt = run("./tests/group1/generics.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_generic_types() {
+    let err_cnt = run("./tests/group1/generic_types.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_generic_constraints() {
+    let err_cnt = run("./tests/group1/generic_constraints.gos", true);
+    assert!(err_cnt == 0);
+}
+
 #[test]
 fn test_typeswitch() {
     let err_cnt = run("./tests/group1/typeswitch.gos", true);
@@ -262,6 +861,33 @@ fn test_init_func() {
     assert!(err_cnt == 0);
 }
 
+#[test]
+fn test_init_order() {
+    let err_cnt = run("./tests/group1/init_order.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_init_cycle() {
+    // the cycle is reported at a, followed by the path it takes
+    let mut engine = engine::Engine::new(config(false, false));
+    let err = engine.load("./tests/group1/init_cycle.gos").unwrap_err();
+    let msgs: Vec<(usize, usize, &str)> = err
+        .diagnostics
+        .iter()
+        .map(|d| (d.line, d.column, d.msg.as_str()))
+        .collect();
+    assert_eq!(
+        msgs,
+        vec![
+            (4, 5, "initialization cycle for a"),
+            (4, 5, "\ta refers to"),
+            (5, 5, "\tb refers to"),
+            (4, 5, "\ta")
+        ]
+    );
+}
+
 #[test]
 fn test_complex() {
     let err_cnt = run("./tests/group1/complex.gos", true);
@@ -338,6 +964,30 @@ fn test_std_sort() {
     assert!(err_cnt == 0);
 }
 
+#[test]
+fn test_std_strings() {
+    time_test!();
+
+    let err_cnt = run("./tests/std/strings.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_std_list() {
+    time_test!();
+
+    let err_cnt = run("./tests/std/list.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_std_runtime() {
+    time_test!();
+
+    let err_cnt = run("./tests/std/runtime.gos", false);
+    assert!(err_cnt == 0);
+}
+
 #[test]
 fn test_std_temp() {
     time_test!();
@@ -345,3 +995,840 @@ fn test_std_temp() {
     let err_cnt = run("./tests/std/temp.gos", false);
     assert!(err_cnt == 0);
 }
+
+#[test]
+fn test_printf() {
+    let err_cnt = run("./tests/group2/printf.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_println() {
+    let err_cnt = run("./tests/group2/println.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_enum_print() {
+    let err_cnt = run("./tests/group2/enum_print.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_printf_family() {
+    let err_cnt = run("./tests/group2/printf_family.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_named() {
+    let err_cnt = run("./tests/group1/named.gos", true);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_goroutine_exit() {
+    // returns when main returns, the other goroutines are abandoned
+    let err_cnt = run_with("./tests/group1/goroutine_exit.gos", false, false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_goroutine_exit_wait() {
+    // the assert in the second goroutine fails
+    let err_cnt = run_with("./tests/group1/goroutine_exit.gos", false, true);
+    assert!(err_cnt == 1);
+
+    // embedders that only set the paths get the waiting run
+    let cfg = engine::Config {
+        work_dir: Some("./".to_owned()),
+        base_path: Some("../std/".to_owned()),
+        ..Default::default()
+    };
+    let mut engine = engine::Engine::new(cfg);
+    assert_eq!(engine.run("./tests/group1/goroutine_exit.gos"), 1);
+}
+
+#[test]
+fn test_goroutine_panic() {
+    let err_cnt = run("./tests/group1/goroutine_panic.gos", false);
+    assert!(err_cnt == 0);
+}
+
+#[test]
+fn test_goroutine_abort() {
+    let err_cnt = run("./tests/group1/goroutine_abort.gos", false);
+    assert!(err_cnt == 1);
+}
+
+#[test]
+fn test_unrecovered_panic() {
+    // any unrecovered panic fails the run, not only a failed assert
+    let src = "package main\n\nfunc main() {\n    s := []int{1}\n    i := 5\n    _ = s[i]\n}\n";
+    assert_eq!(run_src("unrecovered_index.gos", src), 1);
+    let src = "package main\n\nfunc main() {\n    panic(\"boom\")\n}\n";
+    assert_eq!(run_src("unrecovered_panic.gos", src), 1);
+    let src =
+        "package main\n\nfunc main() {\n    defer func() { recover() }()\n    panic(\"boom\")\n}\n";
+    assert_eq!(run_src("recovered_panic.gos", src), 0);
+}
+
+#[test]
+fn test_call_function() {
+    let mut engine = engine::Engine::new(config(false, false));
+    assert!(engine.call_function("main", "Add", vec![]).is_err());
+    assert!(engine.load("./tests/embed/call.gos").is_ok());
+
+    let add = |a, b| engine.call_function("main", "Add", vec![GosValue::Int(a), GosValue::Int(b)]);
+    assert_eq!(add(1, 2).unwrap(), vec![GosValue::Int(3)]);
+    assert_eq!(add(-5, 3).unwrap(), vec![GosValue::Int(-2)]);
+    // the package is initialized once, and keeps its state between calls
+    let calls = engine.call_function("main", "Calls", vec![]).unwrap();
+    assert_eq!(calls, vec![GosValue::Int(112)]);
+
+    let rets = engine.call_function("main", "DivMod", vec![GosValue::Int(7), GosValue::Int(2)]);
+    assert_eq!(rets.unwrap(), vec![GosValue::Int(3), GosValue::Int(1)]);
+    let rets = engine.call_function("main", "Greet", vec![GosValue::new_str("go".to_owned())]);
+    assert_eq!(
+        rets.unwrap(),
+        vec![GosValue::new_str("hello go".to_owned())]
+    );
+
+    // arguments to interface{} parameters are converted
+    let describe = |v| engine.call_function("main", "Describe", vec![v]).unwrap();
+    assert_eq!(
+        describe(GosValue::Int(1)),
+        vec![GosValue::new_str("int".to_owned())]
+    );
+    assert_eq!(
+        describe(GosValue::new_str("s".to_owned())),
+        vec![GosValue::new_str("string".to_owned())]
+    );
+    assert_eq!(
+        describe(GosValue::new_nil()),
+        vec![GosValue::new_str("nil".to_owned())]
+    );
+
+    let err = |name, args| engine.call_function("main", name, args).unwrap_err();
+    let call_err = |msg: &str| ScriptError::Call(msg.to_owned());
+    assert_eq!(
+        err("Add", vec![GosValue::Int(1)]),
+        call_err("main.Add: expected 2 arguments, got 1")
+    );
+    assert_eq!(
+        err("Add", vec![GosValue::Int(1), GosValue::Float64(1.0.into())]),
+        call_err("main.Add: argument 1: expected Int, got Float64")
+    );
+    assert_eq!(
+        err("NotAFunc", vec![]),
+        call_err("main.NotAFunc is not a function")
+    );
+    assert_eq!(
+        err("Missing", vec![]),
+        call_err("main.Missing is not a function")
+    );
+    assert_eq!(
+        engine.call_function("nopkg", "Add", vec![]).unwrap_err(),
+        call_err("package not found: nopkg")
+    );
+    assert_eq!(
+        err("Fail", vec![GosValue::new_str("boom".to_owned())]),
+        ScriptError::Panic("boom".to_owned())
+    );
+}
+
+#[test]
+fn test_panic_value() {
+    let mut engine = engine::Engine::new(config(false, false));
+    assert!(engine.load("./tests/embed/panic_value.gos").is_ok());
+
+    let rets = engine.call_function("main", "Recover", vec![]).unwrap();
+    assert_eq!(rets, vec![GosValue::new_str("ok".to_owned())]);
+
+    // unrecovered panics print errors and Stringers with their methods
+    let err = |name| engine.call_function("main", name, vec![]).unwrap_err();
+    let panic = |msg: &str| ScriptError::Panic(msg.to_owned());
+    assert_eq!(err("PanicInt"), panic("42"));
+    assert_eq!(err("PanicStruct"), panic("{1 a}"));
+    assert_eq!(err("PanicError"), panic("boom"));
+    assert_eq!(err("PanicMyErr"), panic("my error"));
+    assert_eq!(err("PanicStringer"), panic("red"));
+}
+
+#[test]
+fn test_assert() {
+    let mut engine = engine::Engine::new(config(false, false));
+    engine.register_source(
+        "assert.gos",
+        r#"package main
+
+func Pass(x int) {
+    assert(x == 1)
+    assert(x == 1, "x is 1")
+}
+
+func Fail(x int) {
+    assert(x == 1)
+}
+
+func FailMsg(x int) {
+    assert(x == 1, "x is not 1")
+}
+
+func Recover(x int) (r interface{}) {
+    defer func() {
+        r = recover()
+    }()
+    assert(x == 1, "recovered")
+    return nil
+}
+
+func main() {
+}
+"#,
+    );
+    assert!(engine.load("assert.gos").is_ok());
+    let call = |name, x| engine.call_function("main", name, vec![GosValue::Int(x)]);
+    assert_eq!(call("Pass", 1), Ok(vec![]));
+    assert_eq!(call("Recover", 1), Ok(vec![GosValue::new_nil()]));
+
+    // a failed assertion panics with its position and the message
+    let panic = |msg: &str| Err(ScriptError::Panic(msg.to_owned()));
+    assert_eq!(call("Fail", 2), panic("assertion failed at assert.gos:9:5"));
+    assert_eq!(
+        call("FailMsg", 2),
+        panic("assertion failed at assert.gos:13:5: x is not 1")
+    );
+    let rets = call("Recover", 2).unwrap();
+    assert_eq!(
+        rets[0].as_interface().borrow().underlying_value(),
+        Some(&GosValue::new_str(
+            "assertion failed at assert.gos:20:5: recovered".to_owned()
+        ))
+    );
+
+    // the message is a string, and there are at most two arguments
+    engine.register_source(
+        "bad_assert.gos",
+        r#"package main
+
+func main() {
+    x := 1
+    assert(x == 1, 2)
+    assert(x == 1, "a", "b")
+    assert(x)
+}
+"#,
+    );
+    let err = engine.load("bad_assert.gos").unwrap_err();
+    let lines: Vec<usize> = err.diagnostics.iter().map(|d| d.line).collect();
+    assert_eq!(lines, vec![5, 6, 7]);
+}
+
+#[test]
+fn test_package_members() {
+    let mut engine = engine::Engine::new(config(false, false));
+    assert!(engine.package_members("main").is_err());
+    assert!(engine.load("./tests/embed/members.gos").is_ok());
+
+    let mut members = engine.package_members("main").unwrap();
+    members.sort();
+    let expected = vec![
+        ("Version", MemberKind::Const),
+        ("limit", MemberKind::Const),
+        ("Count", MemberKind::Var),
+        ("names", MemberKind::Var),
+        ("Register", MemberKind::Func),
+        ("main", MemberKind::Func),
+        ("Level", MemberKind::Type),
+        ("Plugin", MemberKind::Type),
+    ];
+    let mut expected: Vec<(String, MemberKind)> = expected
+        .into_iter()
+        .map(|(n, k)| (n.to_owned(), k))
+        .collect();
+    expected.sort();
+    assert_eq!(members, expected);
+
+    // the exported functions are the entry points a host can call
+    let entry: Vec<&str> = members
+        .iter()
+        .filter(|(n, k)| *k == MemberKind::Func && n.starts_with(char::is_uppercase))
+        .map(|(n, _)| n.as_str())
+        .collect();
+    assert_eq!(entry, vec!["Register"]);
+    let re = engine.call_function("main", "Register", vec![GosValue::new_str("a".to_owned())]);
+    assert_eq!(re.unwrap(), vec![GosValue::Int(2)]);
+
+    assert!(engine.package_members("fmt2").is_ok());
+    assert_eq!(
+        engine.package_members("nope").unwrap_err(),
+        "package nope not found"
+    );
+}
+
+#[test]
+fn test_build_values() {
+    let mut engine = engine::Engine::new(config(false, false));
+    assert!(engine.type_meta("main", "Point").is_err());
+    assert!(engine.load("./tests/embed/values.gos").is_ok());
+
+    let md = engine.metadata().unwrap();
+    let (mint, mstr, miface) = (md.mint, md.mstr, md.empty_iface);
+    // slices and maps add to the metadata, so they are built first
+    let ints = (1..=4).map(GosValue::Int).collect();
+    let ints = engine.new_slice(mint, ints).unwrap();
+    let ifaces = vec![GosValue::Int(1), GosValue::new_nil()];
+    let ifaces = engine.new_slice(miface, ifaces).unwrap();
+    let strs = vec![GosValue::new_str("x".to_owned())];
+    let strs = engine.new_slice(mstr, strs).unwrap();
+    let pairs = vec![
+        (GosValue::new_str("a".to_owned()), GosValue::Int(1)),
+        (GosValue::new_str("b".to_owned()), GosValue::Int(2)),
+    ];
+    let m = engine.new_map(mstr, mint, pairs).unwrap();
+    assert_eq!(
+        engine
+            .new_slice(mint, vec![GosValue::new_str("x".to_owned())])
+            .unwrap_err(),
+        "element 0: expected Int, got Str"
+    );
+
+    let point = engine.type_meta("main", "Point").unwrap();
+    let new_point = |x, y, name: &str| {
+        let fields = vec![
+            GosValue::Int(x),
+            GosValue::Int(y),
+            GosValue::new_str(name.to_owned()),
+        ];
+        engine.new_struct(point, fields).unwrap()
+    };
+    let p = new_point(3, 4, "p");
+    let call = |name, args| engine.call_function("main", name, args).unwrap();
+    assert_eq!(call("SumPoint", vec![p.clone()]), vec![GosValue::Int(7)]);
+    assert_eq!(
+        call("PointName", vec![p.clone()]),
+        vec![GosValue::new_str("p".to_owned())]
+    );
+
+    // a struct returned by Go code can be read back
+    let moved = call("Moved", vec![p.clone(), GosValue::Int(10)]);
+    let fields = |v: &GosValue| v.unwrap_named_ref().as_struct().0.borrow().fields.clone();
+    assert_eq!(fields(&moved[0])[0], GosValue::Int(13));
+    // arguments are passed by value
+    assert_eq!(fields(&p)[0], GosValue::Int(3));
+
+    let line = engine.type_meta("main", "Line").unwrap();
+    let l = engine
+        .new_struct(line, vec![new_point(1, 1, "a"), new_point(4, 5, "b")])
+        .unwrap();
+    assert_eq!(call("Length", vec![l]), vec![GosValue::Int(7)]);
+    // struct fields are checked against their full types
+    let l = engine
+        .new_struct(line, vec![new_point(0, 0, "a"), new_point(1, 1, "b")])
+        .unwrap();
+    assert_eq!(
+        engine
+            .new_struct(line, vec![l, new_point(1, 1, "b")])
+            .unwrap_err(),
+        "field 0: mismatched Named types"
+    );
+
+    assert_eq!(call("Sum", vec![ints]), vec![GosValue::Int(10)]);
+    assert_eq!(call("Count", vec![ifaces]), vec![GosValue::Int(2)]);
+
+    let lookup = |key: &str| {
+        let args = vec![m.clone(), GosValue::new_str(key.to_owned())];
+        engine.call_function("main", "Lookup", args).unwrap()
+    };
+    assert_eq!(lookup("b"), vec![GosValue::Int(2)]);
+    assert_eq!(lookup("c"), vec![GosValue::Int(0)]);
+
+    // the arguments have to match the parameter types, not only their kinds
+    let err = |name, args| engine.call_function("main", name, args).unwrap_err();
+    let call_err = |msg: &str| ScriptError::Call(msg.to_owned());
+    assert_eq!(
+        err("Length", vec![p.clone()]),
+        call_err("main.Length: argument 0: mismatched Named types")
+    );
+    assert_eq!(
+        err("Sum", vec![strs]),
+        call_err("main.Sum: argument 0: mismatched Slice types")
+    );
+
+    assert_eq!(
+        engine
+            .new_struct(point, vec![GosValue::Int(1)])
+            .unwrap_err(),
+        "expected 3 fields, got 1"
+    );
+    assert_eq!(
+        engine.new_struct(mint, vec![]).unwrap_err(),
+        "not a struct type"
+    );
+    assert_eq!(
+        engine.type_meta("main", "Sum").unwrap_err(),
+        "main.Sum is not a type"
+    );
+}
+
+#[test]
+fn test_extract_values() {
+    let mut engine = engine::Engine::new(config(false, false));
+    assert!(engine.load("./tests/embed/values.gos").is_ok());
+    let mint = engine.metadata().unwrap().mint;
+    let s = engine
+        .new_slice(mint, vec![GosValue::Int(1), GosValue::Int(2)])
+        .unwrap();
+
+    let rets = engine.call_function("main", "Stats", vec![s]).unwrap();
+    assert_eq!(rets[0].as_i64(), Ok(3));
+    assert_eq!(rets[1].as_f64(), Ok(1.5));
+    assert_eq!(rets[2].as_string_lossy(), Ok("stats".to_owned()));
+    // interfaces are unwrapped
+    assert_eq!(rets[3].as_i64(), Ok(1));
+
+    let rets = engine.call_function("main", "Values", vec![]).unwrap();
+    // named types are unwrapped
+    assert_eq!(rets[0].as_f64(), Ok(36.6));
+    assert_eq!(rets[1].as_string_lossy(), Ok("h\u{fffd}i".to_owned()));
+    let strs: Vec<String> = rets[2]
+        .try_into_vec()
+        .unwrap()
+        .iter()
+        .map(|x| x.as_string_lossy().unwrap())
+        .collect();
+    assert_eq!(strs, vec!["a", "b"]);
+    assert_eq!(rets[3].try_into_vec(), Ok(vec![]));
+
+    // mismatched types are errors rather than panics
+    assert_eq!(
+        rets[0].as_i64().unwrap_err(),
+        "expected an integer, got Float64"
+    );
+    assert_eq!(
+        rets[4].as_i64().unwrap_err(),
+        "9223372036854775808 overflows i64"
+    );
+    assert_eq!(
+        rets[4].as_f64().unwrap_err(),
+        "expected a float, got Uint64"
+    );
+    assert_eq!(
+        rets[2].as_string_lossy().unwrap_err(),
+        "expected a string, got Array"
+    );
+    assert_eq!(
+        rets[1].try_into_vec().unwrap()[0]
+            .try_into_vec()
+            .unwrap_err(),
+        "expected a slice, got Uint8"
+    );
+}
+
+#[test]
+fn test_instruction_budget() {
+    let mut cfg = config(false, true);
+    cfg.instruction_budget = Some(100_000);
+    let mut engine = engine::Engine::new(cfg);
+    assert!(engine.load("./tests/embed/budget.gos").is_ok());
+
+    let call = |name, args| engine.call_function("main", name, args);
+    assert_eq!(call("Spin", vec![]), Err(ScriptError::Budget));
+    assert_eq!(call("SpinRecover", vec![]), Err(ScriptError::Budget));
+    // the budget is per call
+    assert_eq!(
+        call("Sum", vec![GosValue::Int(100)]),
+        Ok(vec![GosValue::Int(4950)])
+    );
+    assert_eq!(
+        call("Sum", vec![GosValue::Int(100_000)]),
+        Err(ScriptError::Budget)
+    );
+
+    // goroutines taking turns share the budget, so they can't do more work
+    // than a single goroutine
+    let rounds = |name| {
+        assert_eq!(call(name, vec![]), Err(ScriptError::Budget));
+        *call("Rounds", vec![]).unwrap()[0].as_int()
+    };
+    let solo = rounds("Solo");
+    let ping_pong = rounds("PingPong");
+    assert!(solo > 0 && ping_pong > 0);
+    assert!(ping_pong <= solo, "{} rounds, {} alone", ping_pong, solo);
+
+    // both goroutines spin forever
+    let mut cfg = config(false, true);
+    cfg.instruction_budget = Some(100_000);
+    let mut engine = engine::Engine::new(cfg);
+    assert_eq!(engine.run("./tests/embed/budget.gos"), 1);
+}
+
+#[test]
+fn test_max_heap_objects() {
+    let mut cfg = config(false, false);
+    cfg.max_heap_objects = Some(1000);
+    let mut engine = engine::Engine::new(cfg);
+    assert!(engine.load("./tests/embed/alloc.gos").is_ok());
+
+    let call = |name, n| engine.call_function("main", name, vec![GosValue::Int(n)]);
+    assert_eq!(call("Grow", 100), Ok(vec![GosValue::Int(100)]));
+    assert_eq!(call("Grow", 100_000), Err(ScriptError::Memory));
+    // only the objects alive at the same time count
+    assert_eq!(call("Churn", 100_000), Ok(vec![GosValue::Int(100_000)]));
+    assert_eq!(call("Grow", 500), Ok(vec![GosValue::Int(500)]));
+
+    let mut cfg = config(false, false);
+    cfg.max_heap_objects = Some(1000);
+    let mut engine = engine::Engine::new(cfg);
+    assert_eq!(engine.run("./tests/embed/alloc.gos"), 1);
+}
+
+#[test]
+fn test_max_call_depth() {
+    let mut cfg = config(false, false);
+    cfg.max_call_depth = Some(1000);
+    let mut engine = engine::Engine::new(cfg);
+    assert!(engine.load("./tests/embed/depth.gos").is_ok());
+
+    let call = |name, args| engine.call_function("main", name, args);
+    assert_eq!(
+        call("Depth", vec![GosValue::Int(900)]),
+        Ok(vec![GosValue::Int(900)])
+    );
+    assert_eq!(
+        call("Depth", vec![GosValue::Int(2000)]),
+        Err(ScriptError::Panic("stack overflow".to_owned()))
+    );
+    assert_eq!(
+        call("Forever", vec![GosValue::Int(0)]),
+        Err(ScriptError::Panic("stack overflow".to_owned()))
+    );
+    assert_eq!(
+        call("Recovered", vec![]),
+        Ok(vec![GosValue::new_str("stack overflow".to_owned())])
+    );
+
+    let mut cfg = config(false, false);
+    cfg.max_call_depth = Some(1000);
+    let mut engine = engine::Engine::new(cfg);
+    assert_eq!(engine.run("./tests/embed/depth.gos"), 0);
+}
+
+#[test]
+fn test_large_func() {
+    // a function body big enough that its jumps span tens of thousands of instructions
+    let mut src =
+        String::from("package main\n\nfunc big(n int) int {\n    s := 0\n    if n > 0 {\n");
+    for i in 0..20000 {
+        src.push_str(&format!("        s += {}\n", i % 7));
+    }
+    src.push_str("    } else {\n        s = -1\n    }\n    for i := 0; i < n; i++ {\n");
+    for _ in 0..2000 {
+        src.push_str("        s++\n");
+    }
+    src.push_str("    }\n    for range []int{1, 2} {\n");
+    for _ in 0..2000 {
+        src.push_str("        s--\n");
+    }
+    src.push_str("    }\n    return s\n}\n\nfunc main() {\n");
+    src.push_str("    assert(big(0) == -1 - 4000)\n    assert(big(3) == 59997 + 6000 - 4000)\n}\n");
+    assert_eq!(run_src("large_func.gos", &src), 0);
+
+    // too many values for one assignment is a compile error rather than a crash
+    let names: Vec<String> = (0..300).map(|i| format!("v{}", i)).collect();
+    let zeros = vec!["0"; 300];
+    let src = format!(
+        "package main\n\nvar {} int\n\nfunc main() {{\n    {} = {}\n}}\n",
+        names.join(", "),
+        names.join(", "),
+        zeros.join(", ")
+    );
+    assert_eq!(run_src("large_assign.gos", &src), 1);
+}
+
+#[test]
+fn test_fallthrough_misplaced() {
+    let src = |body| format!("package main\n\nfunc main() {{\n    x := 1\n{}}}\n", body);
+    // not the last statement of the clause
+    let body =
+        "    switch x {\n    case 1:\n        fallthrough\n        x++\n    case 2:\n    }\n";
+    assert_eq!(run_src("fallthrough1.gos", &src(body)), 1);
+    // in the final clause
+    let body = "    switch x {\n    case 1:\n    case 2:\n        fallthrough\n    }\n";
+    assert_eq!(run_src("fallthrough2.gos", &src(body)), 1);
+    // in a type switch
+    let body = "    var i interface{} = x\n    switch i.(type) {\n    case int:\n        fallthrough\n    default:\n    }\n";
+    assert_eq!(run_src("fallthrough3.gos", &src(body)), 1);
+    // in a nested block
+    let body = "    switch x {\n    case 1:\n        {\n            fallthrough\n        }\n    case 2:\n    }\n";
+    assert_eq!(run_src("fallthrough4.gos", &src(body)), 1);
+}
+
+#[test]
+fn test_const_overflow() {
+    let src = |decl| format!("package main\n\n{}\n\nfunc main() {{\n}}\n", decl);
+    assert_eq!(run_src("const1.gos", &src("const a uint8 = 256")), 1);
+    assert_eq!(run_src("const2.gos", &src("const a int8 = -129")), 1);
+    assert_eq!(
+        run_src("const3.gos", &src("const a int8 = 100\nconst b = a * 2")),
+        1
+    );
+    assert_eq!(run_src("const4.gos", &src("const a uint = -1")), 1);
+    assert_eq!(run_src("const5.gos", &src("const a int32 = 1.5")), 1);
+    assert_eq!(
+        run_src("const6.gos", &src("const a = 1 << 40\nvar b int32 = a")),
+        1
+    );
+}
+
+#[test]
+fn test_diagnostics() {
+    let path = format!("{}/diagnostics.gos", env!("CARGO_TARGET_TMPDIR"));
+    let src =
+        "package main\n\nfunc main() {\n    var a int = \"s\"\n    b := 1\n    _ = a + undefined\n}\n";
+    std::fs::write(&path, src).unwrap();
+    let mut engine = engine::Engine::new(config(false, false));
+    let err = engine.load(&path).unwrap_err();
+    let lines: Vec<(usize, engine::Severity)> = err
+        .diagnostics
+        .iter()
+        .map(|d| (d.line, d.severity))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (4, engine::Severity::Error),
+            (5, engine::Severity::SoftError),
+            (6, engine::Severity::Error)
+        ]
+    );
+    assert!(err.diagnostics[0].msg.contains("cannot convert"));
+    assert!(err.diagnostics[1].msg.contains("b declared but not used"));
+    assert!(err.diagnostics[2]
+        .msg
+        .contains("undeclared name: undefined"));
+    assert_eq!(err.diagnostics[0].filename, path);
+    assert_eq!(err.diagnostics[0].column, 17);
+
+    // errors found by the code generator
+    let names: Vec<String> = (0..200).map(|i| format!("v{}", i)).collect();
+    let zeros = vec!["0"; 200];
+    let src = format!(
+        "package main\n\nvar {} int\n\nfunc main() {{\n\n    {} = {}\n}}\n",
+        names.join(", "),
+        names.join(", "),
+        zeros.join(", ")
+    );
+    std::fs::write(&path, src).unwrap();
+    let mut engine = engine::Engine::new(config(false, false));
+    let err = engine.load(&path).unwrap_err();
+    assert_eq!(err.diagnostics.len(), 1);
+    assert_eq!(err.diagnostics[0].line, 7);
+    assert_eq!(err.diagnostics[0].msg, "too many values in assignment");
+    assert_eq!(
+        err.to_string(),
+        format!("{}:7:5: too many values in assignment\n", path)
+    );
+}
+
+#[test]
+fn test_unsupported() {
+    // unsupported constructs are reported instead of aborting the process
+    let path = format!("{}/unsupported.gos", env!("CARGO_TARGET_TMPDIR"));
+    let src = "package main\n\nfunc main() {\n    x := 1\n    println(x)\n    print(\"a\")\n}\n";
+    std::fs::write(&path, src).unwrap();
+    let mut engine = engine::Engine::new(config(false, false));
+    let err = engine.load(&path).unwrap_err();
+    let msgs: Vec<(usize, &str)> = err
+        .diagnostics
+        .iter()
+        .map(|d| (d.line, d.msg.as_str()))
+        .collect();
+    assert_eq!(
+        msgs,
+        vec![
+            (5, "println is not supported"),
+            (6, "print is not supported")
+        ]
+    );
+}
+
+#[test]
+fn test_iface_mapping() {
+    let path = "./tests/group1/iface_mapping.gos";
+    let err_cnt = run(path, false);
+    assert!(err_cnt == 0);
+
+    // every conversion of the same pair shares one entry: (Shape, *Rect),
+    // (interface{ Area() int }, *Rect) and (interface{}, *Rect)
+    let cfg = types::Config {
+        work_dir: Some("./".to_owned()),
+        base_path: Some("../std/".to_owned()),
+        trace_parser: false,
+        trace_checker: false,
+        sources: HashMap::new(),
+    };
+    let mut fs = fe::FileSet::new();
+    let el = fe::errors::ErrorList::new();
+    let code = cg::entry::parse_check_gen(path, &cfg, &mut fs, &el).unwrap();
+    assert_eq!(code.ifaces.len(), 3);
+}
+
+#[test]
+fn test_disassemble() {
+    let path = "./tests/group1/disasm.gos";
+    assert_eq!(run(path, false), 0);
+
+    let cfg = types::Config {
+        work_dir: Some("./".to_owned()),
+        base_path: Some("../std/".to_owned()),
+        trace_parser: false,
+        trace_checker: false,
+        sources: HashMap::new(),
+    };
+    let mut fs = fe::FileSet::new();
+    let el = fe::errors::ErrorList::new();
+    let code = cg::entry::parse_check_gen(path, &cfg, &mut fs, &el).unwrap();
+    let objs = &code.objects;
+    let pkg = code
+        .packages
+        .iter()
+        .map(|k| &objs.packages[*k])
+        .find(|p| p.name() == "main")
+        .unwrap();
+    let add = pkg.member(*pkg.get_member_index("add").unwrap()).clone();
+    let func = &objs.functions[add.as_closure().0.borrow().func.unwrap()];
+    let text = vm::disasm::disassemble(func, objs);
+    // the raw word after STORE_PKG_FIELD, the package key, is skipped
+    let expected = concat!(
+        "; params: 2, locals: 1, consts: 2\n",
+        "   0  LOAD_LOCAL           Int                    1  @67\n",
+        "   1  LOAD_LOCAL           Str                    2  @75\n",
+        "   2  PUSH_CONST           Str                    0  ; \"!\"  @77\n",
+        "   3  ADD                  Str, Str               0  @75\n",
+        "   4  LEN                  Str, _, FlagA          1  @71\n",
+        "   5  ADD                  Int, Int               0  @67\n",
+        "   6  STORE_LOCAL          Int                    -1 3  @62\n",
+        "   7  POP                                         1  @62\n",
+        "   8  LOAD_LOCAL           Int                    3  @92\n",
+        "   9  STORE_PKG_FIELD      Int, Package           29 1 (ADD)  ; main.total  @83\n",
+        "  11  POP                                         1  @83\n",
+        "  12  LOAD_LOCAL           Int                    3  @102\n",
+        "  13  PUSH_IMM             Int                    2  @106\n",
+        "  14  MUL                  Int, Int               0  @102\n",
+        "  15  STORE_LOCAL          Int                    -1 0  @102\n",
+        "  16  POP                                         1  @95\n",
+        "  17  RETURN                                      0  @95\n",
+        "  18  RETURN                                      0  @108\n",
+    );
+    assert_eq!(text, expected);
+
+    // every function of a larger program, the std packages included
+    let code = cg::entry::parse_check_gen("./tests/std/reflect.gos", &cfg, &mut fs, &el).unwrap();
+    for (_, f) in code.objects.functions.iter() {
+        assert!(!vm::disasm::disassemble(f, &code.objects).is_empty());
+    }
+}
+
+#[derive(Ffi)]
+pub struct Callback {}
+
+#[ffi_impl]
+impl Callback {
+    pub fn new(_v: Vec<GosValue>) -> Callback {
+        Callback {}
+    }
+
+    /// calls the func args[0] with each string in args[1], and returns the
+    /// sum of the ints it returns up to the first error, and the error
+    fn ffi_sum(
+        &self,
+        ctx: &mut FfiCallCtx,
+        args: Vec<GosValue>,
+    ) -> Pin<Box<dyn Future<Output = RuntimeResult<Vec<GosValue>>> + '_>> {
+        let cls = args[0].as_closure().clone();
+        let calls: RuntimeResult<Vec<GoCall>> = args[1]
+            .as_slice()
+            .0
+            .get_vec()
+            .into_iter()
+            .map(|s| (ctx.call_go)(cls.clone(), vec![s]))
+            .collect();
+        Box::pin(async move {
+            let mut total = 0;
+            let mut err = GosValue::new_nil();
+            for call in calls? {
+                let results = call.results().await?;
+                assert_eq!(results.len(), 2);
+                err = results[1].clone();
+                let is_nil = match err.try_as_interface() {
+                    Some(iface) => iface.borrow().is_nil(),
+                    None => err.is_nil(),
+                };
+                if !is_nil {
+                    break;
+                }
+                total += *results[0].as_int();
+            }
+            Ok(vec![GosValue::Int(total), err])
+        })
+    }
+}
+
+#[test]
+fn test_ffi_call_go_results() {
+    let mut engine = engine::Engine::new(config(false, false));
+    Callback::register(&mut engine);
+    assert_eq!(engine.run("./tests/embed/callback.gos"), 0);
+}
+
+// the FFI state of a goroutine, counts the times it's dropped
+struct SessionName {
+    name: String,
+    dropped: Rc<std::cell::Cell<isize>>,
+}
+
+impl Drop for SessionName {
+    fn drop(&mut self) {
+        self.dropped.set(self.dropped.get() + 1);
+    }
+}
+
+#[derive(Ffi)]
+pub struct Session {
+    dropped: Rc<std::cell::Cell<isize>>,
+}
+
+#[ffi_impl]
+impl Session {
+    pub fn new(_v: Vec<GosValue>) -> Session {
+        Session {
+            dropped: Rc::new(std::cell::Cell::new(0)),
+        }
+    }
+
+    fn ffi_begin(&self, ctx: &mut FfiCallCtx, args: Vec<GosValue>) {
+        let name = SessionName {
+            name: args[0].as_str().as_str().to_owned(),
+            dropped: self.dropped.clone(),
+        };
+        ctx.locals.set("session", Box::new(name));
+    }
+
+    fn ffi_name(&self, ctx: &mut FfiCallCtx, _args: Vec<GosValue>) -> GosValue {
+        let name = ctx.locals.get::<SessionName>("session");
+        GosValue::new_str(name.map_or(String::new(), |x| x.name.clone()))
+    }
+
+    fn ffi_dropped(&self, _args: Vec<GosValue>) -> GosValue {
+        GosValue::Int(self.dropped.get())
+    }
+}
+
+#[test]
+fn test_ffi_goroutine_locals() {
+    let mut engine = engine::Engine::new(config(false, false));
+    Session::register(&mut engine);
+    assert_eq!(engine.run("./tests/embed/goroutine_locals.gos"), 0);
+}
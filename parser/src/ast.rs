@@ -41,6 +41,7 @@ pub enum Expr {
     Paren(Rc<ParenExpr>),
     Selector(Rc<SelectorExpr>),
     Index(Rc<IndexExpr>),
+    IndexList(Rc<IndexListExpr>),
     Slice(Rc<SliceExpr>),
     TypeAssert(Rc<TypeAssertExpr>),
     Call(Rc<CallExpr>),
@@ -181,6 +182,7 @@ impl Node for Expr {
             Expr::Paren(e) => e.l_paren,
             Expr::Selector(e) => e.expr.pos(arena),
             Expr::Index(e) => e.expr.pos(arena),
+            Expr::IndexList(e) => e.expr.pos(arena),
             Expr::Slice(e) => e.expr.pos(arena),
             Expr::TypeAssert(e) => e.expr.pos(arena),
             Expr::Call(e) => e.func.pos(arena),
@@ -211,6 +213,7 @@ impl Node for Expr {
             Expr::Paren(e) => e.r_paren + 1,
             Expr::Selector(e) => arena.idents[e.sel].end(),
             Expr::Index(e) => e.r_brack + 1,
+            Expr::IndexList(e) => e.r_brack + 1,
             Expr::Slice(e) => e.r_brack + 1,
             Expr::TypeAssert(e) => e.r_paren + 1,
             Expr::Call(e) => e.r_paren + 1,
@@ -238,6 +241,7 @@ impl Node for Expr {
             Expr::Paren(e) => NodeId::Address(&**e as *const ParenExpr as usize),
             Expr::Selector(e) => e.id(),
             Expr::Index(e) => NodeId::Address(&**e as *const IndexExpr as usize),
+            Expr::IndexList(e) => NodeId::Address(&**e as *const IndexListExpr as usize),
             Expr::Slice(e) => NodeId::Address(&**e as *const SliceExpr as usize),
             Expr::TypeAssert(e) => NodeId::Address(&**e as *const TypeAssertExpr as usize),
             Expr::Call(e) => e.id(),
@@ -658,6 +662,16 @@ pub struct IndexExpr {
     pub r_brack: position::Pos,
 }
 
+// An IndexListExpr node represents an expression followed by multiple
+// indices, i.e. the instantiation of a generic function or type.
+#[derive(Debug)]
+pub struct IndexListExpr {
+    pub expr: Expr,
+    pub l_brack: position::Pos,
+    pub indices: Vec<Expr>,
+    pub r_brack: position::Pos,
+}
+
 // An SliceExpr node represents an expression followed by slice indices.
 #[derive(Debug)]
 pub struct SliceExpr {
@@ -753,6 +767,7 @@ pub struct StructType {
 #[derive(Clone, Debug)]
 pub struct FuncType {
     pub func: Option<position::Pos>,
+    pub type_params: Option<FieldList>, // type parameters of a generic function, or None
     pub params: FieldList,
     pub results: Option<FieldList>,
 }
@@ -765,6 +780,7 @@ impl FuncType {
     ) -> FuncType {
         FuncType {
             func: func,
+            type_params: None,
             params: params,
             results: results,
         }
@@ -847,6 +863,7 @@ pub struct ValueSpec {
 pub struct TypeSpec {
     pub name: IdentKey,
     pub assign: position::Pos,
+    pub type_params: Option<FieldList>, // type parameters of a generic type, or None
     pub typ: Expr,
 }
 
@@ -0,0 +1,374 @@
+//! DeepClone makes a copy of an AST node that shares nothing with the original,
+//! every node is either a new Rc or newly allocated in the Objects arena.
+//! This is needed because the results of type checking are keyed by the
+//! identity of the nodes, so the copy can be checked independently, for
+//! instance to instantiate a generic function with different type arguments.
+
+use super::ast::*;
+use super::objects::*;
+use std::rc::Rc;
+
+pub trait DeepClone {
+    fn deep_clone(&self, objs: &mut Objects) -> Self;
+}
+
+impl<T: DeepClone> DeepClone for Option<T> {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        self.as_ref().map(|x| x.deep_clone(objs))
+    }
+}
+
+impl<T: DeepClone> DeepClone for Vec<T> {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        self.iter().map(|x| x.deep_clone(objs)).collect()
+    }
+}
+
+impl<T: DeepClone> DeepClone for Rc<T> {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        Rc::new(self.as_ref().deep_clone(objs))
+    }
+}
+
+impl DeepClone for IdentKey {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        let ident = objs.idents[*self].clone();
+        objs.idents.insert(ident)
+    }
+}
+
+impl DeepClone for FieldKey {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        let (names, typ, tag) = {
+            let f = &objs.fields[*self];
+            (f.names.clone(), f.typ.clone(), f.tag.clone())
+        };
+        let field = Field {
+            names: names.deep_clone(objs),
+            typ: typ.deep_clone(objs),
+            tag: tag.deep_clone(objs),
+        };
+        objs.fields.insert(field)
+    }
+}
+
+impl DeepClone for FieldList {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        FieldList::new(self.openning, self.list.deep_clone(objs), self.closing)
+    }
+}
+
+impl DeepClone for FuncTypeKey {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        let ft = objs.ftypes[*self].clone();
+        let typ = FuncType {
+            func: ft.func,
+            type_params: ft.type_params.deep_clone(objs),
+            params: ft.params.deep_clone(objs),
+            results: ft.results.deep_clone(objs),
+        };
+        objs.ftypes.insert(typ)
+    }
+}
+
+impl DeepClone for FuncDeclKey {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        let (recv, name, typ, body) = {
+            let fd = &objs.fdecls[*self];
+            (fd.recv.clone(), fd.name, fd.typ, fd.body.clone())
+        };
+        let decl = FuncDecl {
+            recv: recv.deep_clone(objs),
+            name: name.deep_clone(objs),
+            typ: typ.deep_clone(objs),
+            body: body.deep_clone(objs),
+        };
+        objs.fdecls.insert(decl)
+    }
+}
+
+impl DeepClone for SpecKey {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        let spec = match objs.specs[*self].clone() {
+            Spec::Import(is) => Spec::Import(Rc::new(ImportSpec {
+                name: is.name.deep_clone(objs),
+                path: BasicLit {
+                    pos: is.path.pos,
+                    token: is.path.token.clone(),
+                },
+                end_pos: is.end_pos,
+            })),
+            Spec::Value(vs) => Spec::Value(Rc::new(ValueSpec {
+                names: vs.names.deep_clone(objs),
+                typ: vs.typ.deep_clone(objs),
+                values: vs.values.deep_clone(objs),
+            })),
+            Spec::Type(ts) => Spec::Type(Rc::new(TypeSpec {
+                name: ts.name.deep_clone(objs),
+                assign: ts.assign,
+                type_params: ts.type_params.deep_clone(objs),
+                typ: ts.typ.deep_clone(objs),
+            })),
+        };
+        objs.specs.insert(spec)
+    }
+}
+
+impl DeepClone for Expr {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        match self {
+            Expr::Bad(e) => Expr::new_bad(e.from, e.to),
+            Expr::Ident(i) => Expr::Ident(i.deep_clone(objs)),
+            Expr::Ellipsis(e) => Expr::new_ellipsis(e.pos, e.elt.deep_clone(objs)),
+            Expr::BasicLit(e) => Expr::new_basic_lit(e.pos, e.token.clone()),
+            Expr::FuncLit(e) => Expr::FuncLit(Rc::new(FuncLit {
+                typ: e.typ.deep_clone(objs),
+                body: e.body.deep_clone(objs),
+            })),
+            Expr::CompositeLit(e) => Expr::CompositeLit(Rc::new(CompositeLit {
+                typ: e.typ.deep_clone(objs),
+                l_brace: e.l_brace,
+                elts: e.elts.deep_clone(objs),
+                r_brace: e.r_brace,
+                incomplete: e.incomplete,
+            })),
+            Expr::Paren(e) => Expr::Paren(Rc::new(ParenExpr {
+                l_paren: e.l_paren,
+                expr: e.expr.deep_clone(objs),
+                r_paren: e.r_paren,
+            })),
+            Expr::Selector(e) => {
+                let x = e.expr.deep_clone(objs);
+                Expr::new_selector(x, e.sel.deep_clone(objs))
+            }
+            Expr::Index(e) => Expr::Index(Rc::new(IndexExpr {
+                expr: e.expr.deep_clone(objs),
+                l_brack: e.l_brack,
+                index: e.index.deep_clone(objs),
+                r_brack: e.r_brack,
+            })),
+            Expr::IndexList(e) => Expr::IndexList(Rc::new(IndexListExpr {
+                expr: e.expr.deep_clone(objs),
+                l_brack: e.l_brack,
+                indices: e.indices.deep_clone(objs),
+                r_brack: e.r_brack,
+            })),
+            Expr::Slice(e) => Expr::Slice(Rc::new(SliceExpr {
+                expr: e.expr.deep_clone(objs),
+                l_brack: e.l_brack,
+                low: e.low.deep_clone(objs),
+                high: e.high.deep_clone(objs),
+                max: e.max.deep_clone(objs),
+                slice3: e.slice3,
+                r_brack: e.r_brack,
+            })),
+            Expr::TypeAssert(e) => Expr::TypeAssert(Rc::new(TypeAssertExpr {
+                expr: e.expr.deep_clone(objs),
+                l_paren: e.l_paren,
+                typ: e.typ.deep_clone(objs),
+                r_paren: e.r_paren,
+            })),
+            Expr::Call(e) => Expr::Call(Rc::new(CallExpr {
+                func: e.func.deep_clone(objs),
+                l_paren: e.l_paren,
+                args: e.args.deep_clone(objs),
+                ellipsis: e.ellipsis,
+                r_paren: e.r_paren,
+            })),
+            Expr::Star(e) => Expr::Star(Rc::new(StarExpr {
+                star: e.star,
+                expr: e.expr.deep_clone(objs),
+            })),
+            Expr::Unary(e) => {
+                let x = e.expr.deep_clone(objs);
+                Expr::new_unary_expr(e.op_pos, e.op.clone(), x)
+            }
+            Expr::Binary(e) => Expr::Binary(Rc::new(BinaryExpr {
+                expr_a: e.expr_a.deep_clone(objs),
+                op_pos: e.op_pos,
+                op: e.op.clone(),
+                expr_b: e.expr_b.deep_clone(objs),
+            })),
+            Expr::KeyValue(e) => Expr::KeyValue(Rc::new(KeyValueExpr {
+                key: e.key.deep_clone(objs),
+                colon: e.colon,
+                val: e.val.deep_clone(objs),
+            })),
+            Expr::Array(e) => Expr::Array(Rc::new(ArrayType {
+                l_brack: e.l_brack,
+                len: e.len.deep_clone(objs),
+                elt: e.elt.deep_clone(objs),
+            })),
+            Expr::Struct(e) => Expr::Struct(Rc::new(StructType {
+                struct_pos: e.struct_pos,
+                fields: e.fields.deep_clone(objs),
+                incomplete: e.incomplete,
+            })),
+            Expr::Func(e) => Expr::Func(e.deep_clone(objs)),
+            Expr::Interface(e) => Expr::Interface(Rc::new(InterfaceType {
+                interface: e.interface,
+                methods: e.methods.deep_clone(objs),
+                incomplete: e.incomplete,
+            })),
+            Expr::Map(e) => Expr::Map(Rc::new(MapType {
+                map: e.map,
+                key: e.key.deep_clone(objs),
+                val: e.val.deep_clone(objs),
+            })),
+            Expr::Chan(e) => Expr::Chan(Rc::new(ChanType {
+                begin: e.begin,
+                arrow: e.arrow,
+                dir: e.dir.clone(),
+                val: e.val.deep_clone(objs),
+            })),
+        }
+    }
+}
+
+impl DeepClone for Decl {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        match self {
+            Decl::Bad(d) => Decl::Bad(Rc::new(BadDecl {
+                from: d.from,
+                to: d.to,
+            })),
+            Decl::Gen(d) => Decl::Gen(Rc::new(GenDecl {
+                token_pos: d.token_pos,
+                token: d.token.clone(),
+                l_paran: d.l_paran,
+                specs: d.specs.deep_clone(objs),
+                r_paren: d.r_paren,
+            })),
+            Decl::Func(d) => Decl::Func(d.deep_clone(objs)),
+        }
+    }
+}
+
+impl DeepClone for BlockStmt {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        BlockStmt::new(self.l_brace, self.list.deep_clone(objs), self.r_brace)
+    }
+}
+
+impl DeepClone for CaseClause {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        CaseClause {
+            case: self.case,
+            list: self.list.deep_clone(objs),
+            colon: self.colon,
+            body: self.body.deep_clone(objs),
+        }
+    }
+}
+
+impl DeepClone for CommClause {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        CommClause {
+            case: self.case,
+            comm: self.comm.deep_clone(objs),
+            colon: self.colon,
+            body: self.body.deep_clone(objs),
+        }
+    }
+}
+
+impl DeepClone for Stmt {
+    fn deep_clone(&self, objs: &mut Objects) -> Self {
+        match self {
+            Stmt::Bad(s) => Stmt::new_bad(s.from, s.to),
+            Stmt::Decl(d) => Stmt::Decl(d.deep_clone(objs)),
+            Stmt::Empty(s) => Stmt::Empty(Rc::new(EmptyStmt {
+                semi: s.semi,
+                implicit: s.implicit,
+            })),
+            Stmt::Labeled(s) => {
+                let (label, colon, stmt) = {
+                    let ls = &objs.l_stmts[*s];
+                    (ls.label, ls.colon, ls.stmt.clone())
+                };
+                let label = label.deep_clone(objs);
+                let stmt = stmt.deep_clone(objs);
+                Stmt::Labeled(LabeledStmt::arena_new(objs, label, colon, stmt))
+            }
+            Stmt::Expr(e) => Stmt::Expr(Box::new(e.deep_clone(objs))),
+            Stmt::Send(s) => Stmt::Send(Rc::new(SendStmt {
+                chan: s.chan.deep_clone(objs),
+                arrow: s.arrow,
+                val: s.val.deep_clone(objs),
+            })),
+            Stmt::IncDec(s) => Stmt::IncDec(Rc::new(IncDecStmt {
+                expr: s.expr.deep_clone(objs),
+                token_pos: s.token_pos,
+                token: s.token.clone(),
+            })),
+            Stmt::Assign(s) => {
+                let (lhs, pos, token, rhs) = {
+                    let a = &objs.a_stmts[*s];
+                    (a.lhs.clone(), a.token_pos, a.token.clone(), a.rhs.clone())
+                };
+                let lhs = lhs.deep_clone(objs);
+                let rhs = rhs.deep_clone(objs);
+                Stmt::new_assign(objs, lhs, pos, token, rhs)
+            }
+            Stmt::Go(s) => Stmt::Go(Rc::new(GoStmt {
+                go: s.go,
+                call: s.call.deep_clone(objs),
+            })),
+            Stmt::Defer(s) => Stmt::Defer(Rc::new(DeferStmt {
+                defer: s.defer,
+                call: s.call.deep_clone(objs),
+            })),
+            Stmt::Return(s) => Stmt::Return(Rc::new(ReturnStmt {
+                ret: s.ret,
+                results: s.results.deep_clone(objs),
+            })),
+            Stmt::Branch(s) => Stmt::Branch(Rc::new(BranchStmt {
+                token_pos: s.token_pos,
+                token: s.token.clone(),
+                label: s.label.deep_clone(objs),
+            })),
+            Stmt::Block(s) => Stmt::Block(s.deep_clone(objs)),
+            Stmt::If(s) => Stmt::If(Rc::new(IfStmt {
+                if_pos: s.if_pos,
+                init: s.init.deep_clone(objs),
+                cond: s.cond.deep_clone(objs),
+                body: s.body.deep_clone(objs),
+                els: s.els.deep_clone(objs),
+            })),
+            Stmt::Case(s) => Stmt::Case(s.deep_clone(objs)),
+            Stmt::Switch(s) => Stmt::Switch(Rc::new(SwitchStmt {
+                switch: s.switch,
+                init: s.init.deep_clone(objs),
+                tag: s.tag.deep_clone(objs),
+                body: s.body.deep_clone(objs),
+            })),
+            Stmt::TypeSwitch(s) => Stmt::TypeSwitch(Rc::new(TypeSwitchStmt {
+                switch: s.switch,
+                init: s.init.deep_clone(objs),
+                assign: s.assign.deep_clone(objs),
+                body: s.body.deep_clone(objs),
+            })),
+            Stmt::Comm(s) => Stmt::Comm(s.deep_clone(objs)),
+            Stmt::Select(s) => Stmt::Select(Rc::new(SelectStmt {
+                select: s.select,
+                body: s.body.deep_clone(objs),
+            })),
+            Stmt::For(s) => Stmt::For(Rc::new(ForStmt {
+                for_pos: s.for_pos,
+                init: s.init.deep_clone(objs),
+                cond: s.cond.deep_clone(objs),
+                post: s.post.deep_clone(objs),
+                body: s.body.deep_clone(objs),
+            })),
+            Stmt::Range(s) => Stmt::Range(Rc::new(RangeStmt {
+                for_pos: s.for_pos,
+                key: s.key.deep_clone(objs),
+                val: s.val.deep_clone(objs),
+                token_pos: s.token_pos,
+                token: s.token.clone(),
+                expr: s.expr.deep_clone(objs),
+                body: s.body.deep_clone(objs),
+            })),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 pub mod ast;
+pub mod deep_clone;
 pub mod errors;
 pub mod objects;
 mod parser;
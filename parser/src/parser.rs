@@ -554,8 +554,22 @@ impl<'a> Parser<'a> {
         self.trace_begin("ArrayType");
 
         let lpos = self.expect(&Token::LBRACK);
+        let ret = self.parse_array_type_rest(lpos, None);
+
+        self.trace_end();
+        ret
+    }
+
+    // Parses the rest of an array type after the "[", if x is not None
+    // it's the operand the length expression starts with.
+    fn parse_array_type_rest(&mut self, lpos: position::Pos, x: Option<Expr>) -> Expr {
         self.expr_level += 1;
         let len = match self.token {
+            _ if x.is_some() => {
+                let x = self.parse_primary_expr_rest(x.unwrap(), false);
+                let x = self.parse_binary_expr_rest(x, false, LOWEST_PREC+1);
+                Some(self.check_expr(x))
+            },
             // always permit ellipsis for more fault-tolerant parsing
             Token::ELLIPSIS => {
                 let ell = Expr::new_ellipsis(self.pos, None);
@@ -571,11 +585,42 @@ impl<'a> Parser<'a> {
         self.expect(&Token::RBRACK);
         let elt = self.parse_type();
 
-        self.trace_end();
         Expr::Array(Rc::new(ArrayType{
             l_brack: lpos, len: len, elt: elt}))
     }
 
+    // Parses the type arguments if x is the name of a generic type
+    // followed by "[".
+    fn try_type_args(&mut self, x: Expr) -> Expr {
+        match (&x, &self.token) {
+            (Expr::Ident(_), Token::LBRACK) | (Expr::Selector(_), Token::LBRACK) => {},
+            _ => return x,
+        }
+        self.trace_begin("TypeArgs");
+
+        let lbrack = self.expect(&Token::LBRACK);
+        self.expr_level += 1;
+        let mut list = vec![self.parse_type()];
+        while self.token == Token::COMMA {
+            self.next();
+            if self.token != Token::RBRACK && self.token != Token::EOF {
+                list.push(self.parse_type());
+            }
+        }
+        self.expr_level -= 1;
+        let rbrack = self.expect(&Token::RBRACK);
+        let ret = if list.len() == 1 {
+            Expr::Index(Rc::new(IndexExpr{
+                expr: x, l_brack: lbrack, index: list.pop().unwrap(), r_brack: rbrack}))
+        } else {
+            Expr::IndexList(Rc::new(IndexListExpr{
+                expr: x, l_brack: lbrack, indices: list, r_brack: rbrack}))
+        };
+
+        self.trace_end();
+        ret
+    }
+
     fn make_ident_list(&mut self, exprs: &mut Vec<Expr>) -> Vec<IdentKey> {
         exprs.iter().map(|x| {
             match x {
@@ -611,7 +656,7 @@ impl<'a> Parser<'a> {
         let typ = match self.try_var_type(false) {
             Some(t) => {
                 idents = self.make_ident_list(&mut list);
-                t
+                self.try_type_args(t)
             }
             // ["*"] TypeName (AnonymousField)
             None => { 
@@ -739,7 +784,7 @@ impl<'a> Parser<'a> {
             }
         }
         let mut params = vec![];
-        let typ = self.try_var_type(ellipsis_ok);
+        let typ = self.try_var_type(ellipsis_ok).map(|t| self.try_type_args(t));
         if let Some(t) = typ {
             // IdentifierList Type
             let idents = self.make_ident_list(&mut list);
@@ -761,6 +806,7 @@ impl<'a> Parser<'a> {
             while self.token != Token::RPAREN && self.token != Token::EOF {
                 let idents = self.parse_ident_list();
                 let t = self.parse_var_type(ellipsis_ok);
+                let t = self.try_type_args(t);
                 let to_resolve = t.clone_ident();
                 let field = new_field!(self, idents, t, None);
                 // warning: copy paste
@@ -829,6 +875,79 @@ impl<'a> Parser<'a> {
         (params, results)
     }
 
+    // TypeParams = "[" TypeParamDecl { "," TypeParamDecl } [ "," ] "]"
+    // The "[" has been consumed, and so has the first identifier if first
+    // is not None.
+    fn parse_type_params(&mut self, scope: ScopeKey, lbrack: position::Pos,
+        mut first: Option<IdentKey>) -> FieldList {
+        self.trace_begin("TypeParams");
+
+        let mut list = vec![];
+        while first.is_some() || (self.token != Token::RBRACK && self.token != Token::EOF) {
+            let idents = match first.take() {
+                Some(ident) => {
+                    let mut idents = vec![ident];
+                    while self.token == Token::COMMA {
+                        self.next();
+                        idents.push(self.parse_ident());
+                    }
+                    idents
+                }
+                None => self.parse_ident_list(),
+            };
+            let typ = self.parse_constraint();
+            let field = new_field!(self, idents, typ, None);
+            list.push(field);
+            self.declare(DeclObj::Field(field), EntityData::NoData,
+                EntityKind::Typ, &scope);
+            if !self.at_comma("type parameter list", &Token::RBRACK) {
+                break;
+            }
+            self.next();
+        }
+        let rbrack = self.expect(&Token::RBRACK);
+        if list.is_empty() {
+            self.error_str(rbrack, "empty type parameter list");
+        }
+
+        self.trace_end();
+        FieldList::new(Some(lbrack), list, Some(rbrack))
+    }
+
+    // a single type set term: T or ~T
+    fn parse_constraint_term(&mut self) -> Expr {
+        if self.token == Token::TILDE {
+            let pos = self.pos;
+            self.next();
+            let typ = self.parse_type();
+            Expr::new_unary_expr(pos, Token::TILDE, typ)
+        } else {
+            self.parse_type()
+        }
+    }
+
+    // a union of type set terms: T1 | ~T2 | ...
+    fn parse_constraint_union(&mut self, mut x: Expr) -> Expr {
+        while self.token == Token::OR {
+            let pos = self.pos;
+            self.next();
+            let y = self.parse_constraint_term();
+            x = Expr::Binary(Rc::new(BinaryExpr{
+                expr_a: x, op_pos: pos, op: Token::OR, expr_b: y}));
+        }
+        x
+    }
+
+    fn parse_constraint(&mut self) -> Expr {
+        self.trace_begin("Constraint");
+
+        let x = self.parse_constraint_term();
+        let ret = self.parse_constraint_union(x);
+
+        self.trace_end();
+        ret
+    }
+
     fn parse_func_type(&mut self) -> (FuncType, ScopeKey) {
         self.trace_begin("FuncType");
 
@@ -845,16 +964,23 @@ impl<'a> Parser<'a> {
         self.trace_begin("MethodSpec");
 
         let mut idents = vec![];
-        let mut typ = self.parse_type_name();
-        let ident = typ.try_as_ident();
-        if ident.is_some() && self.token == Token::LPAREN {
-            idents = vec![*ident.unwrap()];
-            let scope = new_scope!(self, self.top_scope);
-            let (params, results) = self.parse_signature(scope);
-            typ = Expr::box_func_type(FuncType::new(None, params, results), &mut self.objects);
+        let mut typ;
+        if self.token == Token::TILDE {
+            // type set element
+            typ = self.parse_constraint();
         } else {
-            // embedded interface
-            self.resolve(&typ);
+            typ = self.parse_type_name();
+            let ident = typ.try_as_ident();
+            if ident.is_some() && self.token == Token::LPAREN {
+                idents = vec![*ident.unwrap()];
+                let scope = new_scope!(self, self.top_scope);
+                let (params, results) = self.parse_signature(scope);
+                typ = Expr::box_func_type(FuncType::new(None, params, results), &mut self.objects);
+            } else {
+                // embedded interface or type set element
+                self.resolve(&typ);
+                typ = self.parse_constraint_union(typ);
+            }
         }
         self.expect_semi();
         let field = new_field!(self, idents, typ, None);
@@ -872,7 +998,10 @@ impl<'a> Parser<'a> {
         let scope = new_scope!(self, None);
         let mut list = vec![];
         loop {
-            if let Token::IDENT(_) = self.token {} else {break;}
+            match self.token {
+                Token::IDENT(_) | Token::TILDE => {}
+                _ => break,
+            }
             list.push(self.parse_method_spec(scope));
         }
         let rbrace = self.expect(&Token::RBRACE);
@@ -961,6 +1090,7 @@ impl<'a> Parser<'a> {
 
     fn try_type(&mut self) -> Option<Expr> {
         if let Some(typ) = self.try_ident_or_type() {
+            let typ = self.try_type_args(typ);
             self.resolve(&typ);
             Some(typ)
         } else {
@@ -1123,7 +1253,23 @@ impl<'a> Parser<'a> {
         let mut colons = vec![0, 0, 0];
         let mut ncolons = 0;
         if self.token != Token::COLON {
-            indices[0] = Some(self.parse_rhs());
+            // it may be the type argument of an instantiation
+            indices[0] = Some(self.parse_rhs_or_type());
+        }
+        if self.token == Token::COMMA {
+            // instantiation with multiple type arguments
+            let mut list = vec![indices.into_iter().nth(0).unwrap().unwrap()];
+            while self.token == Token::COMMA {
+                self.next();
+                if self.token != Token::RBRACK && self.token != Token::EOF {
+                    list.push(self.parse_type());
+                }
+            }
+            self.expr_level -= 1;
+            let rbrack = self.expect(&Token::RBRACK);
+            self.trace_end();
+            return Expr::IndexList(Rc::new(IndexListExpr{
+                expr: x, l_brack: lbrack, indices: list, r_brack: rbrack}));
         }
         while self.token == Token::COLON && ncolons < N - 1  {
             colons[ncolons] = self.pos;
@@ -1305,6 +1451,7 @@ impl<'a> Parser<'a> {
             Expr::Paren(_) => { unreachable!(); },
             Expr::Selector(_) => x,
             Expr::Index(_) => x,
+            Expr::IndexList(_) => x,
             Expr::Slice(_) => x,
             // If t.Type == nil we have a type assertion of the form
             // y.(type), which is only allowed in type switch expressions.
@@ -1344,6 +1491,15 @@ impl<'a> Parser<'a> {
             Expr::Selector(s) => {
                 if let Expr::Ident(_) = s.expr {true} else {false}
             },
+            _ => Parser::is_type_inst(x)
+        }
+    }
+
+    // isTypeInst reports whether x may be the instantiation of a generic type.
+    fn is_type_inst(x: &Expr) -> bool {
+        match x {
+            Expr::Index(ie) => Parser::is_type_name(&ie.expr),
+            Expr::IndexList(ie) => Parser::is_type_name(&ie.expr),
             _ => false
         }
     }
@@ -1377,10 +1533,19 @@ impl<'a> Parser<'a> {
         return x;
     }
 
-    fn parse_primary_expr(&mut self, mut lhs: bool) -> Expr {
+    fn parse_primary_expr(&mut self, lhs: bool) -> Expr {
         self.trace_begin("PrimaryExpr");
 
-        let mut x = self.parse_operand(lhs);
+        let x = self.parse_operand(lhs);
+        let ret = self.parse_primary_expr_rest(x, lhs);
+
+        self.trace_end();
+        ret
+    }
+
+    // Parses the selectors, indices, calls and literal values following
+    // the operand x of a primary expression.
+    fn parse_primary_expr_rest(&mut self, mut x: Expr, mut lhs: bool) -> Expr {
         loop {
             match self.token {
                 Token::PERIOD => {
@@ -1418,8 +1583,10 @@ impl<'a> Parser<'a> {
                     x = self.parse_call_or_conversion(self.check_expr_or_type(x));
                 }
                 Token::LBRACE => {
-                    if Parser::is_literal_type(&x) && 
-                        (self.expr_level >= 0 || !Parser::is_type_name(&x)) {
+                    // in control clauses a literal type must be parenthesized
+                    if Parser::is_literal_type(&x) &&
+                        (self.expr_level >= 0 || !(Parser::is_type_name(&x) ||
+                        Parser::is_type_inst(&x))) {
                         if lhs {
                             self.resolve(&x);
                         }
@@ -1432,8 +1599,6 @@ impl<'a> Parser<'a> {
             }
             lhs = false; // no need to try to resolve again
         }
-        
-        self.trace_end();
         x
     }
 
@@ -1531,7 +1696,15 @@ impl<'a> Parser<'a> {
     fn parse_binary_expr(&mut self, lhs: bool, prec1: usize) -> Expr {
         self.trace_begin("BinaryExpr");
 
-        let mut x = self.parse_unary_expr(lhs);
+        let x = self.parse_unary_expr(lhs);
+        let ret = self.parse_binary_expr_rest(x, lhs, prec1);
+
+        self.trace_end();
+        ret
+    }
+
+    // Parses the binary operations following the operand x.
+    fn parse_binary_expr_rest(&mut self, mut x: Expr, lhs: bool, prec1: usize) -> Expr {
         loop {
             let (op, prec) = self.token_prec();
             if prec < prec1 {
@@ -1545,8 +1718,6 @@ impl<'a> Parser<'a> {
             x = Expr::Binary(Rc::new(BinaryExpr{
                 expr_a: x, op_pos: pos, op: op, expr_b: y}))
         }
-
-        self.trace_end();
         x
     }
 
@@ -2354,7 +2525,7 @@ impl<'a> Parser<'a> {
 	    // (Global identifiers are resolved in a separate phase after parsing.)
         let placeholder = Expr::new_bad(0, 0);
         let spec_val = Spec::Type(Rc::new(TypeSpec{
-            name: ident, assign: 0, typ: placeholder
+            name: ident, assign: 0, type_params: None, typ: placeholder
         }));
         let index = specs_mut!(self).insert(spec_val);
         let scope = self.top_scope.unwrap();
@@ -2363,10 +2534,36 @@ impl<'a> Parser<'a> {
             self.next();
             self.pos
             } else {0};
-        let typ = self.parse_type();
+        let mut type_params = None;
+        let typ = if assign == 0 && self.token == Token::LBRACK {
+            // either the type parameters of a generic type or an array type
+            let lbrack = self.pos;
+            self.next();
+            if let Token::IDENT(_) = self.token {
+                let x = self.parse_ident();
+                match self.token {
+                    Token::IDENT(_) | Token::COMMA | Token::INTERFACE | Token::LBRACK |
+                    Token::FUNC | Token::MAP | Token::CHAN | Token::STRUCT | Token::TILDE => {
+                        let scope = new_scope!(self, self.top_scope);
+                        type_params = Some(self.parse_type_params(scope, lbrack, Some(x)));
+                        self.parse_type()
+                    }
+                    _ => {
+                        let x = Expr::Ident(x);
+                        self.resolve(&x);
+                        self.parse_array_type_rest(lbrack, Some(x))
+                    }
+                }
+            } else {
+                self.parse_array_type_rest(lbrack, None)
+            }
+        } else {
+            self.parse_type()
+        };
         let spec = if let Spec::Type(boxts) = spec_mut!(self, index) {
             Rc::get_mut(boxts).unwrap()} else {unreachable!()};
         spec.assign = assign;
+        spec.type_params = type_params;
         spec.typ = typ;
         self.expect_semi();
 
@@ -2416,6 +2613,15 @@ impl<'a> Parser<'a> {
             None
         };
         let ident = self.parse_ident();
+        let type_params = if self.token == Token::LBRACK {
+            if recv.is_some() {
+                self.error_str(self.pos, "method must have no type parameters");
+            }
+            let lbrack = self.expect(&Token::LBRACK);
+            Some(self.parse_type_params(scope, lbrack, None))
+        } else {
+            None
+        };
         let (params, results) = self.parse_signature(scope);
         let body = if self.token == Token::LBRACE {
             Some(Rc::new(self.parse_body(scope)))
@@ -2427,6 +2633,7 @@ impl<'a> Parser<'a> {
         let recv_is_none = recv.is_none();
         let typ = self.objects.ftypes.insert(FuncType{
             func: Some(pos),
+            type_params: type_params,
             params: params,
             results: results,
         });
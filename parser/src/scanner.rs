@@ -124,6 +124,7 @@ impl<'a> Scanner<'a> {
                 }
             }
             Some(',') => self.scan_token(Token::COMMA, false),
+            Some('~') => self.scan_token(Token::TILDE, false),
             Some(';') => self.scan_token(Token::SEMICOLON(true.into()), false),
             Some('(') => self.scan_token(Token::LPAREN, false),
             Some(')') => self.scan_token(Token::RPAREN, true),
@@ -253,13 +254,13 @@ impl<'a> Scanner<'a> {
             self.advance_and_push(&mut literal, '0');
             match self.peek_char() {
                 // hexadecimal int
-                Some('x') | Some('X') => self.prefixed_int(literal, IntPrefix::Hex),
+                Some('x') | Some('X') => self.hex_int_or_float(literal),
                 // octal int (explicit)
                 Some('o') | Some('O') => self.prefixed_int(literal, IntPrefix::Octal(false)),
                 // binary int
                 Some('b') | Some('B') => self.prefixed_int(literal, IntPrefix::Binary),
-                // octal int (bare)
-                Some(ch) if is_decimal(*ch) => self.prefixed_int(literal, IntPrefix::Octal(true)),
+                // octal int (bare) or decimal float like 09.5
+                Some(ch) if is_decimal(*ch) || *ch == '_' => self.bare_octal_or_float(literal),
                 _ => self.decimal_int_or_float(literal),
             }
         } else {
@@ -296,6 +297,61 @@ impl<'a> Scanner<'a> {
         Token::INT(literal.into())
     }
 
+    fn hex_int_or_float(&mut self, mut literal: String) -> Token {
+        // 0x1F / 0x1.8p3 / 0x.8p-1
+        self.advance_and_push(&mut literal, 'x');
+        let mut count = match self.scan_digits(&mut literal, is_hex, true) {
+            Ok(count) => count,
+            Err(e) => {
+                self.error(e);
+                return Token::ILLEGAL(literal.into());
+            }
+        };
+        let is_float = match self.peek_char() {
+            Some('.') => {
+                self.advance_and_push(&mut literal, '.');
+                match self.scan_digits(&mut literal, is_hex, false) {
+                    Ok(c) => count += c,
+                    Err(e) => {
+                        self.error(e);
+                        return Token::ILLEGAL(literal.into());
+                    }
+                }
+                true
+            }
+            Some('p') | Some('P') => true,
+            _ => false,
+        };
+        if count == 0 {
+            self.error(IntPrefix::Hex.err_msg());
+            return Token::ILLEGAL(literal.into());
+        }
+        match self.peek_char() {
+            Some('p') | Some('P') => self.scan_exponent_and_finish(literal, 'p'),
+            _ if is_float => {
+                self.error("hexadecimal mantissa requires a 'p' exponent");
+                Token::ILLEGAL(literal.into())
+            }
+            _ => Token::INT(literal.into()),
+        }
+    }
+
+    fn bare_octal_or_float(&mut self, literal: String) -> Token {
+        // 017 / 0_17, but 09.5 and 017e2 are decimal floats
+        match self.decimal_int_or_float(literal) {
+            Token::INT(lit) => {
+                let digits = &lit.as_str()[1..];
+                if digits.chars().all(|c| is_octal(c) || c == '_') {
+                    Token::INT(format!("0o{}", digits.trim_start_matches('_')).into())
+                } else {
+                    self.error(IntPrefix::Octal(true).err_msg());
+                    Token::ILLEGAL(lit)
+                }
+            }
+            tok => tok,
+        }
+    }
+
     fn decimal_int_or_float(&mut self, mut literal: String) -> Token {
         // decimal int or float 3 / 3.14
         if let Err(e) = self.scan_digits(&mut literal, is_decimal, true) {
@@ -303,7 +359,7 @@ impl<'a> Scanner<'a> {
             return Token::ILLEGAL(literal.into());
         }
         match self.peek_char() {
-            Some('e') | Some('E') => self.scan_exponent_and_finish(literal),
+            Some('e') | Some('E') => self.scan_exponent_and_finish(literal, 'e'),
             Some('.') => self.scan_fraction_and_finish(literal),
             _ => Token::INT(literal.into()),
         }
@@ -318,7 +374,18 @@ impl<'a> Scanner<'a> {
     fn scan_char(&mut self) -> Token {
         let mut lit = String::new();
         if let Some(unquoted) = self.scan_string_char_lit(&mut lit, '\'') {
-            Token::CHAR((lit, unquoted.chars().next().unwrap()).into())
+            let mut chars = unquoted.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Token::CHAR((lit, ch).into()),
+                (None, _) => {
+                    self.error("empty rune literal or unescaped ' in rune literal");
+                    Token::ILLEGAL(lit.into())
+                }
+                (Some(_), Some(_)) => {
+                    self.error("more than one character in rune literal");
+                    Token::ILLEGAL(lit.into())
+                }
+            }
         } else {
             Token::ILLEGAL(lit.into())
         }
@@ -609,13 +676,13 @@ impl<'a> Scanner<'a> {
             return Token::ILLEGAL(lit.into());
         }
         match self.peek_char() {
-            Some('e') | Some('E') => self.scan_exponent_and_finish(lit),
+            Some('e') | Some('E') => self.scan_exponent_and_finish(lit, 'e'),
             _ => Token::FLOAT(lit.into()),
         }
     }
 
-    fn scan_exponent_and_finish(&mut self, mut lit: String) -> Token {
-        self.advance_and_push(&mut lit, 'e');
+    fn scan_exponent_and_finish(&mut self, mut lit: String, exp: char) -> Token {
+        self.advance_and_push(&mut lit, exp);
         match self.peek_char() {
             Some(&ch) if ch == '+' || ch == '-' => {
                 self.advance_and_push(&mut lit, ch);
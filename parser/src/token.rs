@@ -64,6 +64,7 @@ pub enum Token {
     GEQ,      // >=
     DEFINE,   // :=
     ELLIPSIS, // ...
+    TILDE,    // ~
 
     LPAREN, // (
     LBRACK, // [
@@ -166,6 +167,7 @@ impl Token {
             Token::GEQ => (TokenType::Operator, ">="),
             Token::DEFINE => (TokenType::Operator, ":="),
             Token::ELLIPSIS => (TokenType::Operator, "..."),
+            Token::TILDE => (TokenType::Operator, "~"),
             Token::LPAREN => (TokenType::Operator, "("),
             Token::LBRACK => (TokenType::Operator, "["),
             Token::LBRACE => (TokenType::Operator, "{"),
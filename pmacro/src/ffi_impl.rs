@@ -44,9 +44,10 @@ pub fn ffi_impl_implement(
                 let ffi_name = method.sig.ident.to_string();
                 ffi_name.strip_prefix(FFI_FUNC_PREFIX).map(|x| {
                     let wrapper_name = format!("{}{}", WRAPPER_FUNC_PREFIX, x);
-                    let m = gen_wrapper_method(&method, &wrapper_name);
+                    let m = gen_wrapper_method(&method, &wrapper_name, x);
+                    let arg_types = get_arg_types(&m.sig);
                     output_block.items.push(ImplItem::Method(m));
-                    (wrapper_name, get_arg_types(&method.sig))
+                    (wrapper_name, arg_types)
                 })
             }
             _ => None,
@@ -77,7 +78,10 @@ fn gen_dispatch_method(name_args: &Vec<(String, Vec<Box<Type>>)>) -> ImplItemMet
         ) -> Pin<Box<dyn Future<Output = goscript_vm::value::RuntimeResult<Vec<GosValue>>> + '_>> {
             match ctx.func_name {
                 "dummy" => self.dummy(ctx, args),
-                _ => unreachable!(),
+                _ => {
+                    let msg = format!("no ffi method named {}", ctx.func_name);
+                    Box::pin(async move { Err(msg) })
+                }
             }
         }
     };
@@ -155,11 +159,41 @@ fn gen_register_method(type_name: &Ident, meta: &Vec<NestedMeta>) -> ImplItemMet
     }
 }
 
-fn gen_wrapper_method(m: &ImplItemMethod, name: &str) -> ImplItemMethod {
+fn gen_wrapper_method(m: &ImplItemMethod, name: &str, short_name: &str) -> ImplItemMethod {
     let mut wrapper = m.clone();
     wrapper.sig.ident = Ident::new(name, Span::call_site());
     let callee = &m.sig.ident;
     let args = get_args(&m.sig);
+    // the arguments taken one by one as GosValues are passed to the wrapper
+    // in a Vec, which has to hold exactly as many of them
+    let single_args: Vec<Box<Pat>> = m
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|x| match x {
+            FnArg::Typed(pt) if is_gos_value(&pt.ty) => Some(pt.pat.clone()),
+            _ => None,
+        })
+        .collect();
+    if !single_args.is_empty() {
+        if get_arg_types(&m.sig)
+            .iter()
+            .any(|t| get_type_name(t).map_or(false, |seg| seg.ident == "Vec"))
+        {
+            panic!("ffi methods take either a Vec<GosValue> or GosValue arguments");
+        }
+        wrapper.sig.inputs = m
+            .sig
+            .inputs
+            .iter()
+            .filter(|x| match x {
+                FnArg::Typed(pt) => !is_gos_value(&pt.ty),
+                _ => true,
+            })
+            .cloned()
+            .collect();
+        wrapper.sig.inputs.push(parse_quote! {args: Vec<GosValue>});
+    }
     let is_async = m.sig.asyncness.is_some();
     let (is_result, rcount) = get_return_type_attributes(&m.sig.output);
     wrapper.block = match (is_async, is_result, rcount) {
@@ -199,20 +233,66 @@ fn gen_wrapper_method(m: &ImplItemMethod, name: &str) -> ImplItemMethod {
                 Box::pin(async move { Ok( re ) })
             }}
         }
+        (true, true, FfiReturnType::ZeroVal) => {
+            parse_quote! {{
+                Box::pin(async move { self.#callee(#args).await.map(|x| vec![]) })
+            }}
+        }
+        (true, true, FfiReturnType::OneVal) => {
+            parse_quote! {{
+                Box::pin(async move { self.#callee(#args).await.map(|x| vec![x]) })
+            }}
+        }
         (true, true, FfiReturnType::MultipleVal) => {
             parse_quote! {{
                 let re = self.#callee(#args);
                 Box::pin( re )
             }}
         }
+        (true, false, FfiReturnType::ZeroVal) => {
+            parse_quote! {{
+                Box::pin(async move {
+                    self.#callee(#args).await;
+                    Ok(vec![])
+                })
+            }}
+        }
+        (true, false, FfiReturnType::OneVal) => {
+            parse_quote! {{
+                Box::pin(async move { Ok(vec![self.#callee(#args).await]) })
+            }}
+        }
+        (true, false, FfiReturnType::MultipleVal) => {
+            parse_quote! {{
+                Box::pin(async move { Ok(self.#callee(#args).await) })
+            }}
+        }
         (_, _, FfiReturnType::AlreadyBoxed) => {
             parse_quote! {{
                 self.#callee(#args)
             }}
         }
-        (true, _, _) => panic!("async func can only return RuntimeResult<Vec<GosValue>>>"),
     };
 
+    if !single_args.is_empty() {
+        let block = &wrapper.block;
+        let count = single_args.len();
+        wrapper.block = parse_quote! {{
+            if args.len() != #count {
+                let msg = format!(
+                    "ffi method {} takes {} arguments, got {}",
+                    #short_name,
+                    #count,
+                    args.len()
+                );
+                return Box::pin(async move { Err(msg) });
+            }
+            let mut args = args.into_iter();
+            #(let #single_args = args.next().unwrap();)*
+            #block
+        }};
+    }
+
     wrapper.sig.output = parse_quote! {-> Pin<Box<dyn Future<Output = goscript_vm::value::RuntimeResult<Vec<GosValue>>> + '_>>};
     wrapper.sig.asyncness = None;
     wrapper
@@ -254,6 +334,13 @@ fn fn_arg_as_pat_type(arg: &FnArg) -> &PatType {
     }
 }
 
+fn is_gos_value(t: &Type) -> bool {
+    match t {
+        Type::Path(tp) => tp.path.segments.last().unwrap().ident == "GosValue",
+        _ => false,
+    }
+}
+
 fn get_type_name(t: &Type) -> Option<PathSegment> {
     match t {
         Type::Path(tp) => Some(tp),
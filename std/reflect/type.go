@@ -380,20 +380,31 @@ func (t reflectType) Size() uintptr {
 	panic("not implemented")
 }
 
+// String returns the name of t's kind, which is the type's string
+// representation for the predeclared types.
 func (t reflectType) String() string {
-	panic("not implemented")
+	return t.kind.String()
 }
 
 func (t reflectType) Kind() Kind {
 	return t.kind
 }
 
+func (t reflectType) mustBeFunc(method string) {
+	if t.kind != Func {
+		panic("reflect: " + method + " of non-func type " + t.String())
+	}
+}
+
 func (t reflectType) Implements(u Type) bool {
 	panic("not implemented")
 }
 
 func (t reflectType) AssignableTo(u Type) bool {
-	panic("not implemented")
+	if u == nil {
+		panic("reflect: nil type passed to Type.AssignableTo")
+	}
+	return native.type_assignable_to(t.typePtr, u.(reflectType).typePtr)
 }
 
 func (t reflectType) ConvertibleTo(u Type) bool {
@@ -413,7 +424,8 @@ func (t reflectType) ChanDir() ChanDir {
 }
 
 func (t reflectType) IsVariadic() bool {
-	panic("not implemented")
+	t.mustBeFunc("IsVariadic")
+	return native.type_is_variadic(t.typePtr)
 }
 
 func (t reflectType) Elem() Type {
@@ -437,7 +449,9 @@ func (t reflectType) FieldByNameFunc(match func(string) bool) (StructField, bool
 }
 
 func (t reflectType) In(i int) Type {
-	panic("not implemented")
+	t.mustBeFunc("In")
+	p, kind := native.type_in(t.typePtr, i)
+	return reflectType{typePtr: p, kind: Kind(kind)}
 }
 
 func (t reflectType) Key() Type {
@@ -453,15 +467,19 @@ func (t reflectType) NumField() int {
 }
 
 func (t reflectType) NumIn() int {
-	panic("not implemented")
+	t.mustBeFunc("NumIn")
+	return native.type_num_in(t.typePtr)
 }
 
 func (t reflectType) NumOut() int {
-	panic("not implemented")
+	t.mustBeFunc("NumOut")
+	return native.type_num_out(t.typePtr)
 }
 
 func (t reflectType) Out(i int) Type {
-	panic("not implemented")
+	t.mustBeFunc("Out")
+	p, kind := native.type_out(t.typePtr, i)
+	return reflectType{typePtr: p, kind: Kind(kind)}
 }
 
 // TypeOf returns the reflection Type that represents the dynamic type of i.
@@ -17,6 +17,18 @@ func init() {
 type ffiReflect interface {
 	value_of(i interface{}) unsafe.Pointer
 	type_of(p unsafe.Pointer) (unsafe.Pointer, uint)
+	type_num_in(t unsafe.Pointer) int
+	type_num_out(t unsafe.Pointer) int
+	type_in(t unsafe.Pointer, i int) (unsafe.Pointer, uint)
+	type_out(t unsafe.Pointer, i int) (unsafe.Pointer, uint)
+	type_is_variadic(t unsafe.Pointer) bool
+	type_assignable_to(t unsafe.Pointer, u unsafe.Pointer) bool
+	value_kind(p unsafe.Pointer) uint
+	interface_val(p unsafe.Pointer) interface{}
+	zero(t unsafe.Pointer) unsafe.Pointer
+	new(t unsafe.Pointer) unsafe.Pointer
+	make_slice(t unsafe.Pointer, len int, cap int) unsafe.Pointer
+	append(s unsafe.Pointer, x []unsafe.Pointer) unsafe.Pointer
 
 	bool_val(p unsafe.Pointer) bool
 	int_val(p unsafe.Pointer) int64
@@ -48,6 +60,10 @@ type ffiReflect interface {
 	set_string(p unsafe.Pointer, x string)
 
 	swap(slice interface{}, i int, j int)
+
+	method_by_name(p unsafe.Pointer, name string) unsafe.Pointer
+	num_out(p unsafe.Pointer) int
+	call(p unsafe.Pointer, in []unsafe.Pointer, out []unsafe.Pointer)
 }
 
 // Value is the reflection interface to a Go value.
@@ -126,7 +142,27 @@ func (v Value) CanSet() bool {
 // If v is a variadic function, Call creates the variadic slice parameter
 // itself, copying in the corresponding values.
 func (v Value) Call(in []Value) []Value {
-	panic("not implemented")
+	t := v.Type()
+	n := t.NumIn()
+	if t.IsVariadic() {
+		n--
+	}
+	for i := 0; i < n && i < len(in); i++ {
+		if xt, targ := in[i].Type(), t.In(i); !xt.AssignableTo(targ) {
+			panic("reflect: Call using " + xt.String() + " as type " + targ.String())
+		}
+	}
+	ptrs := make([]unsafe.Pointer, len(in))
+	for i, x := range in {
+		ptrs[i] = x.ptr
+	}
+	outs := make([]unsafe.Pointer, native.num_out(v.ptr))
+	native.call(v.ptr, ptrs, outs)
+	ret := make([]Value, len(outs))
+	for i, p := range outs {
+		ret[i] = valuePtrToValue(p)
+	}
+	return ret
 }
 
 // CallSlice calls the variadic function v with the input arguments in,
@@ -225,7 +261,7 @@ func (v Value) CanInterface() bool {
 // It panics if the Value was obtained by accessing
 // unexported struct fields.
 func (v Value) Interface() (i interface{}) {
-	panic("not implemented")
+	return native.interface_val(v.ptr)
 }
 
 // InterfaceData returns the interface v's value as a uintptr pair.
@@ -257,7 +293,10 @@ func (v Value) IsValid() bool {
 // Kind returns v's Kind.
 // If v is the zero Value (IsValid returns false), Kind returns Invalid.
 func (v Value) Kind() Kind {
-	return v.typ.kind
+	if v.ptr == nil {
+		return Invalid
+	}
+	return Kind(native.value_kind(v.ptr))
 }
 
 // Len returns v's length.
@@ -345,7 +384,11 @@ func (v Value) NumMethod() int {
 // a receiver; the returned function will always use v as the receiver.
 // It returns the zero Value if no method was found.
 func (v Value) MethodByName(name string) Value {
-	panic("not implemented")
+	p := native.method_by_name(v.ptr, name)
+	if p == nil {
+		return Value{}
+	}
+	return valuePtrToValue(p)
 }
 
 // NumField returns the number of fields in the struct v.
@@ -405,7 +448,10 @@ func (v Value) Send(x Value) {
 // It panics if CanSet returns false.
 // As in Go, x's value must be assignable to v's type.
 func (v Value) Set(x Value) {
-	native.set(v.ptr, x.ptr)
+	if !native.set(v.ptr, x.ptr) {
+		panic("reflect.Set: value of type " + x.Type().String() +
+			" is not assignable to type " + v.Type().String())
+	}
 }
 
 // SetBool sets v's underlying value.
@@ -501,7 +547,15 @@ func (v Value) Slice3(i, j, k int) Value {
 // The fmt package treats Values specially. It does not call their String
 // method implicitly but instead prints the concrete values they hold.
 func (v Value) String() string {
-	panic("not implemented")
+	switch k := v.Kind(); k {
+	case Invalid:
+		return "<invalid Value>"
+	case String:
+		return v.Interface().(string)
+	default:
+		// the type names are not available, the kind stands in for T
+		return "<" + k.String() + " Value>"
+	}
 }
 
 // TryRecv attempts to receive a value from the channel v but will not block.
@@ -565,7 +619,11 @@ type SliceHeader struct {
 // Append appends the values x to a slice s and returns the resulting slice.
 // As in Go, each x's value must be assignable to the slice's element type.
 func Append(s Value, x ...Value) Value {
-	panic("not implemented")
+	ptrs := make([]unsafe.Pointer, len(x))
+	for i, v := range x {
+		ptrs[i] = v.ptr
+	}
+	return valuePtrToValue(native.append(s.ptr, ptrs))
 }
 
 // AppendSlice appends a slice t to a slice s and returns the resulting slice.
@@ -634,7 +692,7 @@ func Select(cases []SelectCase) (chosen int, recv Value, recvOK bool) {
 // MakeSlice creates a new zero-initialized slice value
 // for the specified slice type, length, and capacity.
 func MakeSlice(typ Type, len, cap int) Value {
-	panic("not implemented")
+	return valuePtrToValue(native.make_slice(typ.(reflectType).typePtr, len, cap))
 }
 
 // MakeChan creates a new channel with the specified type and buffer size.
@@ -673,13 +731,13 @@ func ValueOf(i interface{}) Value {
 // For example, Zero(TypeOf(42)) returns a Value with Kind Int and value 0.
 // The returned value is neither addressable nor settable.
 func Zero(typ Type) Value {
-	panic("not implemented")
+	return valuePtrToValue(native.zero(typ.(reflectType).typePtr))
 }
 
 // New returns a Value representing a pointer to a new zero value
 // for the specified type. That is, the returned Value's Type is PtrTo(typ).
 func New(typ Type) Value {
-	panic("not implemented")
+	return valuePtrToValue(native.new(typ.(reflectType).typePtr))
 }
 
 // NewAt returns a Value representing a pointer to a value of the
@@ -4,9 +4,16 @@
 
 package time
 
+import "unsafe"
+
 // Sleep pauses the current goroutine for at least the duration d.
 // A negative or zero duration causes Sleep to return immediately.
-func Sleep(d Duration)
+func Sleep(d Duration) {
+	if d <= 0 {
+		return
+	}
+	ffi(ffiTime, "time").sleep_until(when(d))
+}
 
 // Interface to timers implemented in package runtime.
 // Must be in sync with ../runtime/time.go:/^type timer
@@ -19,6 +26,9 @@ type runtimeTimer struct {
 	f      func(interface{}, uintptr) // NOTE: must not be closure
 	arg    interface{}
 	seq    uintptr
+
+	// goscript: the pending sleep of the timer goroutine, Stop cancels it
+	sleep unsafe.Pointer
 }
 
 // when is a helper function for setting the 'when' field of a runtimeTimer.
@@ -36,13 +46,45 @@ func when(d Duration) int64 {
 	return t
 }
 
-// goscript: todo
-func startTimer(*runtimeTimer) {
+// goscript: each timer runs in its own goroutine, r.i is 1 while the timer
+// is active and r.seq tells the goroutine whether it has been restarted
+func startTimer(r *runtimeTimer) {
+	r.i = 1
+	r.seq++
+	seq := r.seq
+	go func() {
+		native := ffi(ffiTime, "time")
+		for {
+			// the timer may have been stopped before this goroutine started
+			if r.i != 1 || r.seq != seq {
+				return
+			}
+			r.sleep = native.new_sleep(r.when)
+			native.wait_sleep(r.sleep)
+			if r.i != 1 || r.seq != seq {
+				return
+			}
+			if r.period <= 0 {
+				r.i = 0
+			}
+			r.f(r.arg, r.seq)
+			if r.period <= 0 {
+				return
+			}
+			r.when += r.period
+		}
+	}()
 }
 
-// goscript: todo
-func stopTimer(*runtimeTimer) bool {
-	return true
+func stopTimer(r *runtimeTimer) bool {
+	active := r.i == 1
+	r.i = 0
+	if r.sleep != nil {
+		// wakes up the timer goroutine so that it returns
+		ffi(ffiTime, "time").cancel_sleep(r.sleep)
+		r.sleep = nil
+	}
+	return active
 }
 
 // The Timer type represents a single event.
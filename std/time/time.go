@@ -1067,15 +1067,13 @@ func daysIn(m Month, year int) int {
 	return int(daysBefore[m] - daysBefore[m-1])
 }
 
-// goscript: todo
 func now() (sec int64, nsec int32, mono int64) {
-	return 0, 0, 0
+	return ffi(ffiTime, "time").now()
 }
 
 // runtimeNano returns the current value of the runtime clock in nanoseconds.
-// goscript: todo
 func runtimeNano() int64 {
-	return 0
+	return ffi(ffiTime, "time").runtime_nano()
 }
 
 // Monotonic times are reported as offsets from startNano.
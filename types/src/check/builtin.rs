@@ -243,6 +243,21 @@ impl<'a> Checker<'a> {
                     _ => record(self, x.typ, &vec![ty], false),
                 }
             }
+            Builtin::Clear => {
+                // clear(m) and clear(s)
+                let t = x.typ.unwrap();
+                let utype = self.otype(t).underlying_val(self.tc_objs);
+                if utype.try_as_map().is_none() && utype.try_as_slice().is_none() {
+                    let xd = self.new_dis(x);
+                    self.invalid_arg(
+                        xd.pos(),
+                        &format!("cannot clear {}: argument must be map or slice", xd),
+                    );
+                    return false;
+                }
+                x.mode = OperandMode::NoValue;
+                record(self, None, &vec![t], false);
+            }
             Builtin::Close => {
                 // close(c)
                 let tkey = typ::underlying_type(x.typ.unwrap(), self.tc_objs);
@@ -598,6 +613,82 @@ impl<'a> Checker<'a> {
                     false,
                 );
             }
+            Builtin::Max | Builtin::Min => {
+                // max(x, y...) T and min(x, y...) T, where T is the type of the arguments
+                let op = if id == Builtin::Max {
+                    Token::GTR
+                } else {
+                    Token::LSS
+                };
+                for i in 0..nargs {
+                    let mut a = Operand::new();
+                    if i > 0 {
+                        // first argument already evaluated
+                        unpack_result.as_ref().unwrap().get(self, &mut a, i, fctx);
+                        if a.invalid() {
+                            return false;
+                        }
+                    }
+                    let arg = if i > 0 { &a } else { &*x };
+                    if !typ::is_ordered(arg.typ.unwrap(), self.tc_objs) {
+                        let ad = self.new_dis(arg);
+                        self.invalid_arg(ad.pos(), &format!("{} cannot be ordered", ad));
+                        return false;
+                    }
+                    if i == 0 {
+                        continue;
+                    }
+
+                    self.convert_untyped(x, a.typ.unwrap(), fctx);
+                    if x.invalid() {
+                        return false;
+                    }
+                    self.convert_untyped(&mut a, x.typ.unwrap(), fctx);
+                    if a.invalid() {
+                        return false;
+                    }
+                    if !typ::identical_o(x.typ, a.typ, self.tc_objs) {
+                        let xd = self.new_td_o(&x.typ);
+                        let ad = self.new_dis(a.expr.as_ref().unwrap());
+                        let atd = self.new_td_o(&a.typ);
+                        self.invalid_arg(
+                            a.pos(self.ast_objs),
+                            &format!(
+                                "mismatched types {} (previous argument) and {} (type of {})",
+                                xd, atd, ad
+                            ),
+                        );
+                        return false;
+                    }
+                    match (&x.mode, &a.mode) {
+                        (OperandMode::Constant(vx), OperandMode::Constant(va)) => {
+                            if Value::compare(va, &op, vx) {
+                                x.mode = a.mode.clone();
+                            }
+                        }
+                        _ => x.mode = OperandMode::Value,
+                    }
+                }
+
+                // a single argument must be a value or a constant
+                if x.mode.constant_val().is_none() {
+                    x.mode = OperandMode::Value;
+                    let msg = format!("argument to {}", self.builtin_info(id).name);
+                    self.assignment(x, None, &msg, fctx);
+                    if x.invalid() {
+                        return false;
+                    }
+                }
+
+                // use the final type computed above for all arguments
+                let t = x.typ.unwrap();
+                for arg in call.args.iter() {
+                    self.update_expr_type(arg, t, true, fctx);
+                }
+                if x.mode.constant_val().is_none() {
+                    record(self, Some(t), &vec![t; nargs], false);
+                }
+            }
             Builtin::New => {
                 // new(T)
                 // (no argument evaluated yet)
@@ -754,6 +845,33 @@ impl<'a> Checker<'a> {
                 // assert(pred) causes a typechecker error if pred is false.
                 // The result of assert is the value of pred if there is no error.
                 // oxfeefeee: minor change to make it work at runtime
+                // goscript: assert(pred, msg) takes an optional string message,
+                // a non-constant pred that is false panics at runtime with the
+                // source position and the message
+                if nargs > 2 {
+                    let expr = Expr::Call(call.clone());
+                    let ed = self.new_dis(&expr);
+                    self.invalid_op(
+                        call.r_paren,
+                        &format!(
+                            "too many arguments for {} (expected at most 2, found {})",
+                            &ed, nargs
+                        ),
+                    );
+                    return false;
+                }
+                let str_type = self.basic_type(BasicType::Str);
+                if nargs == 2 {
+                    let mut m = Operand::new();
+                    unpack_result.as_ref().unwrap().get(self, &mut m, 1, fctx);
+                    if m.invalid() {
+                        return false;
+                    }
+                    self.assignment(&mut m, Some(str_type), "argument to assert", fctx);
+                    if m.invalid() {
+                        return false;
+                    }
+                }
                 let default_err = || {
                     let xd = self.new_dis(x);
                     self.invalid_arg(xd.pos(), &format!("{} is not a boolean", xd));
@@ -791,7 +909,7 @@ impl<'a> Checker<'a> {
                         }
                         // only record when the argument is not constant
                         x.mode = OperandMode::NoValue;
-                        record(self, None, &vec![tkey], false);
+                        record(self, None, &[tkey, str_type][..nargs], false);
                     }
                 }
             }
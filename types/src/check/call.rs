@@ -63,9 +63,8 @@ impl<'a> Checker<'a> {
             }
             _ => {
                 // function/method call
-                let sig_key = typ::underlying_type(x.typ.unwrap(), self.tc_objs);
+                let mut sig_key = typ::underlying_type(x.typ.unwrap(), self.tc_objs);
                 if let Some(sig) = self.otype(sig_key).try_as_signature() {
-                    let sig_results = sig.results();
                     let variadic = sig.variadic();
                     let pcount = sig.params_count(self.tc_objs);
                     let result = self.unpack(&e.args, pcount, false, variadic, fctx);
@@ -73,12 +72,25 @@ impl<'a> Checker<'a> {
                         UnpackResult::Error => x.mode = OperandMode::Invalid,
                         _ => {
                             let (count, _) = result.rhs_count();
-                            let re = UnpackedResultLeftovers::new(&result, None);
+                            let consumed = match self.generic_func(x) {
+                                Some(_) => {
+                                    let args = self.call_inst(x, e, &result, count, fctx);
+                                    if x.invalid() {
+                                        x.expr = expr;
+                                        return ExprKind::Statement;
+                                    }
+                                    sig_key = x.typ.unwrap();
+                                    Some(args)
+                                }
+                                None => None,
+                            };
+                            let re = UnpackedResultLeftovers::new(&result, consumed.as_ref());
                             self.arguments(x, e, sig_key, &re, count, fctx);
                         }
                     }
 
                     // determine result
+                    let sig_results = self.otype(sig_key).try_as_signature().unwrap().results();
                     let sigre = self.tc_objs.types[sig_results].try_as_tuple().unwrap();
                     match sigre.vars().len() {
                         0 => x.mode = OperandMode::NoValue,
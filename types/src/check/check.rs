@@ -4,12 +4,13 @@ use super::super::importer::{Config, ImportKey, Importer};
 use super::super::objects::{DeclInfoKey, ObjKey, PackageKey, ScopeKey, TCObjects, TypeKey};
 use super::super::operand::OperandMode;
 use super::super::selection::Selection;
+use super::instantiate::GenericInfo;
 use super::interface::IfaceInfo;
 use goscript_parser::ast;
 use goscript_parser::ast::Node;
 use goscript_parser::ast::{Expr, NodeId};
 use goscript_parser::errors::{ErrorList, FilePosErrors};
-use goscript_parser::objects::{IdentKey, Objects as AstObjects};
+use goscript_parser::objects::{FuncDeclKey, IdentKey, Objects as AstObjects};
 use goscript_parser::position::Pos;
 use goscript_parser::FileSet;
 use std::cell::RefCell;
@@ -117,6 +118,11 @@ pub struct TypeInfo {
     /// in source order. Variables without an initialization expression do not
     /// appear in this list.
     pub init_order: Vec<Initializer>,
+    /// 'instances' are the declarations of the instances of generic functions,
+    /// each one is a copy of the generic declaration checked with the type
+    /// parameters bound to the type arguments. Generic declarations themselves
+    /// are not checked.
+    pub instances: Vec<FuncDeclKey>,
     /// oxfeeefeee: parse result of the package, to be used by code gen
     pub ast_files: Vec<ast::File>,
 }
@@ -131,6 +137,7 @@ impl TypeInfo {
             selections: HashMap::new(),
             scopes: HashMap::new(),
             init_order: Vec::new(),
+            instances: Vec::new(),
             ast_files: Vec::new(),
         }
     }
@@ -164,6 +171,8 @@ pub struct ObjContext {
     pub has_label: bool,
     // set if an expression contains a function call or channel receive operation
     pub has_call_or_recv: bool,
+    // set while the constraint of a type parameter is evaluated
+    pub in_constraint: bool,
 }
 
 type DelayedAction = Box<dyn FnOnce(&mut Checker, &mut FilesContext)>;
@@ -180,6 +189,8 @@ pub struct FilesContext<'a> {
     // maps package scope type names(LangObj::TypeName) to associated
     // non-blank, non-interface methods(LangObj::Func)
     pub methods: HashMap<ObjKey, Vec<ObjKey>>,
+    // maps generic type names to the declarations of their methods
+    pub generic_methods: HashMap<ObjKey, Vec<DeclInfoKey>>,
     // maps interface(LangObj::TypeName) type names to corresponding
     // interface infos
     pub ifaces: HashMap<ObjKey, Option<RcIfaceInfo>>,
@@ -217,6 +228,8 @@ pub struct Checker<'a> {
     config: &'a Config,
     // result of type checking
     pub result: TypeInfo,
+    // maps generic functions to their type parameters and instances
+    pub generics: HashMap<ObjKey, GenericInfo>,
     // for debug
     pub indent: Rc<RefCell<usize>>,
 }
@@ -232,6 +245,7 @@ impl ObjContext {
             panics: None,
             has_label: false,
             has_call_or_recv: false,
+            in_constraint: false,
         }
     }
 }
@@ -242,6 +256,7 @@ impl FilesContext<'_> {
             files: files,
             unused_dot_imports: HashMap::new(),
             methods: HashMap::new(),
+            generic_methods: HashMap::new(),
             ifaces: HashMap::new(),
             untyped: HashMap::new(),
             delayed: Vec::new(),
@@ -411,6 +426,7 @@ impl<'a> Checker<'a> {
             octx: ObjContext::new(),
             config: cfg,
             result: TypeInfo::new(),
+            generics: HashMap::new(),
             indent: Rc::new(RefCell::new(0)),
         }
     }
@@ -130,7 +130,11 @@ impl<'a> Checker<'a> {
                     EntityType::TypeName => {
                         let cd = d.as_type();
                         let (typ, alias) = (cd.typ.clone(), cd.alias);
-                        self.type_decl(okey, &typ, def, alias, fctx);
+                        if cd.tparams.is_some() {
+                            self.generic_type_decl(okey, dkey, fctx);
+                        } else {
+                            self.type_decl(okey, &typ, def, alias, fctx);
+                        }
                     }
                     EntityType::Func(_) => {
                         self.func_decl(okey, dkey, fctx);
@@ -338,7 +342,7 @@ impl<'a> Checker<'a> {
 
         // determine type, if any
         if let Some(texpr) = typ {
-            let t = self.type_expr(texpr, fctx);
+            let t = self.var_type(texpr, fctx);
             self.lobj_mut(okey).set_type(Some(t));
             // We cannot spread the type to all lhs variables if there
             // are more than one since that would mark them as checked
@@ -447,6 +451,11 @@ impl<'a> Checker<'a> {
         let fdecl_key = d.fdecl;
         let fdecl = &self.ast_objs.fdecls[fdecl_key];
         let (recv, typ) = (fdecl.recv.clone(), fdecl.typ);
+        if self.ast_objs.ftypes[typ].type_params.is_some() {
+            // generic functions are only checked when instantiated
+            self.generic_func_decl(okey, dkey, fctx);
+            return;
+        }
         let sig_key = self.func_type(recv.as_ref(), typ, fctx);
         self.lobj_mut(okey).set_type(Some(sig_key));
 
@@ -683,6 +692,10 @@ impl<'a> Checker<'a> {
                         ast::Spec::Type(ts) => {
                             let ident = self.ast_ident(ts.name);
                             let (pos, name) = (ident.pos, ident.name.clone());
+                            if ts.type_params.is_some() {
+                                let msg = "generic type cannot be declared in a function";
+                                self.error(pos, msg.to_owned());
+                            }
                             let okey = self.tc_objs.new_type_name(pos, Some(self.pkg), name, None);
                             // spec: "The scope of a type identifier declared inside a function
                             // begins at the identifier in the TypeSpec and ends at the end of
@@ -246,6 +246,7 @@ impl<'a> Checker<'a> {
             | Expr::FuncLit(_)
             | Expr::CompositeLit(_)
             | Expr::Index(_)
+            | Expr::IndexList(_)
             | Expr::Slice(_)
             | Expr::TypeAssert(_)
             | Expr::Star(_)
@@ -1281,7 +1282,15 @@ impl<'a> Checker<'a> {
                 self.selector(x, s, fctx);
             }
             Expr::Index(ie) => {
-                self.expr(x, &ie.expr, fctx);
+                self.expr_or_type(x, &ie.expr, fctx);
+                if !x.invalid() && (self.generic_func(x).or(self.generic_type(x))).is_some() {
+                    self.generic_inst(x, e, &vec![ie.index.clone()], fctx);
+                    if x.invalid() {
+                        return on_err(x);
+                    }
+                    return ExprKind::Expression;
+                }
+                self.expr_value_err(x);
                 if x.invalid() {
                     self.use_exprs(&vec![ie.index.clone()], fctx);
                     return on_err(x);
@@ -1355,6 +1364,26 @@ impl<'a> Checker<'a> {
                 let _ = self.index(&ie.index, length, fctx);
                 // ok to continue
             }
+            Expr::IndexList(ie) => {
+                self.expr_or_type(x, &ie.expr, fctx);
+                if x.invalid() {
+                    self.use_exprs(&ie.indices, fctx);
+                    return on_err(x);
+                }
+                if (self.generic_func(x).or(self.generic_type(x))).is_none() {
+                    let xd = self.new_dis(x);
+                    self.invalid_op(
+                        xd.pos(),
+                        &format!("{} is not a generic function or type", xd),
+                    );
+                    self.use_exprs(&ie.indices, fctx);
+                    return on_err(x);
+                }
+                self.generic_inst(x, e, &ie.indices, fctx);
+                if x.invalid() {
+                    return on_err(x);
+                }
+            }
             Expr::Slice(se) => {
                 self.expr(x, &se.expr, fctx);
                 if x.invalid() {
@@ -1580,6 +1609,12 @@ impl<'a> Checker<'a> {
             OperandMode::TypeExpr => Some("is not an expression"),
             _ => None,
         };
+        if msg.is_none() && self.generic_func(x).is_some() {
+            let xd = self.new_dis(x.expr.as_ref().unwrap());
+            let msg = format!("cannot use generic function {} without instantiation", xd);
+            self.error(xd.pos(), msg);
+            x.mode = OperandMode::Invalid;
+        }
         if let Some(m) = msg {
             let xd = self.new_dis(x);
             self.error(xd.pos(), format!("{} {}", xd, m));
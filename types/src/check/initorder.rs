@@ -150,22 +150,28 @@ impl<'a> Checker<'a> {
         // which are permitted. Yet such cycles may incorrectly inflate the dependency
         // count for variables which in turn may not get scheduled for initialization
         // in correct order.)
+        // All function nodes have to be removed before counting the dependencies
+        // of the remaining nodes, as removing a function adds edges to its callers.
+        for (o, node) in map.iter() {
+            if self.lobj(*o).entity_type().is_func() {
+                for p in node.pred.borrow().iter() {
+                    if p != o {
+                        for s in node.succ.borrow().iter() {
+                            if s != o {
+                                map[p].succ.borrow_mut().insert(*s);
+                                map[s].pred.borrow_mut().insert(*p);
+                                map[s].pred.borrow_mut().remove(o);
+                            }
+                        }
+                        map[p].succ.borrow_mut().remove(o);
+                    }
+                }
+            }
+        }
         let mut nodes: Vec<GraphNode> = map
             .iter()
             .filter_map(|(o, node)| {
                 if self.lobj(*o).entity_type().is_func() {
-                    for p in node.pred.borrow().iter() {
-                        if p != o {
-                            for s in node.succ.borrow().iter() {
-                                if s != o {
-                                    map[p].succ.borrow_mut().insert(*s);
-                                    map[s].pred.borrow_mut().insert(*p);
-                                    map[s].pred.borrow_mut().remove(o);
-                                }
-                            }
-                            map[p].succ.borrow_mut().remove(o);
-                        }
-                    }
                     None
                 } else {
                     Some(GraphNode {
@@ -0,0 +1,712 @@
+// This file implements generic functions and types by instantiation.
+//
+// A generic declaration is not checked on its own. The signature of a generic
+// function is computed with each type parameter bound to a placeholder type
+// whose underlying type is the constraint, which is all that is needed to
+// infer type arguments. Each distinct list of type arguments a generic function
+// or type is used with creates an instance: a copy of the declaration (and of
+// the methods, for a type) that is checked, and later compiled, like a regular
+// declaration with the type parameters bound to the type arguments.
+
+#![allow(dead_code)]
+use super::super::lookup;
+use super::super::objects::{DeclInfoKey, ObjKey, ScopeKey, TypeKey};
+use super::super::operand::{Operand, OperandMode};
+use super::super::scope::Scope;
+use super::super::typ::{self, Type};
+use super::check::{Checker, FilesContext};
+use super::resolver::DeclInfo;
+use super::util::UnpackResult;
+use goscript_parser::ast::{CallExpr, Expr, FieldList, Node};
+use goscript_parser::deep_clone::DeepClone;
+use goscript_parser::Pos;
+use std::collections::HashMap;
+
+/// TypeParam is a type parameter of a generic function or type
+#[derive(Debug)]
+pub struct TypeParam {
+    pub name: String,
+    // the placeholder type the type parameter is bound to in the signature
+    pub typ: TypeKey,
+    pub constraint: TypeKey,
+}
+
+/// GenericInfo describes a generic function or type
+#[derive(Debug)]
+pub struct GenericInfo {
+    pub decl: DeclInfoKey,
+    pub tparams: Vec<TypeParam>,
+    // instances created so far, with their type arguments
+    pub instances: Vec<(Vec<TypeKey>, ObjKey)>,
+    // declarations of the methods of a generic type
+    pub methods: Vec<DeclInfoKey>,
+}
+
+impl<'a> Checker<'a> {
+    /// generic_func_decl computes the signature of a generic function, the type
+    /// parameters are bound to placeholder types.
+    pub fn generic_func_decl(&mut self, okey: ObjKey, dkey: DeclInfoKey, fctx: &mut FilesContext) {
+        let fdecl = self.tc_objs.decls[dkey].as_func().fdecl;
+        let ftype = self.ast_objs.fdecls[fdecl].typ;
+        let fields = self.ast_objs.ftypes[ftype].type_params.clone().unwrap();
+        let (skey, tparams) = self.type_params(&fields, fctx);
+        let old_scope = self.octx.scope.replace(skey);
+        let sig = self.func_type(None, ftype, fctx);
+        self.octx.scope = old_scope;
+        self.lobj_mut(okey).set_type(Some(sig));
+        self.generics.insert(
+            okey,
+            GenericInfo {
+                decl: dkey,
+                tparams,
+                instances: vec![],
+                methods: vec![],
+            },
+        );
+    }
+
+    /// generic_type_decl declares a generic type, its methods have been collected
+    /// in fctx.generic_methods.
+    pub fn generic_type_decl(&mut self, okey: ObjKey, dkey: DeclInfoKey, fctx: &mut FilesContext) {
+        let fields = self.tc_objs.decls[dkey].as_type().tparams.clone().unwrap();
+        let (_, tparams) = self.type_params(&fields, fctx);
+        let invalid = self.invalid_type();
+        let t = self.tc_objs.new_t_named(Some(okey), Some(invalid), vec![]);
+        self.lobj_mut(okey).set_type(Some(t));
+
+        let methods = fctx.generic_methods.remove(&okey).unwrap_or_default();
+        let methods = methods
+            .into_iter()
+            .filter(|m| {
+                let recv_typ = self.method_recv_type(*m);
+                match self.recv_type_params(&recv_typ) {
+                    Some(names) if names.len() == tparams.len() => true,
+                    _ => {
+                        let name = self.lobj(okey).name();
+                        self.error(
+                            recv_typ.pos(self.ast_objs),
+                            format!("receiver must list the type parameters of {}", name),
+                        );
+                        false
+                    }
+                }
+            })
+            .collect();
+        self.generics.insert(
+            okey,
+            GenericInfo {
+                decl: dkey,
+                tparams,
+                instances: vec![],
+                methods,
+            },
+        );
+    }
+
+    /// type_params declares the type parameters in a new scope and evaluates
+    /// their constraints.
+    fn type_params(
+        &mut self,
+        fields: &FieldList,
+        fctx: &mut FilesContext,
+    ) -> (ScopeKey, Vec<TypeParam>) {
+        let skey =
+            self.tc_objs
+                .new_scope(self.octx.scope, 0, 0, "type parameters".to_owned(), false);
+
+        // declare all type parameters first, so that constraints can refer to them
+        let mut tparams = vec![];
+        for f in fields.list.iter() {
+            for ikey in self.ast_objs.fields[*f].names.clone() {
+                let ident = self.ast_ident(ikey);
+                let (pos, name) = (ident.pos, ident.name.clone());
+                // no package, so that the placeholder prints as just the name
+                let tname = self.tc_objs.new_type_name(pos, None, name.clone(), None);
+                let placeholder = self.tc_objs.new_t_named(Some(tname), None, vec![]);
+                self.lobj_mut(tname).set_type(Some(placeholder));
+                self.declare(skey, Some(ikey), tname, 0);
+                tparams.push(TypeParam {
+                    name,
+                    typ: placeholder,
+                    constraint: self.invalid_type(),
+                });
+            }
+        }
+
+        let old_scope = self.octx.scope.replace(skey);
+        let mut i = 0;
+        for f in fields.list.iter() {
+            let field = &self.ast_objs.fields[*f];
+            let (count, texpr) = (field.names.len(), field.typ.clone());
+            // a constraint other than an interface, e.g. [T ~int | ~string],
+            // is short for the interface embedding it
+            let bound = if Checker::is_union_expr(&texpr) {
+                let terms = self.union_terms(&texpr, fctx);
+                self.implicit_constraint(terms)
+            } else {
+                let in_constraint = std::mem::replace(&mut self.octx.in_constraint, true);
+                let t = self.type_expr(&texpr, fctx);
+                self.octx.in_constraint = in_constraint;
+                let under = typ::underlying_type(t, self.tc_objs);
+                if t != self.invalid_type() && self.otype(under).try_as_interface().is_none() {
+                    self.implicit_constraint(vec![typ::TypeTerm {
+                        tilde: false,
+                        typ: t,
+                    }])
+                } else {
+                    t
+                }
+            };
+            let underlying = typ::underlying_type(bound, self.tc_objs);
+            for tp in tparams[i..i + count].iter_mut() {
+                tp.constraint = bound;
+                self.tc_objs.types[tp.typ]
+                    .try_as_named_mut()
+                    .unwrap()
+                    .set_underlying(underlying);
+            }
+            i += count;
+        }
+        self.octx.scope = old_scope;
+        (skey, tparams)
+    }
+
+    /// implicit_constraint returns the interface whose type set is the union of terms.
+    fn implicit_constraint(&mut self, terms: Vec<typ::TypeTerm>) -> TypeKey {
+        let t = self.tc_objs.new_t_interface(vec![], vec![]);
+        let iface = self.otype_interface_mut(t);
+        iface.set_empty_complete();
+        iface.unions_mut().push(terms);
+        t
+    }
+
+    /// generic_func returns the generic function x denotes, if any.
+    pub fn generic_func(&self, x: &Operand) -> Option<ObjKey> {
+        match x.mode {
+            OperandMode::Value => self.generic_obj(x),
+            _ => None,
+        }
+    }
+
+    /// generic_type returns the generic type x denotes, if any.
+    pub fn generic_type(&self, x: &Operand) -> Option<ObjKey> {
+        match x.mode {
+            OperandMode::TypeExpr => self.generic_obj(x),
+            _ => None,
+        }
+    }
+
+    fn generic_obj(&self, x: &Operand) -> Option<ObjKey> {
+        let ikey = match x.expr.as_ref().map(Checker::unparen) {
+            Some(Expr::Ident(ikey)) => Some(ikey),
+            Some(Expr::Selector(s)) => Some(&s.sel),
+            _ => None,
+        };
+        ikey.and_then(|i| self.result.uses.get(i))
+            .filter(|o| self.generics.contains_key(o))
+            .copied()
+    }
+
+    /// generic_inst instantiates the generic function or type x denotes with
+    /// the explicit type arguments in indices, e is the index expression.
+    pub fn generic_inst(
+        &mut self,
+        x: &mut Operand,
+        e: &Expr,
+        indices: &[Expr],
+        fctx: &mut FilesContext,
+    ) {
+        let targs: Vec<TypeKey> = indices.iter().map(|t| self.type_expr(t, fctx)).collect();
+        let pos = e.pos(self.ast_objs);
+        if targs.iter().any(|t| *t == self.invalid_type()) {
+            x.mode = OperandMode::Invalid;
+        } else if let Some(okey) = self.generic_type(x) {
+            match self.instantiate_type(okey, targs, pos, fctx) {
+                Some(t) => x.typ = Some(t),
+                None => x.mode = OperandMode::Invalid,
+            }
+        } else {
+            let okey = self.generic_func(x).unwrap();
+            match self.instantiate(okey, targs, pos, fctx) {
+                Some(inst) => self.use_instance(x, inst),
+                None => x.mode = OperandMode::Invalid,
+            }
+        }
+        x.expr = Some(e.clone());
+    }
+
+    /// call_inst instantiates the generic function x denotes with the type
+    /// arguments inferred from the arguments of call, which are evaluated and
+    /// returned so that they are not evaluated again when they are checked.
+    pub fn call_inst(
+        &mut self,
+        x: &mut Operand,
+        call: &CallExpr,
+        args: &UnpackResult,
+        count: usize,
+        fctx: &mut FilesContext,
+    ) -> Vec<Operand> {
+        let okey = self.generic_func(x).unwrap();
+        let operands: Vec<Operand> = (0..count)
+            .map(|i| {
+                let mut arg = Operand::new();
+                args.get(self, &mut arg, i, fctx);
+                arg
+            })
+            .collect();
+        let pos = call.func.pos(self.ast_objs);
+        let inst = self
+            .infer(okey, &operands, call.ellipsis.is_some(), pos)
+            .and_then(|targs| self.instantiate(okey, targs, pos, fctx));
+        match inst {
+            Some(inst) => self.use_instance(x, inst),
+            None => x.mode = OperandMode::Invalid,
+        }
+        operands
+    }
+
+    /// use_instance makes x, which denotes a generic function, denote the
+    /// instance inst instead.
+    pub fn use_instance(&mut self, x: &mut Operand, inst: ObjKey) {
+        let e = x.expr.clone().unwrap();
+        x.typ = self.lobj(inst).typ();
+        let ident = Checker::unparen(&e);
+        if let Expr::Ident(ikey) = ident {
+            self.result.record_use(*ikey, inst);
+        }
+        self.result
+            .record_type_and_value(ident, x.mode.clone(), x.typ.unwrap());
+        self.result
+            .record_type_and_value(&e, x.mode.clone(), x.typ.unwrap());
+    }
+
+    /// infer infers the type arguments of the generic function okey from the
+    /// arguments of a call.
+    pub fn infer(
+        &mut self,
+        okey: ObjKey,
+        args: &[Operand],
+        ellipsis: bool,
+        pos: Pos,
+    ) -> Option<Vec<TypeKey>> {
+        let info = &self.generics[&okey];
+        let placeholders: HashMap<TypeKey, usize> = info
+            .tparams
+            .iter()
+            .enumerate()
+            .map(|(i, tp)| (tp.typ, i))
+            .collect();
+        let sig = self
+            .otype(self.lobj(okey).typ().unwrap())
+            .try_as_signature()
+            .unwrap();
+        let variadic = sig.variadic() && !ellipsis;
+        let params: Vec<TypeKey> = self
+            .otype(sig.params())
+            .try_as_tuple()
+            .unwrap()
+            .vars()
+            .iter()
+            .map(|v| self.lobj(*v).typ().unwrap())
+            .collect();
+        let param_type = |i: usize| -> Option<TypeKey> {
+            if variadic && i >= params.len() - 1 {
+                let last = params[params.len() - 1];
+                self.otype(last).try_as_slice().map(|s| s.elem())
+            } else {
+                params.get(i).copied()
+            }
+        };
+
+        let mut targs = vec![None; placeholders.len()];
+        // typed arguments first, untyped constants only determine the type
+        // parameters that are still unknown afterwards
+        let mut untyped = vec![];
+        for (i, arg) in args.iter().enumerate() {
+            if arg.invalid() {
+                return None;
+            }
+            if let Some(pt) = param_type(i) {
+                let t = arg.typ.unwrap();
+                if typ::is_untyped(t, self.tc_objs) {
+                    untyped.push((pt, t));
+                } else {
+                    self.unify(pt, t, &placeholders, &mut targs);
+                }
+            }
+        }
+        for (pt, t) in untyped {
+            if let Some(i) = placeholders.get(&pt) {
+                if targs[*i].is_none() && t != self.basic_type(typ::BasicType::UntypedNil) {
+                    targs[*i] = Some(typ::untyped_default_type(t, self.tc_objs));
+                }
+            }
+        }
+
+        match targs.iter().position(|t| t.is_none()) {
+            Some(i) => {
+                let name = &self.generics[&okey].tparams[i].name;
+                self.error(pos, format!("cannot infer {}", name));
+                None
+            }
+            None => Some(targs.into_iter().map(|t| t.unwrap()).collect()),
+        }
+    }
+
+    /// unify binds the placeholders in x so that x matches y, it returns false
+    /// if they cannot match, in which case the mismatch is reported later when
+    /// the arguments are checked against the instance.
+    fn unify(
+        &self,
+        x: TypeKey,
+        y: TypeKey,
+        placeholders: &HashMap<TypeKey, usize>,
+        targs: &mut Vec<Option<TypeKey>>,
+    ) -> bool {
+        if let Some(i) = placeholders.get(&x) {
+            return match targs[*i] {
+                Some(t) => typ::identical(t, y, self.tc_objs),
+                None => {
+                    targs[*i] = Some(y);
+                    true
+                }
+            };
+        }
+        if x == y {
+            return true;
+        }
+        let objs = &self.tc_objs;
+        let tuple_vars = |t: TypeKey| -> Vec<TypeKey> {
+            objs.types[t]
+                .try_as_tuple()
+                .unwrap()
+                .vars()
+                .iter()
+                .map(|v| objs.lobjs[*v].typ().unwrap())
+                .collect()
+        };
+        let unify_list = |this: &Self, xs: Vec<TypeKey>, ys: Vec<TypeKey>, targs: &mut _| {
+            xs.len() == ys.len()
+                && xs
+                    .into_iter()
+                    .zip(ys)
+                    .all(|(a, b)| this.unify(a, b, placeholders, targs))
+        };
+        match (&objs.types[x], &objs.types[y]) {
+            (Type::Named(_), _) => typ::identical(x, y, objs),
+            // an unnamed parameter type matches the underlying type of a named argument
+            (_, Type::Named(n)) => self.unify(x, n.underlying(), placeholders, targs),
+            (Type::Array(xa), Type::Array(ya)) => {
+                xa.len() == ya.len() && self.unify(xa.elem(), ya.elem(), placeholders, targs)
+            }
+            (Type::Slice(xs), Type::Slice(ys)) => {
+                self.unify(xs.elem(), ys.elem(), placeholders, targs)
+            }
+            (Type::Pointer(xp), Type::Pointer(yp)) => {
+                self.unify(xp.base(), yp.base(), placeholders, targs)
+            }
+            (Type::Map(xm), Type::Map(ym)) => {
+                self.unify(xm.key(), ym.key(), placeholders, targs)
+                    && self.unify(xm.elem(), ym.elem(), placeholders, targs)
+            }
+            (Type::Chan(xc), Type::Chan(yc)) => {
+                self.unify(xc.elem(), yc.elem(), placeholders, targs)
+            }
+            (Type::Signature(xs), Type::Signature(ys)) => {
+                xs.variadic() == ys.variadic()
+                    && unify_list(
+                        self,
+                        tuple_vars(xs.params()),
+                        tuple_vars(ys.params()),
+                        targs,
+                    )
+                    && unify_list(
+                        self,
+                        tuple_vars(xs.results()),
+                        tuple_vars(ys.results()),
+                        targs,
+                    )
+            }
+            (Type::Struct(xs), Type::Struct(ys)) => {
+                let names = |fields: &Vec<ObjKey>| -> Vec<String> {
+                    fields
+                        .iter()
+                        .map(|f| objs.lobjs[*f].name().clone())
+                        .collect()
+                };
+                let types = |fields: &Vec<ObjKey>| -> Vec<TypeKey> {
+                    fields
+                        .iter()
+                        .map(|f| objs.lobjs[*f].typ().unwrap())
+                        .collect()
+                };
+                names(xs.fields()) == names(ys.fields())
+                    && unify_list(self, types(xs.fields()), types(ys.fields()), targs)
+            }
+            _ => typ::identical(x, y, objs),
+        }
+    }
+
+    /// verify_type_args reports whether targs are valid type arguments for the
+    /// generic function or type okey.
+    fn verify_type_args(&self, okey: ObjKey, targs: &[TypeKey], pos: Pos) -> bool {
+        let tparams = &self.generics[&okey].tparams;
+        if targs.len() != tparams.len() {
+            let msg = if targs.len() < tparams.len() {
+                "not enough"
+            } else {
+                "too many"
+            };
+            let name = self.lobj(okey).name();
+            self.error(pos, format!("{} type arguments for {}", msg, name));
+            return false;
+        }
+        for (tp, t) in tparams.iter().zip(targs.iter()) {
+            if let Some(msg) = self.unsatisfied(*t, tp.constraint) {
+                let (td, cd) = (self.new_dis(t), self.new_dis(&tp.constraint));
+                self.error(pos, format!("{} does not satisfy {} ({})", td, cd, msg));
+                return false;
+            }
+        }
+        true
+    }
+
+    /// find_instance returns the existing instance of okey for targs, if any.
+    fn find_instance(&self, okey: ObjKey, targs: &[TypeKey]) -> Option<ObjKey> {
+        self.generics[&okey]
+            .instances
+            .iter()
+            .find(|(args, _)| {
+                args.iter()
+                    .zip(targs.iter())
+                    .all(|(a, b)| typ::identical(*a, *b, self.tc_objs))
+            })
+            .map(|(_, inst)| *inst)
+    }
+
+    /// instance_scope returns a new scope in which the type parameters names
+    /// denote the type arguments.
+    fn instance_scope(
+        &mut self,
+        parent: ScopeKey,
+        names: Vec<String>,
+        targs: &[TypeKey],
+    ) -> ScopeKey {
+        let skey = self
+            .tc_objs
+            .new_scope(Some(parent), 0, 0, "instance".to_owned(), false);
+        for (name, t) in names.into_iter().zip(targs.iter()) {
+            let tname = self
+                .tc_objs
+                .new_type_name(0, Some(self.pkg), name, Some(*t));
+            Scope::insert(skey, tname, self.tc_objs);
+        }
+        skey
+    }
+
+    /// instance_name returns the name of the instance of okey for targs,
+    /// e.g. Map[int,string].
+    fn instance_name(&self, okey: ObjKey, targs: &[TypeKey]) -> String {
+        let targ_strs: Vec<String> = targs.iter().map(|t| self.new_dis(t).to_string()).collect();
+        format!("{}[{}]", self.lobj(okey).name(), targ_strs.join(","))
+    }
+
+    /// instance_func declares a copy of the function declaration dkey as the
+    /// function or method inst, to be checked in scope skey.
+    fn instance_func(&mut self, dkey: DeclInfoKey, skey: ScopeKey, name: Option<String>) -> ObjKey {
+        let fdecl = self.tc_objs.decls[dkey].as_func().fdecl;
+        let clone = fdecl.deep_clone(self.ast_objs);
+        let clone_val = &self.ast_objs.fdecls[clone];
+        let (ikey, ftype) = (clone_val.name, clone_val.typ);
+        if let Some(n) = name {
+            self.ast_objs.idents[ikey].name = n;
+        }
+        self.ast_objs.ftypes[ftype].type_params = None;
+
+        let ident = self.ast_ident(ikey);
+        let inst = self
+            .tc_objs
+            .new_func(ident.pos, Some(self.pkg), ident.name.clone(), None);
+        self.result.record_def(ikey, Some(inst));
+        let di = self.tc_objs.decls.insert(DeclInfo::new_func(skey, clone));
+        self.obj_map.insert(inst, di);
+        let order = self.obj_map.len() as u32;
+        self.lobj_mut(inst).set_order(order);
+        self.result.instances.push(clone);
+        inst
+    }
+
+    /// instantiate returns the instance of the generic function okey for the
+    /// type arguments targs, it is created and checked if it doesn't exist yet.
+    pub fn instantiate(
+        &mut self,
+        okey: ObjKey,
+        targs: Vec<TypeKey>,
+        pos: Pos,
+        fctx: &mut FilesContext,
+    ) -> Option<ObjKey> {
+        if !self.verify_type_args(okey, &targs, pos) {
+            return None;
+        }
+        if let Some(inst) = self.find_instance(okey, &targs) {
+            return Some(inst);
+        }
+
+        // check a copy of the declaration, with the type parameters bound
+        // to the type arguments
+        let info = &self.generics[&okey];
+        let dkey = info.decl;
+        let names = info.tparams.iter().map(|tp| tp.name.clone()).collect();
+        let file_scope = *self.tc_objs.decls[dkey].file_scope();
+        let skey = self.instance_scope(file_scope, names, &targs);
+        let name = self.instance_name(okey, &targs);
+        let inst = self.instance_func(dkey, skey, Some(name));
+        self.generics
+            .get_mut(&okey)
+            .unwrap()
+            .instances
+            .push((targs, inst));
+
+        self.obj_decl(inst, None, fctx);
+        Some(inst)
+    }
+
+    /// instantiate_type returns the instance of the generic type okey for the
+    /// type arguments targs, it is created and checked, together with its
+    /// methods, if it doesn't exist yet.
+    pub fn instantiate_type(
+        &mut self,
+        okey: ObjKey,
+        targs: Vec<TypeKey>,
+        pos: Pos,
+        fctx: &mut FilesContext,
+    ) -> Option<TypeKey> {
+        if !self.verify_type_args(okey, &targs, pos) {
+            return None;
+        }
+        if let Some(inst) = self.find_instance(okey, &targs) {
+            return self.lobj(inst).typ();
+        }
+
+        let info = &self.generics[&okey];
+        let (dkey, methods) = (info.decl, info.methods.clone());
+        let names = info.tparams.iter().map(|tp| tp.name.clone()).collect();
+        let tdecl = self.tc_objs.decls[dkey].as_type();
+        let (file_scope, typ) = (tdecl.file_scope, tdecl.typ.clone());
+        let skey = self.instance_scope(file_scope, names, &targs);
+        let name = self.instance_name(okey, &targs);
+        let tpos = self.lobj(okey).pos();
+        let inst = self.tc_objs.new_type_name(tpos, Some(self.pkg), name, None);
+        let clone = typ.deep_clone(self.ast_objs);
+        let di = self
+            .tc_objs
+            .decls
+            .insert(DeclInfo::new_type(skey, clone, false, None));
+        self.obj_map.insert(inst, di);
+        let order = self.obj_map.len() as u32;
+        self.lobj_mut(inst).set_order(order);
+        // registered before checking, the type may refer to itself
+        self.generics
+            .get_mut(&okey)
+            .unwrap()
+            .instances
+            .push((targs.clone(), inst));
+
+        // the methods are bound to the type when it's declared
+        let mut inst_methods = vec![];
+        for m in methods {
+            let recv_typ = self.method_recv_type(m);
+            let names = self.recv_type_params(&recv_typ).unwrap();
+            let mfile_scope = *self.tc_objs.decls[m].file_scope();
+            let mskey = self.instance_scope(mfile_scope, names, &targs);
+            let method = self.instance_func(m, mskey, None);
+            let ptr = matches!(Checker::unparen(&recv_typ), Expr::Star(_));
+            self.lobj_mut(method)
+                .entity_type_mut()
+                .func_set_has_ptr_recv(ptr);
+            fctx.methods.entry(inst).or_default().push(method);
+            inst_methods.push(method);
+        }
+
+        self.obj_decl(inst, None, fctx);
+        for m in inst_methods {
+            self.obj_decl(m, None, fctx);
+        }
+        self.lobj(inst).typ()
+    }
+
+    fn method_recv_type(&self, dkey: DeclInfoKey) -> Expr {
+        let fdecl = self.tc_objs.decls[dkey].as_func().fdecl;
+        let recv = self.ast_objs.fdecls[fdecl].recv.as_ref().unwrap();
+        self.ast_objs.fields[recv.list[0]].typ.clone()
+    }
+
+    /// recv_type_params returns the names of the type parameters in the
+    /// receiver type of a method of a generic type.
+    fn recv_type_params(&self, recv_typ: &Expr) -> Option<Vec<String>> {
+        let base = match Checker::unparen(recv_typ) {
+            Expr::Star(s) => Checker::unparen(&s.expr),
+            t => t,
+        };
+        let indices = match base {
+            Expr::Index(ie) => vec![&ie.index],
+            Expr::IndexList(ie) => ie.indices.iter().collect(),
+            _ => return None,
+        };
+        indices
+            .into_iter()
+            .map(|e| match e {
+                Expr::Ident(i) => Some(self.ast_ident(*i).name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// unsatisfied returns why t doesn't satisfy the constraint, if it doesn't.
+    fn unsatisfied(&self, t: TypeKey, constraint: TypeKey) -> Option<String> {
+        if constraint == *self.tc_objs.universe().comparable() {
+            return match typ::comparable(t, self.tc_objs) {
+                true => None,
+                false => Some("not comparable".to_owned()),
+            };
+        }
+        let iface = typ::underlying_type(constraint, self.tc_objs);
+        let detail = self.otype(iface).try_as_interface()?;
+        for terms in detail.unions().iter() {
+            if !terms.iter().any(|term| self.has_term(t, term)) {
+                let union: Vec<String> = terms
+                    .iter()
+                    .map(|term| {
+                        let tilde = if term.tilde { "~" } else { "" };
+                        format!("{}{}", tilde, self.new_dis(&term.typ))
+                    })
+                    .collect();
+                let td = self.new_dis(&t);
+                return Some(format!("{} missing in {}", td, union.join(" | ")));
+            }
+        }
+        for e in detail.embeddeds().iter() {
+            if let Some(msg) = self.unsatisfied(t, *e) {
+                return Some(msg);
+            }
+        }
+        lookup::missing_method(t, iface, true, self.tc_objs)
+            .map(|(m, _)| format!("missing method {}", self.lobj(m).name()))
+    }
+
+    /// has_term reports whether t is in the type set of term.
+    fn has_term(&self, t: TypeKey, term: &typ::TypeTerm) -> bool {
+        let objs = &self.tc_objs;
+        if term.tilde {
+            let (x, y) = (
+                typ::underlying_type(t, objs),
+                typ::underlying_type(term.typ, objs),
+            );
+            return typ::identical(x, y, objs);
+        }
+        match self.otype(typ::underlying_type(term.typ, objs)) {
+            Type::Interface(_) => self.unsatisfied(t, term.typ).is_none(),
+            _ => typ::identical(t, term.typ, objs),
+        }
+    }
+}
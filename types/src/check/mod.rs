@@ -9,6 +9,7 @@ mod conversion;
 mod decl;
 mod expr;
 mod initorder;
+mod instantiate;
 mod interface;
 mod label;
 mod resolver;
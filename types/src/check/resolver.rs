@@ -31,6 +31,7 @@ pub struct DeclInfoType {
     pub file_scope: ScopeKey, // scope of file containing this declaration
     pub typ: Expr,            // type
     pub alias: bool,          // type alias declaration
+    pub tparams: Option<ast::FieldList>, // type parameters of a generic type
 }
 
 #[derive(Debug)]
@@ -74,11 +75,17 @@ impl DeclInfo {
         })
     }
 
-    pub fn new_type(file_scope: ScopeKey, typ: Expr, alias: bool) -> DeclInfo {
+    pub fn new_type(
+        file_scope: ScopeKey,
+        typ: Expr,
+        alias: bool,
+        tparams: Option<ast::FieldList>,
+    ) -> DeclInfo {
         DeclInfo::Type(DeclInfoType {
             file_scope: file_scope,
             typ: typ,
             alias: alias,
+            tparams: tparams,
         })
     }
 
@@ -378,6 +385,7 @@ impl<'a> Checker<'a> {
                                         file_scope,
                                         tspec.typ.clone(),
                                         tspec.assign > 0,
+                                        tspec.type_params.clone(),
                                     ));
                                     let _ = self.declare_pkg_obj(tspec.name, okey, di);
                                 }
@@ -474,6 +482,14 @@ impl<'a> Checker<'a> {
                 // determine the receiver base type and associate f with it.
                 let typ = &self.ast_objs.fields[fl.list[0]].typ;
                 if let Some((ptr, base)) = self.resolve_base_type_name(typ) {
+                    let di = self.obj_map[&base];
+                    if self.tc_objs.decls[di].as_type().tparams.is_some() {
+                        // methods of a generic type are only checked when
+                        // the type is instantiated
+                        let fdi = self.obj_map.remove(&f).unwrap();
+                        fctx.generic_methods.entry(base).or_default().push(fdi);
+                        continue;
+                    }
                     self.lobj_mut(f)
                         .entity_type_mut()
                         .func_set_has_ptr_recv(ptr);
@@ -547,7 +563,7 @@ impl<'a> Checker<'a> {
                             } else {
                                 self.soft_error(
                                     obj_val.pos(),
-                                    format!("{} imported but not used as {}", path, base),
+                                    format!("{} imported but not used as {}", path, obj_val.name()),
                                 );
                             }
                         }
@@ -631,6 +647,13 @@ impl<'a> Checker<'a> {
                 ptr = true;
                 typ = Checker::unparen(&t.expr);
             }
+            // the receiver type of a method of a generic type lists the
+            // type parameters
+            match typ {
+                Expr::Index(ie) => typ = &ie.expr,
+                Expr::IndexList(ie) => typ = &ie.expr,
+                _ => {}
+            }
 
             // typ must be the name
             if let Expr::Ident(i) = typ {
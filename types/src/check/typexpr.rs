@@ -158,6 +158,61 @@ impl<'a> Checker<'a> {
         t
     }
 
+    /// var_type is like type_expr but also reports an error if e denotes
+    /// a constraint interface, which can only be used as a type constraint.
+    pub fn var_type(&mut self, e: &Expr, fctx: &mut FilesContext) -> TypeKey {
+        let t = self.type_expr(e, fctx);
+        self.valid_var_type(e, t, fctx);
+        t
+    }
+
+    /// indirect_var_type is to indirect_type what var_type is to type_expr.
+    pub fn indirect_var_type(&mut self, e: &Expr, fctx: &mut FilesContext) -> TypeKey {
+        let t = self.indirect_type(e, fctx);
+        self.valid_var_type(e, t, fctx);
+        t
+    }
+
+    fn valid_var_type(&mut self, e: &Expr, t: TypeKey, fctx: &mut FilesContext) {
+        let pos = e.pos(self.ast_objs);
+        // Delay the check so that the embedded interfaces are collected.
+        let f = move |checker: &mut Checker, _: &mut FilesContext| {
+            let is_tparam = checker
+                .generics
+                .values()
+                .any(|g| g.tparams.iter().any(|tp| tp.typ == t));
+            if !is_tparam && checker.is_constraint_iface(t, &mut vec![]) {
+                checker.error_str(
+                    pos,
+                    "cannot use interface with type constraints outside a type constraint",
+                );
+            }
+        };
+        fctx.later(Box::new(f));
+    }
+
+    /// is_constraint_iface reports whether t is an interface with type terms,
+    /// directly or through its embedded interfaces.
+    fn is_constraint_iface(&self, t: TypeKey, seen: &mut Vec<TypeKey>) -> bool {
+        if t == *self.tc_objs.universe().comparable() {
+            return true;
+        }
+        if seen.contains(&t) {
+            return false;
+        }
+        seen.push(t);
+        match self.otype(typ::underlying_type(t, self.tc_objs)) {
+            Type::Interface(iface) => {
+                !iface.unions().is_empty()
+                    || iface
+                        .embeddeds()
+                        .iter()
+                        .any(|e| self.is_constraint_iface(*e, seen))
+            }
+            _ => false,
+        }
+    }
+
     /// indirect_type is like type_expr but it also breaks the (otherwise) infinite size of
     /// recursivetypes by introducing an indirection. It should be called for components of
     /// types that are not laid out in place in memory, such as pointer base types, slice or
@@ -278,6 +333,12 @@ impl<'a> Checker<'a> {
                 let mut x = Operand::new();
                 self.ident(&mut x, *i, def, true, fctx);
                 match x.mode {
+                    OperandMode::TypeExpr if self.generic_type(&x).is_some() => {
+                        let xd = self.new_dis(x.expr.as_ref().unwrap());
+                        let msg = format!("cannot use generic type {} without instantiation", xd);
+                        self.error(xd.pos(), msg);
+                        None
+                    }
                     OperandMode::TypeExpr => {
                         set_underlying(x.typ, self.tc_objs);
                         x.typ
@@ -313,15 +374,38 @@ impl<'a> Checker<'a> {
                 }
             }
             Expr::Paren(p) => Some(self.defined_type(&p.expr, def, fctx)),
+            Expr::Index(_) | Expr::IndexList(_) => {
+                let (base, indices) = match e {
+                    Expr::Index(ie) => (&ie.expr, vec![ie.index.clone()]),
+                    Expr::IndexList(ie) => (&ie.expr, ie.indices.clone()),
+                    _ => unreachable!(),
+                };
+                let mut x = Operand::new();
+                self.expr_or_type(&mut x, base, fctx);
+                if x.invalid() {
+                    None
+                } else if self.generic_type(&x).is_none() {
+                    error_operand!(x, "{} is not a generic type", self);
+                    None
+                } else {
+                    self.generic_inst(&mut x, e, &indices, fctx);
+                    if x.invalid() {
+                        None
+                    } else {
+                        set_underlying(x.typ, self.tc_objs);
+                        x.typ
+                    }
+                }
+            }
             Expr::Array(a) => {
                 if let Some(l) = &a.len {
                     let len = self.array_len(&l, fctx);
-                    let elem = self.type_expr(&a.elt, fctx);
+                    let elem = self.var_type(&a.elt, fctx);
                     let t = self.tc_objs.new_t_array(elem, len);
                     set_underlying(Some(t), self.tc_objs);
                     Some(t)
                 } else {
-                    let elem = self.indirect_type(&a.elt, fctx);
+                    let elem = self.indirect_var_type(&a.elt, fctx);
                     let t = self.tc_objs.new_t_slice(elem);
                     set_underlying(Some(t), self.tc_objs);
                     Some(t)
@@ -333,7 +417,7 @@ impl<'a> Checker<'a> {
                 Some(t)
             }
             Expr::Star(s) => {
-                let base = self.indirect_type(&s.expr, fctx);
+                let base = self.indirect_var_type(&s.expr, fctx);
                 let t = self.tc_objs.new_t_pointer(base);
                 set_underlying(Some(t), self.tc_objs);
                 Some(t)
@@ -349,8 +433,8 @@ impl<'a> Checker<'a> {
                 Some(t)
             }
             Expr::Map(m) => {
-                let k = self.indirect_type(&m.key, fctx);
-                let v = self.indirect_type(&m.val, fctx);
+                let k = self.indirect_var_type(&m.key, fctx);
+                let v = self.indirect_var_type(&m.val, fctx);
                 let t = self.tc_objs.new_t_map(k, v);
                 set_underlying(Some(t), self.tc_objs);
 
@@ -371,7 +455,7 @@ impl<'a> Checker<'a> {
                     ast::ChanDir::Recv => typ::ChanDir::RecvOnly,
                     ast::ChanDir::SendRecv => typ::ChanDir::SendRecv,
                 };
-                let elem = self.indirect_type(&chan.val, fctx);
+                let elem = self.indirect_var_type(&chan.val, fctx);
                 let t = self.tc_objs.new_t_chan(dir, elem);
                 set_underlying(Some(t), self.tc_objs);
                 Some(t)
@@ -475,7 +559,7 @@ impl<'a> Checker<'a> {
                     }
                 }
                 let ftype = &ftype.clone();
-                let ty = self.indirect_type(ftype, fctx);
+                let ty = self.indirect_var_type(ftype, fctx);
                 // The parser ensures that f.Tag is nil and we don't
                 // care if a constructed AST contains a non-nil tag.
                 if field_names.len() > 0 {
@@ -534,6 +618,49 @@ impl<'a> Checker<'a> {
         }
     }
 
+    /// is_union_expr reports whether e is a union of type terms or a ~T term,
+    /// the other elements of an interface are methods and embedded types.
+    pub fn is_union_expr(e: &Expr) -> bool {
+        match Checker::unparen(e) {
+            Expr::Binary(b) => b.op == Token::OR,
+            Expr::Unary(u) => u.op == Token::TILDE,
+            _ => false,
+        }
+    }
+
+    /// union_terms evaluates the terms of the union e.
+    pub fn union_terms(&mut self, e: &Expr, fctx: &mut FilesContext) -> Vec<typ::TypeTerm> {
+        match Checker::unparen(e) {
+            Expr::Binary(b) if b.op == Token::OR => {
+                let mut terms = self.union_terms(&b.expr_a, fctx);
+                terms.append(&mut self.union_terms(&b.expr_b, fctx));
+                terms
+            }
+            Expr::Unary(u) if u.op == Token::TILDE => {
+                let t = self.indirect_type(&u.expr, fctx);
+                let under = typ::underlying_type(t, self.tc_objs);
+                if t != self.invalid_type() && t != under {
+                    let (td, ud) = (self.new_dis(&t), self.new_dis(&under));
+                    self.error(
+                        u.op_pos,
+                        format!("invalid use of ~ (underlying type of {} is {})", td, ud),
+                    );
+                }
+                vec![typ::TypeTerm {
+                    tilde: true,
+                    typ: t,
+                }]
+            }
+            _ => {
+                let t = self.indirect_type(e, fctx);
+                vec![typ::TypeTerm {
+                    tilde: false,
+                    typ: t,
+                }]
+            }
+        }
+    }
+
     fn interface_type(
         &mut self,
         expr: &ast::Expr,
@@ -570,7 +697,7 @@ impl<'a> Checker<'a> {
             let mut embeds = vec![];
             for fkey in iface_clone.methods.list.iter() {
                 let field = &checker.ast_objs.fields[*fkey];
-                if field.names.len() == 0 {
+                if field.names.is_empty() && !Checker::is_union_expr(&field.typ) {
                     let texpr = field.typ.clone();
                     let ty = checker.indirect_type(&texpr, fctx);
                     // ty should be a named type denoting an interface
@@ -584,12 +711,24 @@ impl<'a> Checker<'a> {
                             // Correct embedded interfaces must be complete
                             assert!(embed.all_methods().is_some());
                         }
-                        _ => {
+                        _ if !checker.octx.in_constraint => {
                             let pos = texpr.pos(checker.ast_objs);
                             let td = checker.new_dis(&ty);
                             checker.error(pos, format!("{} is not an interface", td));
                             continue;
                         }
+                        _ => {
+                            // a single type term in a constraint, e.g. [T interface{ int }]
+                            let term = typ::TypeTerm {
+                                tilde: false,
+                                typ: ty,
+                            };
+                            checker
+                                .otype_interface_mut(itype)
+                                .unions_mut()
+                                .push(vec![term]);
+                            continue;
+                        }
                     }
                     // collect interface
                     embeds.push(ty);
@@ -610,6 +749,16 @@ impl<'a> Checker<'a> {
         };
         fctx.later(Box::new(f));
 
+        // collect the unions of type terms
+        for fkey in iface.methods.list.iter() {
+            let field = &self.ast_objs.fields[*fkey];
+            if field.names.is_empty() && Checker::is_union_expr(&field.typ) {
+                let texpr = field.typ.clone();
+                let terms = self.union_terms(&texpr, fctx);
+                self.otype_interface_mut(itype).unions_mut().push(terms);
+            }
+        }
+
         // compute method set
         let (tname, path) = if let Some(d) = def {
             let t = *self.otype(d).try_as_named().unwrap().obj();
@@ -829,7 +978,7 @@ impl<'a> Checker<'a> {
             let fnames = field.names.clone();
             let ftag = self.tag(&field.tag);
             let ftype = field.typ.clone();
-            let ty = self.type_expr(&ftype, fctx);
+            let ty = self.var_type(&ftype, fctx);
             if fnames.len() > 0 {
                 // named fields
                 for name in fnames.iter() {
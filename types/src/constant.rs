@@ -779,6 +779,7 @@ pub fn short_quote_str(s: &str, max: usize) -> String {
 }
 
 pub fn int_from_literal(lit: &str) -> Value {
+    let lit = lit.replace('_', "");
     let result = if lit.starts_with("0x") {
         BigInt::from_str_radix(&lit[2..], 16)
     } else if lit.starts_with("0o") {
@@ -786,7 +787,7 @@ pub fn int_from_literal(lit: &str) -> Value {
     } else if lit.starts_with("0b") {
         BigInt::from_str_radix(&lit[2..], 2)
     } else {
-        BigInt::from_str_radix(lit, 10)
+        BigInt::from_str_radix(&lit, 10)
     };
     match result {
         Ok(i) => Value::Int(i),
@@ -795,12 +796,47 @@ pub fn int_from_literal(lit: &str) -> Value {
 }
 
 pub fn float_from_literal(lit: &str) -> Value {
+    let lit = lit.replace('_', "");
+    if let Some(hex) = lit.strip_prefix("0x") {
+        return hex_float_from_literal(hex);
+    }
+    if lit.starts_with("0o") || lit.starts_with("0b") {
+        // the mantissa of an imaginary literal like 0o17i
+        return match int_from_literal(&lit) {
+            Value::Int(i) => Value::with_f64(i.to_f64().unwrap_or(f64::INFINITY)),
+            _ => Value::Unknown,
+        };
+    }
     match lit.parse::<f64>() {
         Ok(f) => Value::with_f64(f),
         Err(_) => Value::Unknown,
     }
 }
 
+/// Converts the part after "0x" of a literal like 0x1.8p3, the exponent is
+/// optional so that hexadecimal imaginary literals like 0x10i work too
+fn hex_float_from_literal(lit: &str) -> Value {
+    let (mantissa, exp) = match lit.find('p') {
+        Some(i) => match lit[i + 1..].parse::<i32>() {
+            Ok(exp) => (&lit[..i], exp),
+            Err(_) => return Value::Unknown,
+        },
+        None => (lit, 0),
+    };
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+    let digits = format!("{}{}", int_part, frac_part);
+    match BigInt::from_str_radix(&digits, 16) {
+        Ok(m) => {
+            let f = m.to_f64().unwrap_or(f64::INFINITY);
+            Value::with_f64(f * 2f64.powi(exp - 4 * frac_part.len() as i32))
+        }
+        Err(_) => Value::Unknown,
+    }
+}
+
 fn shorten_with_ellipsis(s: String, max: usize) -> String {
     if s.len() <= max {
         s
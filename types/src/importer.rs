@@ -21,9 +21,28 @@ pub struct Config {
     pub trace_parser: bool,
     // print debug info in checker
     pub trace_checker: bool,
+    // in-memory source files by name, consulted before the file system, an
+    // import path names the file with that name or all the files in it
+    pub sources: HashMap<String, String>,
 }
 
 impl Config {
+    /// Returns the in-memory files of the package at the import path, sorted
+    /// by name
+    fn virtual_files(&self, path: &str) -> Vec<(String, String)> {
+        let dir = format!("{}/", path.trim_end_matches('/'));
+        let mut files: Vec<(String, String)> = self
+            .sources
+            .iter()
+            .filter(|(name, _)| {
+                *name == path || name.strip_prefix(&dir).map_or(false, |n| !n.contains('/'))
+            })
+            .map(|(name, src)| (name.clone(), src.clone()))
+            .collect();
+        files.sort();
+        files
+    }
+
     fn get_working_dir(&self) -> io::Result<PathBuf> {
         if let Some(wd) = &self.work_dir {
             let mut buf = PathBuf::new();
@@ -94,15 +113,28 @@ impl<'a> Importer<'a> {
         if key.path == "unsafe" {
             return Ok(*self.tc_objs.universe().unsafe_pkg());
         }
-        let pb = self.validate_path(key)?;
-        let path = pb.0.as_path();
-        let import_path = pb.1;
-        match self.pkgs.get(&import_path) {
+        let vfiles = self.config.virtual_files(&key.path);
+        let (cache_key, import_path, dir) = if vfiles.is_empty() {
+            let (dir, import_path) = self.validate_path(key)?;
+            // the same local package reached through different relative paths
+            // must map to a single package
+            let cache_key = match is_local(&key.path) {
+                true => dir.to_string_lossy().to_string(),
+                false => import_path.clone(),
+            };
+            (cache_key, import_path, Some(dir))
+        } else {
+            (key.path.clone(), key.path.clone(), None)
+        };
+        match self.pkgs.get(&cache_key) {
             Some(key) => Ok(*key),
             None => {
-                let pkg = self.tc_objs.new_package(import_path.clone());
-                self.pkgs.insert(import_path, pkg);
-                let files = self.parse_dir(path)?;
+                let pkg = self.tc_objs.new_package(import_path);
+                self.pkgs.insert(cache_key, pkg);
+                let files = match dir {
+                    Some(dir) => self.parse_dir(dir.as_path())?,
+                    None => self.parse_files(vfiles)?,
+                };
                 Checker::new(
                     self.tc_objs,
                     self.ast_objs,
@@ -172,38 +204,21 @@ impl<'a> Importer<'a> {
                     self.error(format!("no source file found in dir: {}", path.display()));
                     Err(())
                 } else {
-                    let mut afiles = vec![];
-                    for (path_buf, content) in contents.into_iter() {
-                        // try get short display name for the file
-                        let p = path_buf.as_path();
-                        let full_name = match &working_dir {
-                            Some(wd) => p.strip_prefix(wd).unwrap_or(p),
-                            None => p,
-                        }
-                        .to_string_lossy()
-                        .to_string();
-                        let mut pfile = self.fset.add_file(
-                            full_name,
-                            Some(self.fset.base()),
-                            content.chars().count(),
-                        );
-                        let afile = Parser::new(
-                            self.ast_objs,
-                            &mut pfile,
-                            self.errors,
-                            &content,
-                            self.config.trace_parser,
-                        )
-                        .parse_file();
-                        if afile.is_none() {
-                            // parse error, the details should be in the errorlist already.
-                            // give up
-                            return Err(());
-                        } else {
-                            afiles.push(afile.unwrap());
-                        }
-                    }
-                    Ok(afiles)
+                    let contents = contents
+                        .into_iter()
+                        .map(|(path_buf, content)| {
+                            // try get short display name for the file
+                            let p = path_buf.as_path();
+                            let full_name = match &working_dir {
+                                Some(wd) => p.strip_prefix(wd).unwrap_or(p),
+                                None => p,
+                            }
+                            .to_string_lossy()
+                            .to_string();
+                            (full_name, content)
+                        })
+                        .collect();
+                    self.parse_files(contents)
                 }
             }
             Err(_) => {
@@ -213,6 +228,31 @@ impl<'a> Importer<'a> {
         }
     }
 
+    fn parse_files(&mut self, contents: Vec<(String, String)>) -> Result<Vec<ast::File>, ()> {
+        let mut afiles = vec![];
+        for (name, content) in contents.into_iter() {
+            let mut pfile =
+                self.fset
+                    .add_file(name, Some(self.fset.base()), content.chars().count());
+            let afile = Parser::new(
+                self.ast_objs,
+                &mut pfile,
+                self.errors,
+                &content,
+                self.config.trace_parser,
+            )
+            .parse_file();
+            if afile.is_none() {
+                // parse error, the details should be in the errorlist already.
+                // give up
+                return Err(());
+            } else {
+                afiles.push(afile.unwrap());
+            }
+        }
+        Ok(afiles)
+    }
+
     fn error(&self, err: String) {
         let pos_file = self.fset.file(self.pos).unwrap();
         FilePosErrors::new(pos_file, self.errors).add(self.pos, err, false);
@@ -388,6 +388,18 @@ impl<'a, 'b> ExprVisitor for ExprFormater<'a, 'b> {
         self.f.write_char(']')
     }
 
+    fn visit_expr_index_list(&mut self, _: &Expr, expr: &Expr, indices: &[Expr]) -> Self::Result {
+        self.visit_expr(expr)?;
+        self.f.write_char('[')?;
+        for (i, index) in indices.iter().enumerate() {
+            if i > 0 {
+                self.f.write_str(", ")?;
+            }
+            self.visit_expr(index)?;
+        }
+        self.f.write_char(']')
+    }
+
     fn visit_expr_slice(
         &mut self,
         _: &Expr,
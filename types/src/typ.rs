@@ -676,12 +676,22 @@ impl SignatureDetail {
     }
 }
 
+/// A TypeTerm is a term of a union in a constraint interface: T or ~T
+#[derive(Debug, Clone, Copy)]
+pub struct TypeTerm {
+    pub tilde: bool,
+    pub typ: TypeKey,
+}
+
 /// An InterfaceDetail represents an interface type.
 #[derive(Debug)]
 pub struct InterfaceDetail {
     methods: Vec<ObjKey>,
     embeddeds: Vec<TypeKey>,
     all_methods: Rc<RefCell<Option<Vec<ObjKey>>>>,
+    // the unions listed in a constraint interface, the type set of
+    // the interface is the intersection of them
+    unions: Vec<Vec<TypeTerm>>,
 }
 
 impl InterfaceDetail {
@@ -718,6 +728,7 @@ impl InterfaceDetail {
             methods: methods,
             embeddeds: embeddeds,
             all_methods: Rc::new(RefCell::new(None)),
+            unions: Vec::new(),
         }
     }
 
@@ -726,6 +737,7 @@ impl InterfaceDetail {
             methods: Vec::new(),
             embeddeds: Vec::new(),
             all_methods: Rc::new(RefCell::new(Some(Vec::new()))),
+            unions: Vec::new(),
         }
     }
 
@@ -745,6 +757,14 @@ impl InterfaceDetail {
         &mut self.embeddeds
     }
 
+    pub fn unions(&self) -> &Vec<Vec<TypeTerm>> {
+        &self.unions
+    }
+
+    pub fn unions_mut(&mut self) -> &mut Vec<Vec<TypeTerm>> {
+        &mut self.unions
+    }
+
     pub fn all_methods(&self) -> Ref<Option<Vec<ObjKey>>> {
         self.all_methods.borrow()
     }
@@ -1237,6 +1257,20 @@ fn fmt_type_impl(
                 }
                 fmt_type_impl(Some(*k), f, visited, objs)?;
             }
+            for (i, terms) in detail.unions().iter().enumerate() {
+                if i > 0 || detail.methods().len() + detail.embeddeds().len() > 0 {
+                    f.write_str("; ")?;
+                }
+                for (j, term) in terms.iter().enumerate() {
+                    if j > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    if term.tilde {
+                        f.write_char('~')?;
+                    }
+                    fmt_type_impl(Some(term.typ), f, visited, objs)?;
+                }
+            }
             if detail.all_methods().is_none() {
                 f.write_str(" /* incomplete */")?;
             }
@@ -1283,6 +1317,8 @@ fn fmt_type_impl(
             }
         }
     }
+    // only the types being written are cycles, not the ones used twice
+    visited.remove(&tkey);
     Ok(())
 }
 
@@ -1294,8 +1330,12 @@ fn fmt_signature_impl(
 ) -> fmt::Result {
     let sig = &objs.types[t].try_as_signature().unwrap();
     fmt_tuple(sig.params(), sig.variadic(), f, visited, &objs)?;
-    f.write_char(' ')?;
     let results = &objs.types[sig.results()].try_as_tuple().unwrap();
+    if results.vars().is_empty() {
+        // no result
+        return Ok(());
+    }
+    f.write_char(' ')?;
     if results.vars().len() == 1 {
         let obj = &objs.lobjs[results.vars()[0]];
         if obj.name().is_empty() {
@@ -22,6 +22,7 @@ pub enum ExprKind {
 pub enum Builtin {
     Append,
     Cap,
+    Clear,
     Close,
     Complex,
     Copy,
@@ -29,6 +30,8 @@ pub enum Builtin {
     Imag,
     Len,
     Make,
+    Max,
+    Min,
     New,
     Panic,
     Print,
@@ -72,6 +75,8 @@ pub struct Universe {
     indir: ObjKey,
     // guard_sig is a empty signature type used to guard against func cycles
     guard_sig: TypeKey,
+    // comparable is the constraint satisfied by all comparable types
+    comparable: TypeKey,
     types: HashMap<BasicType, TypeKey>,
     builtins: HashMap<Builtin, BuiltinInfo>,
 }
@@ -90,6 +95,7 @@ impl Universe {
             objs,
         );
         Universe::def_error_type(&types, &uskey, &unsafe_, objs);
+        let comparable = Universe::def_any_comparable(&uskey, &unsafe_, objs);
         // consts
         Universe::def_consts(&types, &uskey, &unsafe_, objs);
         Universe::def_nil(&types, &uskey, &unsafe_, objs);
@@ -113,6 +119,7 @@ impl Universe {
             no_value_tuple: no_value_tuple,
             indir: indir,
             guard_sig: guard_sig,
+            comparable: comparable,
             types: types,
             builtins: builtins,
         }
@@ -162,6 +169,10 @@ impl Universe {
         &self.guard_sig
     }
 
+    pub fn comparable(&self) -> &TypeKey {
+        &self.comparable
+    }
+
     fn def_universe_unsafe(objs: &mut TCObjects) -> (ScopeKey, PackageKey) {
         let uskey = objs
             .scopes
@@ -220,6 +231,25 @@ impl Universe {
         Universe::def(type_name, universe, unsafe_, objs);
     }
 
+    ///define this:
+    ///type any = interface{}
+    ///type comparable interface{ comparable }
+    fn def_any_comparable(
+        universe: &ScopeKey,
+        unsafe_: &PackageKey,
+        objs: &mut TCObjects,
+    ) -> TypeKey {
+        let empty = objs.new_t_empty_interface();
+        let any = objs.new_type_name(0, None, "any".to_owned(), Some(empty));
+        Universe::def(any, universe, unsafe_, objs);
+
+        let underlying = objs.new_t_empty_interface();
+        let typ = objs.new_t_named(None, Some(underlying), vec![]);
+        let comparable = objs.new_type_name(0, None, "comparable".to_owned(), Some(typ));
+        Universe::def(comparable, universe, unsafe_, objs);
+        typ
+    }
+
     fn def_basic_types(
         types: &HashMap<BasicType, TypeKey>,
         universe: &ScopeKey,
@@ -331,6 +361,7 @@ impl Universe {
             // use vec becasue array doesn't have into_iter()!
             (Builtin::Append, "append", 1, true, ExprKind::Expression),
             (Builtin::Cap, "cap", 1, false, ExprKind::Expression),
+            (Builtin::Clear, "clear", 1, false, ExprKind::Statement),
             (Builtin::Close, "close", 1, false, ExprKind::Statement),
             (Builtin::Complex, "complex", 2, false, ExprKind::Expression),
             (Builtin::Copy, "copy", 2, false, ExprKind::Statement),
@@ -338,6 +369,8 @@ impl Universe {
             (Builtin::Imag, "imag", 1, false, ExprKind::Expression),
             (Builtin::Len, "len", 1, false, ExprKind::Expression),
             (Builtin::Make, "make", 1, true, ExprKind::Expression),
+            (Builtin::Max, "max", 1, true, ExprKind::Expression),
+            (Builtin::Min, "min", 1, true, ExprKind::Expression),
             (Builtin::New, "new", 1, false, ExprKind::Expression),
             (Builtin::Panic, "panic", 1, false, ExprKind::Statement),
             (Builtin::Print, "print", 0, true, ExprKind::Statement),
@@ -353,7 +386,7 @@ impl Universe {
                 ExprKind::Expression,
             ),
             (Builtin::Sizeof, "Sizeof", 1, false, ExprKind::Expression),
-            (Builtin::Assert, "assert", 1, false, ExprKind::Statement),
+            (Builtin::Assert, "assert", 1, true, ExprKind::Statement),
             (Builtin::Trace, "trace", 0, true, ExprKind::Statement),
             (Builtin::Ffi, "ffi", 2, false, ExprKind::Expression),
         ]
@@ -67,6 +67,7 @@ fn test_file(path: &str, trace: bool) {
         base_path: None,
         trace_parser: trace,
         trace_checker: trace,
+        sources: HashMap::new(),
     };
     let fs = &mut fe::FileSet::new();
     let asto = &mut fe::objects::Objects::new();
@@ -197,6 +198,11 @@ fn test_auto() {
     test_file("./tests/data/vardecl.src", trace);
 }
 
+#[test]
+fn test_constraints() {
+    test_file("./tests/data/constraints.src", false);
+}
+
 #[test]
 fn test_temp() {
     test_file("./tests/data/temp.gos", true);
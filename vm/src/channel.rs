@@ -2,7 +2,7 @@ use super::instruction::*;
 use super::value::*;
 use futures_lite::future;
 use rand::prelude::*;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::mem;
 use std::rc::Rc;
 
@@ -13,19 +13,58 @@ pub enum RendezvousState {
     Closed,
 }
 
+/// An unbuffered channel, a value is handed from a sender to a receiver
+/// through the slot
+#[derive(Debug)]
+pub struct Rendezvous {
+    state: RefCell<RendezvousState>,
+    // the number of values received so far
+    received: Cell<usize>,
+    // the number of goroutines waiting to receive, including selects with a
+    // receive case on the channel
+    receivers: Cell<usize>,
+}
+
 #[derive(Clone, Debug)]
 pub enum Channel {
     Bounded(
         async_channel::Sender<GosValue>,
         async_channel::Receiver<GosValue>,
     ),
-    Rendezvous(Rc<RefCell<RendezvousState>>),
+    Rendezvous(Rc<Rendezvous>),
+}
+
+// counts a waiting receiver of an unbuffered channel until it's dropped
+struct Receiving(Option<Rc<Rendezvous>>);
+
+impl Receiving {
+    fn new(chan: &Channel) -> Receiving {
+        match chan {
+            Channel::Bounded(_, _) => Receiving(None),
+            Channel::Rendezvous(rv) => {
+                rv.receivers.set(rv.receivers.get() + 1);
+                Receiving(Some(rv.clone()))
+            }
+        }
+    }
+}
+
+impl Drop for Receiving {
+    fn drop(&mut self) {
+        if let Some(rv) = &self.0 {
+            rv.receivers.set(rv.receivers.get() - 1);
+        }
+    }
 }
 
 impl Channel {
     pub fn new(cap: usize) -> Channel {
         if cap == 0 {
-            Channel::Rendezvous(Rc::new(RefCell::new(RendezvousState::Empty)))
+            Channel::Rendezvous(Rc::new(Rendezvous {
+                state: RefCell::new(RendezvousState::Empty),
+                received: Cell::new(0),
+                receivers: Cell::new(0),
+            }))
         } else {
             let (s, r) = async_channel::bounded(cap);
             Channel::Bounded(s, r)
@@ -49,25 +88,30 @@ impl Channel {
     }
 
     #[inline]
-    pub fn close(&self) {
-        match self {
-            Channel::Bounded(s, _) => {
-                s.close();
+    pub fn close(&self) -> RuntimeResult<()> {
+        let closed = match self {
+            Channel::Bounded(s, _) => !s.close(),
+            Channel::Rendezvous(rv) => {
+                let old = mem::replace(&mut *rv.state.borrow_mut(), RendezvousState::Closed);
+                matches!(old, RendezvousState::Closed)
             }
-            Channel::Rendezvous(state) => *state.borrow_mut() = RendezvousState::Closed,
+        };
+        match closed {
+            true => Err("close of closed channel".to_owned()),
+            false => Ok(()),
         }
     }
 
     pub fn try_send(&self, v: GosValue) -> Result<(), async_channel::TrySendError<GosValue>> {
         match self {
             Channel::Bounded(s, _) => s.try_send(v),
-            Channel::Rendezvous(state) => {
-                let state_ref = state.borrow();
+            Channel::Rendezvous(rv) => {
+                let state_ref = rv.state.borrow();
                 let s: &RendezvousState = &state_ref;
                 match s {
                     RendezvousState::Empty => {
                         drop(state_ref);
-                        *state.borrow_mut() = RendezvousState::Full(v);
+                        *rv.state.borrow_mut() = RendezvousState::Full(v);
                         Ok(())
                     }
                     RendezvousState::Full(_) => Err(async_channel::TrySendError::Full(v)),
@@ -80,16 +124,17 @@ impl Channel {
     pub fn try_recv(&self) -> Result<GosValue, async_channel::TryRecvError> {
         match self {
             Channel::Bounded(_, r) => r.try_recv(),
-            Channel::Rendezvous(state) => {
-                let state_ref = state.borrow();
+            Channel::Rendezvous(rv) => {
+                let state_ref = rv.state.borrow();
                 let s: &RendezvousState = &state_ref;
                 match s {
                     RendezvousState::Empty => Err(async_channel::TryRecvError::Empty),
                     RendezvousState::Full(_) => {
                         drop(state_ref);
-                        let cur_state: &mut RendezvousState = &mut state.borrow_mut();
+                        let cur_state: &mut RendezvousState = &mut rv.state.borrow_mut();
                         let full = mem::replace(cur_state, RendezvousState::Empty);
                         if let RendezvousState::Full(v) = full {
+                            rv.received.set(rv.received.get() + 1);
                             Ok(v)
                         } else {
                             unreachable!()
@@ -104,7 +149,7 @@ impl Channel {
     pub async fn send(&self, v: &GosValue) -> RuntimeResult<()> {
         loop {
             match self.try_send(v.clone()) {
-                Ok(()) => return Ok(()),
+                Ok(()) => break,
                 Err(e) => match e {
                     async_channel::TrySendError::Full(_) => {
                         future::yield_now().await;
@@ -115,9 +160,42 @@ impl Channel {
                 },
             }
         }
+        self.handed_over().await
+    }
+
+    /// Waits until the value the caller has just sent is received, if the
+    /// channel is unbuffered. The slot was empty, so the value is the next
+    /// one received, the sender is blocked until then like in Go.
+    async fn handed_over(&self) -> RuntimeResult<()> {
+        if let Channel::Rendezvous(rv) = self {
+            let ticket = rv.received.get() + 1;
+            while rv.received.get() < ticket {
+                if let RendezvousState::Closed = *rv.state.borrow() {
+                    return Err("channel closed!".to_owned());
+                }
+                future::yield_now().await;
+            }
+        }
+        Ok(())
+    }
+
+    // a select can only send on an unbuffered channel when someone other than
+    // itself waits to receive, so that it doesn't commit to a send nobody takes
+    fn has_receiver(&self, own: &[Receiving]) -> bool {
+        match self {
+            Channel::Bounded(_, _) => true,
+            Channel::Rendezvous(rv) => {
+                let own = own
+                    .iter()
+                    .filter(|r| r.0.as_ref().map_or(false, |o| Rc::ptr_eq(o, rv)))
+                    .count();
+                rv.receivers.get() > own
+            }
+        }
     }
 
     pub async fn recv(&self) -> Option<GosValue> {
+        let _receiving = Receiving::new(self);
         loop {
             match self.try_recv() {
                 Ok(v) => return Some(v),
@@ -151,19 +229,44 @@ impl Selector {
     }
 
     pub async fn select(&self) -> RuntimeResult<(usize, Option<GosValue>)> {
-        let count = self.comms.len();
+        // communications on nil channels can never proceed, they are skipped
+        let comms: Vec<(usize, &SelectComm)> = self
+            .comms
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| match entry {
+                SelectComm::Send(c, _, _) => !c.is_nil(),
+                SelectComm::Recv(c, _, _) => !c.is_nil(),
+            })
+            .collect();
+        if comms.is_empty() && self.default_offset.is_none() {
+            // blocks forever
+            future::pending::<()>().await;
+        }
+        // the receive cases wait to receive while the select is blocked
+        let receiving: Vec<Receiving> = comms
+            .iter()
+            .filter_map(|(_, entry)| match entry {
+                SelectComm::Recv(c, _, _) => Some(Receiving::new(&c.as_channel().chan)),
+                SelectComm::Send(_, _, _) => None,
+            })
+            .collect();
+        let count = comms.len();
         let mut rng = rand::thread_rng();
         loop {
-            for (i, entry) in self
-                .comms
-                .iter()
-                .enumerate()
-                .choose_multiple(&mut rng, count)
-            {
+            for &(i, entry) in comms.iter().choose_multiple(&mut rng, count) {
                 match entry {
                     SelectComm::Send(c, val, _) => {
-                        match c.as_channel().chan.try_send(val.clone()) {
-                            Ok(_) => return Ok((i, None)),
+                        let chan = &c.as_channel().chan;
+                        if !chan.has_receiver(&receiving) {
+                            continue;
+                        }
+                        match chan.try_send(val.clone()) {
+                            Ok(_) => {
+                                drop(receiving);
+                                chan.handed_over().await?;
+                                return Ok((i, None));
+                            }
                             Err(e) => match e {
                                 async_channel::TrySendError::Full(_) => {}
                                 async_channel::TrySendError::Closed(_) => {
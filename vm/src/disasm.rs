@@ -0,0 +1,163 @@
+use super::instruction::{Instruction, OpIndex, Opcode, ValueType};
+use super::objects::{u64_to_key, FunctionVal, PackageKey, VMObjects};
+use super::value::GosValue;
+use std::fmt::Write;
+
+/// Returns the instructions of the function in a readable form, one per line:
+/// the index, the opcode, the type flags, the immediates, the consts and the
+/// package members they refer to, and the position in the source file.
+///
+/// It's a tool for looking into the code generator, the format is not stable.
+pub fn disassemble(func: &FunctionVal, objs: &VMObjects) -> String {
+    let code = func.code();
+    let pos = func.pos();
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "; params: {}, locals: {}, consts: {}",
+        func.param_count(),
+        func.local_count(),
+        func.consts.len()
+    );
+    let mut pc = 0;
+    while pc < code.len() {
+        let inst = code[pc];
+        let raw = raw_word_count(&inst);
+        let raw_words = &code[(pc + 1).min(code.len())..(pc + 1 + raw).min(code.len())];
+        let line = format!(
+            "{:>4}  {:<20} {:<22} {}",
+            pc,
+            inst.op().text(),
+            type_flags(&inst),
+            operands(&inst, func, objs, raw_words)
+        );
+        let mut line = line.trim_end().to_owned();
+        if let Some(p) = pos[pc] {
+            let _ = write!(line, "  @{}", p);
+        }
+        out.push_str(&line);
+        out.push('\n');
+        pc += 1 + raw;
+    }
+    out
+}
+
+/// The number of the raw words, like package keys, following the instruction
+fn raw_word_count(inst: &Instruction) -> usize {
+    match inst.op() {
+        Opcode::BIND_METHOD
+        | Opcode::LOAD_PKG_FIELD
+        | Opcode::LOAD_PKG_INIT
+        | Opcode::STORE_PKG_FIELD
+        | Opcode::REF_PKG_MEMBER => 1,
+        Opcode::CAST => match inst.t0() {
+            _ if inst.t1() == ValueType::Nil => 0,
+            ValueType::Pointer if inst.t2() != ValueType::Zero => 1,
+            ValueType::Channel => 1,
+            _ => 0,
+        },
+        Opcode::WRAP if inst.t1() == ValueType::FlagA => 1,
+        _ => 0,
+    }
+}
+
+fn type_flags(inst: &Instruction) -> String {
+    let raw = inst.get_u64();
+    let bytes = [(raw >> 48) as u8, (raw >> 40) as u8, (raw >> 32) as u8];
+    let mut flags: Vec<String> = bytes
+        .iter()
+        .map(|b| match value_type(*b) {
+            Some(ValueType::Zero) => "_".to_owned(),
+            Some(t) => format!("{:?}", t),
+            // t2 can hold an index rather than a type
+            None => format!("#{}", *b as i8),
+        })
+        .collect();
+    while flags.last().map_or(false, |x| x == "_") {
+        flags.pop();
+    }
+    flags.join(", ")
+}
+
+fn value_type(b: u8) -> Option<ValueType> {
+    match b <= ValueType::FlagE as u8 {
+        true => Some(unsafe { std::mem::transmute(b) }),
+        false => None,
+    }
+}
+
+fn operands(
+    inst: &Instruction,
+    func: &FunctionVal,
+    objs: &VMObjects,
+    raw: &[Instruction],
+) -> String {
+    let op = inst.op();
+    let mut s = match op {
+        Opcode::STORE_LOCAL
+        | Opcode::STORE_UPVALUE
+        | Opcode::STORE_FIELD
+        | Opcode::STORE_STRUCT_FIELD
+        | Opcode::STORE_PKG_FIELD
+        | Opcode::STORE_DEREF => {
+            let (i0, i1) = inst.imm824();
+            match i0 < 0 {
+                true => format!("{} {}", i0, i1),
+                // an assignment with an operator like +=
+                false => format!("{} {} ({})", i0, i1, Instruction::index2code(i0).text()),
+            }
+        }
+        Opcode::CAST => {
+            let (i0, i1) = inst.imm824();
+            format!("{} {}", i0, i1)
+        }
+        _ => format!("{}", inst.imm()),
+    };
+    match op {
+        Opcode::PUSH_CONST | Opcode::PUSH_ZERO_VALUE | Opcode::TYPE_ASSERT | Opcode::LITERAL => {
+            let _ = write!(s, "  ; {}", describe_const(func, inst.imm()));
+        }
+        Opcode::LOAD_PKG_FIELD | Opcode::REF_PKG_MEMBER | Opcode::STORE_PKG_FIELD => {
+            let index = match op {
+                Opcode::STORE_PKG_FIELD => inst.imm824().1,
+                _ => inst.imm(),
+            };
+            if let Some(r) = raw.first() {
+                let _ = write!(
+                    s,
+                    "  ; {}",
+                    describe_member(objs, u64_to_key(r.get_u64()), index)
+                );
+            }
+        }
+        Opcode::LOAD_PKG_INIT => {
+            if let Some(r) = raw.first() {
+                let key: PackageKey = u64_to_key(r.get_u64());
+                if let Some(pkg) = objs.packages.get(key) {
+                    let _ = write!(s, "  ; {}", pkg.name());
+                }
+            }
+        }
+        _ => {
+            for r in raw {
+                let _ = write!(s, "  ; raw {:#x}", r.get_u64());
+            }
+        }
+    }
+    s
+}
+
+fn describe_const(func: &FunctionVal, index: OpIndex) -> String {
+    match func.consts.get(index as usize) {
+        Some(GosValue::Str(st)) => format!("{:?}", st.as_str()),
+        Some(c) => format!("{}", c),
+        None => "<no const>".to_owned(),
+    }
+}
+
+fn describe_member(objs: &VMObjects, key: PackageKey, index: OpIndex) -> String {
+    match objs.packages.get(key) {
+        Some(pkg) => format!("{}.{}", pkg.name(), pkg.member_name(index).unwrap_or("?")),
+        None => "<unknown package>".to_owned(),
+    }
+}
@@ -1,12 +1,17 @@
 use super::gc::GcoVec;
-use super::objects::VMObjects;
+use super::instruction::ValueType;
+use super::objects::{ClosureObj, PointerObj, UserData, VMObjects};
 use super::stack::Stack;
-use super::value::{GosValue, RuntimeResult};
-use std::cell::RefCell;
+use super::value::{GosValue, RCount, RuntimeResult};
+use futures_lite::future;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 
 pub type FfiCtorResult<T> = std::result::Result<T, String>;
 
@@ -17,9 +22,305 @@ pub struct FfiCallCtx<'a> {
     pub vm_objs: &'a VMObjects,
     pub stack: &'a mut Stack,
     pub gcv: &'a GcoVec,
+    // the number of goroutines that have not returned
+    pub num_goroutines: usize,
+    // calls a Go closure with the arguments in a new goroutine
+    pub call_go:
+        &'a dyn Fn(Rc<(RefCell<ClosureObj>, RCount)>, Vec<GosValue>) -> RuntimeResult<GoCall>,
+    // passes a value to the host driving the program as a coroutine, fails if
+    // the program is not run as one
+    pub yield_to_host: &'a dyn Fn(GosValue) -> RuntimeResult<HostYield>,
+    // parks the goroutine until the deadline without keeping the scheduler busy
+    pub sleep_until: &'a dyn Fn(Instant) -> Sleep,
+    // the state FFI code keeps for the goroutine making the call
+    pub locals: &'a mut GoroutineLocals,
 }
 
-/// A FFI function call
+/// The state FFI code associates with a goroutine, like a transaction or a
+/// request context. Every goroutine has its own, it's dropped when the
+/// goroutine returns.
+#[derive(Default)]
+pub struct GoroutineLocals {
+    values: HashMap<&'static str, Box<dyn Any>>,
+}
+
+impl GoroutineLocals {
+    pub fn new() -> GoroutineLocals {
+        GoroutineLocals {
+            values: HashMap::new(),
+        }
+    }
+
+    /// Sets the value of the key, returns the old value if there is one
+    pub fn set(&mut self, key: &'static str, v: Box<dyn Any>) -> Option<Box<dyn Any>> {
+        self.values.insert(key, v)
+    }
+
+    /// Returns the value of the key, or None if it's not set or not a T
+    pub fn get<T: 'static>(&self, key: &str) -> Option<&T> {
+        self.values.get(key).and_then(|v| v.downcast_ref::<T>())
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, key: &str) -> Option<&mut T> {
+        self.values.get_mut(key).and_then(|v| v.downcast_mut::<T>())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Box<dyn Any>> {
+        self.values.remove(key)
+    }
+}
+
+/// A call of a Go closure made by FFI code with FfiCallCtx::call_go, it runs
+/// in its own goroutine, so a panic in it aborts the program.
+pub struct GoCall {
+    done: Rc<RefCell<Option<Stack>>>,
+    ret_types: Vec<ValueType>,
+    aborted: Rc<Cell<bool>>,
+}
+
+impl GoCall {
+    pub fn new(
+        done: Rc<RefCell<Option<Stack>>>,
+        ret_types: Vec<ValueType>,
+        aborted: Rc<Cell<bool>>,
+    ) -> GoCall {
+        GoCall {
+            done: done,
+            ret_types: ret_types,
+            aborted: aborted,
+        }
+    }
+
+    /// Waits for the call to return, other goroutines keep running meanwhile
+    pub async fn results(self) -> RuntimeResult<Vec<GosValue>> {
+        loop {
+            if let Some(mut stack) = self.done.borrow_mut().take() {
+                return Ok(stack.pop_with_type_n(&self.ret_types));
+            }
+            if self.aborted.get() {
+                return Err("the called function did not return".to_owned());
+            }
+            future::yield_now().await;
+        }
+    }
+}
+
+/// The values passed between the goroutines of a coroutine and the host
+/// driving it, a yield is answered by the resume with the same id
+#[derive(Default)]
+pub struct HostLink {
+    next_id: usize,
+    // the value yielded by a goroutine, until the host takes it
+    yielded: Option<(usize, GosValue)>,
+    // the value the host resumed with, until the goroutine takes it
+    resumed: Option<(usize, GosValue)>,
+}
+
+impl HostLink {
+    pub fn new() -> HostLink {
+        HostLink {
+            next_id: 0,
+            yielded: None,
+            resumed: None,
+        }
+    }
+
+    /// Records a value yielded to the host and returns the id of the yield
+    pub fn put_yielded(&mut self, v: GosValue) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.yielded = Some((id, v));
+        id
+    }
+
+    pub fn has_yielded(&self) -> bool {
+        self.yielded.is_some()
+    }
+
+    pub fn take_yielded(&mut self) -> Option<(usize, GosValue)> {
+        self.yielded.take()
+    }
+
+    /// Answers the yield with the id with the value
+    pub fn resume(&mut self, id: usize, v: GosValue) {
+        self.resumed = Some((id, v));
+    }
+
+    fn take_resumed(&mut self, id: usize) -> Option<GosValue> {
+        match &self.resumed {
+            Some((rid, _)) if *rid == id => self.resumed.take().map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// A value yielded to the host by FfiCallCtx::yield_to_host, the goroutine
+/// waits for the host to resume the coroutine with a value.
+pub struct HostYield {
+    id: usize,
+    host: Rc<RefCell<HostLink>>,
+    aborted: Rc<Cell<bool>>,
+}
+
+impl HostYield {
+    pub fn new(id: usize, host: Rc<RefCell<HostLink>>, aborted: Rc<Cell<bool>>) -> HostYield {
+        HostYield {
+            id: id,
+            host: host,
+            aborted: aborted,
+        }
+    }
+
+    /// Waits for the value the host resumes with. The runner stops once a
+    /// goroutine has yielded, so nothing else runs until the host resumes
+    pub async fn resumed(self) -> RuntimeResult<GosValue> {
+        loop {
+            if let Some(v) = self.host.borrow_mut().take_resumed(self.id) {
+                return Ok(v);
+            }
+            if self.aborted.get() {
+                return Err("the coroutine was aborted".to_owned());
+            }
+            future::yield_now().await;
+        }
+    }
+}
+
+struct SleepState {
+    when: Instant,
+    done: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl SleepState {
+    fn wake(&self) {
+        self.done.set(true);
+        if let Some(w) = self.waker.borrow_mut().take() {
+            w.wake();
+        }
+    }
+}
+
+/// A sleep started with FfiCallCtx::sleep_until, the goroutine awaiting it
+/// is parked until the deadline or until the sleep is cancelled.
+#[derive(Clone)]
+pub struct Sleep {
+    state: Rc<SleepState>,
+}
+
+impl Sleep {
+    /// Ends the sleep early, the goroutine awaiting it is woken up
+    pub fn cancel(&self) {
+        self.state.wake();
+    }
+
+    /// Waits for the deadline or the cancellation
+    pub fn wait(&self) -> impl Future<Output = ()> {
+        SleepWait {
+            state: self.state.clone(),
+        }
+    }
+}
+
+struct SleepWait {
+    state: Rc<SleepState>,
+}
+
+impl Future for SleepWait {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.state.done.get() || Instant::now() >= self.state.when {
+            self.state.done.set(true);
+            Poll::Ready(())
+        } else {
+            self.state.waker.replace(Some(cx.waker().clone()));
+            Poll::Pending
+        }
+    }
+}
+
+/// The pending sleeps of a program, the scheduler sleeps until the earliest
+/// deadline when no goroutine can run. Sleeps nobody refers to are dropped.
+#[derive(Default)]
+pub struct Timers {
+    sleeps: Vec<Weak<SleepState>>,
+    // the earliest deadline of the sleeps
+    next: Option<Instant>,
+}
+
+impl Timers {
+    pub fn new() -> Timers {
+        Timers {
+            sleeps: vec![],
+            next: None,
+        }
+    }
+
+    pub fn sleep_until(&mut self, when: Instant) -> Sleep {
+        let state = Rc::new(SleepState {
+            when: when,
+            done: Cell::new(false),
+            waker: RefCell::new(None),
+        });
+        self.sleeps.push(Rc::downgrade(&state));
+        self.next = Some(self.next.map_or(when, |next| next.min(when)));
+        Sleep { state: state }
+    }
+
+    /// Returns how long to wait for the earliest pending sleep, or None if
+    /// there is none
+    pub fn next_timeout(&mut self) -> Option<Duration> {
+        self.sleeps
+            .retain(|s| s.upgrade().map_or(false, |s| !s.done.get()));
+        self.next = self
+            .sleeps
+            .iter()
+            .filter_map(|s| s.upgrade())
+            .map(|s| s.when)
+            .min();
+        self.next
+            .map(|next| next.saturating_duration_since(Instant::now()))
+    }
+
+    /// Wakes up the goroutines whose deadlines have passed, it's cheap when
+    /// none has
+    pub fn wake_expired(&mut self) {
+        match self.next {
+            Some(next) if next <= Instant::now() => {}
+            _ => return,
+        }
+        let now = Instant::now();
+        for s in self.sleeps.iter().filter_map(|s| s.upgrade()) {
+            if s.when <= now {
+                s.wake();
+            }
+        }
+        self.next_timeout();
+    }
+}
+
+/// Returns the user data v points to as a T, or an error if v doesn't point
+/// to user data or the user data is not a T.
+pub fn user_data<T: UserData + 'static>(v: &GosValue) -> RuntimeResult<&T> {
+    match v {
+        GosValue::Pointer(p) => match p as &PointerObj {
+            PointerObj::UserData(ud) => ud.as_any().downcast_ref::<T>().ok_or_else(|| {
+                let name = std::any::type_name::<T>();
+                format!(
+                    "user data is not of type {}",
+                    name.rsplit("::").next().unwrap()
+                )
+            }),
+            _ => Err("not a pointer to user data".to_owned()),
+        },
+        _ => Err("not a pointer to user data".to_owned()),
+    }
+}
+
+/// A FFI function call. The goroutine making the call awaits the returned
+/// future, it's parked until the future resolves while the other goroutines
+/// keep running, so a future that is not ready should yield rather than block.
 pub trait Ffi {
     fn call(
         &self,
@@ -66,3 +367,38 @@ impl std::fmt::Debug for FfiFactory {
         write!(f, "FfiFactory")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Apple {}
+
+    impl UserData for Apple {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    struct Orange {}
+
+    impl UserData for Orange {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_user_data() {
+        let apple = GosValue::new_pointer(PointerObj::UserData(Rc::new(Apple {})));
+        assert!(user_data::<Apple>(&apple).is_ok());
+        assert_eq!(
+            user_data::<Orange>(&apple).err().unwrap(),
+            "user data is not of type Orange"
+        );
+        assert_eq!(
+            user_data::<Apple>(&GosValue::Int(1)).err().unwrap(),
+            "not a pointer to user data"
+        );
+    }
+}
@@ -1,18 +1,34 @@
 use super::objects::*;
 use super::value::{GosValue, RCQueue, RCount, IRC};
 use std::cell::Ref;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::rc::{Rc, Weak};
 
+// the number of tracked objects that triggers the first collection
+const MIN_GC_THRESHOLD: usize = 4096;
+
 pub struct GcoVec {
     inner: Rc<RefCell<Vec<GcWeak>>>,
+    // the number of tracked objects that triggers the next collection
+    next_gc: Cell<usize>,
 }
 
 impl GcoVec {
     pub fn new() -> GcoVec {
         GcoVec {
             inner: Rc::new(RefCell::new(Vec::new())),
+            next_gc: Cell::new(MIN_GC_THRESHOLD),
+        }
+    }
+
+    /// Runs the collector if the number of tracked objects has doubled since
+    /// the last collection, like Go does by default
+    #[inline]
+    pub fn maybe_gc(&self) {
+        if self.inner.borrow().len() >= self.next_gc.get() {
+            gc(self);
         }
     }
 
@@ -27,6 +43,25 @@ impl GcoVec {
         self.inner.borrow_mut().push(w);
     }
 
+    /// Returns the number of live objects, and drops the entries of the
+    /// freed ones
+    pub fn live_count(&self) -> usize {
+        let mut inner = self.inner.borrow_mut();
+        inner.retain(|w| w.is_alive());
+        inner.len()
+    }
+
+    /// Returns true if more than max objects are alive. The entries of freed
+    /// objects are only dropped when there are more than max of all entries.
+    pub fn over_limit(&self, max: usize) -> bool {
+        let mut inner = self.inner.borrow_mut();
+        if inner.len() <= max {
+            return false;
+        }
+        inner.retain(|w| w.is_alive());
+        inner.len() > max
+    }
+
     fn borrow_data(&self) -> Ref<Vec<GcWeak>> {
         self.inner.borrow()
     }
@@ -60,6 +95,16 @@ impl GcWeak {
         }
     }
 
+    fn is_alive(&self) -> bool {
+        match &self {
+            GcWeak::Array(w) => w.strong_count() > 0,
+            GcWeak::Closure(w) => w.strong_count() > 0,
+            GcWeak::Slice(w) => w.strong_count() > 0,
+            GcWeak::Map(w) => w.strong_count() > 0,
+            GcWeak::Struct(w) => w.strong_count() > 0,
+        }
+    }
+
     fn to_gosv(&self) -> Option<GosValue> {
         match &self {
             GcWeak::Array(w) => w.upgrade().map(|v| {
@@ -88,22 +133,7 @@ impl GcWeak {
 
 fn children_ref_sub_one(val: &GosValue) {
     match val {
-        GosValue::Array(arr) => arr
-            .0
-            .borrow_data_mut()
-            .iter()
-            .for_each(|obj| obj.borrow().ref_sub_one()),
         GosValue::Closure(c) => c.0.borrow().ref_sub_one(),
-        GosValue::Slice(s) => {
-            // todo: slice shares data, could use some optimization
-            let sdata = &s.0;
-            if !sdata.is_nil() {
-                sdata
-                    .borrow()
-                    .iter()
-                    .for_each(|obj| obj.borrow().ref_sub_one())
-            }
-        }
         GosValue::Map(m) => {
             let mdata = &m.0;
             if !mdata.is_nil() {
@@ -114,10 +144,21 @@ fn children_ref_sub_one(val: &GosValue) {
             }
         }
         GosValue::Struct(s) => s.0.borrow().fields.iter().for_each(|obj| obj.ref_sub_one()),
+        // the elements are referenced by the shared vector
+        GosValue::Array(_) | GosValue::Slice(_) => {}
         _ => unreachable!(),
     };
 }
 
+/// The vector of an array or a slice, which is shared by the slices of it
+fn shared_vec(val: &GosValue) -> Option<&Rc<RefCell<GosVec>>> {
+    match val {
+        GosValue::Array(arr) => Some(&arr.0.vec),
+        GosValue::Slice(s) => s.0.vec.as_ref(),
+        _ => None,
+    }
+}
+
 fn children_mark_dirty(val: &GosValue, queue: &mut RCQueue) {
     match val {
         GosValue::Array(arr) => arr
@@ -126,12 +167,10 @@ fn children_mark_dirty(val: &GosValue, queue: &mut RCQueue) {
             .iter()
             .for_each(|obj| obj.borrow().mark_dirty(queue)),
         GosValue::Closure(c) => c.0.borrow().mark_dirty(queue),
+        // the whole vector is alive, not only the part in the slice
         GosValue::Slice(s) => {
-            let sdata = &s.0;
-            // todo: slice shares data, could use some optimization
-            if !sdata.is_nil() {
-                sdata
-                    .borrow()
+            if let Some(vec) = &s.0.vec {
+                vec.borrow()
                     .iter()
                     .for_each(|obj| obj.borrow().mark_dirty(queue))
             }
@@ -157,7 +196,12 @@ fn children_mark_dirty(val: &GosValue, queue: &mut RCQueue) {
 
 fn break_cycle(obj: &mut GosValue) {
     match obj {
-        GosValue::Array(arr) => arr.0.borrow_data_mut().clear(),
+        // the vector may be shared by a live slice
+        GosValue::Array(arr) => {
+            if Rc::strong_count(&arr.0.vec) == 1 {
+                arr.0.borrow_data_mut().clear()
+            }
+        }
         GosValue::Closure(c) => {
             let r: &mut ClosureObj = &mut RefCell::borrow_mut(&c.0);
             if let Some(uvs) = &mut r.uvs {
@@ -185,25 +229,14 @@ fn break_cycle(obj: &mut GosValue) {
 
 /// put the non-zero-rc on the left, and the others on the right
 fn partition_to_scan(to_scan: &mut Vec<GosValue>) -> usize {
-    let len = to_scan.len();
-    if len == 0 {
-        return 0;
-    }
-    let mut p0 = 0;
-    let mut p1 = len - 1;
-    loop {
-        while p0 < len - 1 && to_scan[p0].rc() > 0 {
-            p0 += 1;
+    let mut boundary = 0;
+    for i in 0..to_scan.len() {
+        if to_scan[i].rc() > 0 {
+            to_scan.swap(boundary, i);
+            boundary += 1;
         }
-        while p1 > 1 && to_scan[p1].rc() <= 0 {
-            p1 -= 1;
-        }
-        if p0 >= p1 {
-            break;
-        }
-        to_scan.swap(p0, p1);
     }
-    p0
+    boundary
 }
 
 pub fn gc(objs: &GcoVec) {
@@ -213,8 +246,23 @@ pub fn gc(objs: &GcoVec) {
         .filter_map(|o| o.to_gosv())
         .collect();
     //print!("objs before GC: {}\n", to_scan.len());
+    // arrays and slices can share vectors, whose elements are referenced once
+    // however many of them share it
+    let mut vecs: HashMap<*const RefCell<GosVec>, (Rc<RefCell<GosVec>>, usize)> = HashMap::new();
     for v in to_scan.iter() {
-        children_ref_sub_one(v);
+        match shared_vec(v) {
+            Some(vec) => vecs.entry(Rc::as_ptr(vec)).or_insert((vec.clone(), 0)).1 += 1,
+            None => children_ref_sub_one(v),
+        }
+    }
+    for (vec, holders) in vecs.into_values() {
+        // if the vector is also held by something not tracked, like a value
+        // on the stack, the elements are alive. One count is for `vec` itself.
+        if Rc::strong_count(&vec) == holders + 1 {
+            vec.borrow()
+                .iter()
+                .for_each(|obj| obj.borrow().ref_sub_one());
+        }
     }
 
     let boundary = partition_to_scan(&mut to_scan);
@@ -227,13 +275,15 @@ pub fn gc(objs: &GcoVec) {
         children_mark_dirty(&to_scan[i], &mut queue);
     }
 
-    loop {
-        if let Some(i) = queue.pop_front() {
-            let obj = &to_scan[(-i) as usize];
-            obj.set_rc(666);
-            children_mark_dirty(&obj, &mut queue);
-        } else {
-            break;
+    while let Some((i, rc)) = queue.pop_front() {
+        // objects that are not tracked are alive, as their references are not
+        // subtracted from their children
+        match to_scan.get((-i) as usize) {
+            Some(obj) if obj.is_rc_of(rc) => {
+                obj.set_rc(666);
+                children_mark_dirty(&obj, &mut queue);
+            }
+            _ => {}
         }
     }
 
@@ -243,10 +293,9 @@ pub fn gc(objs: &GcoVec) {
         }
     }
 
-    let _result: Vec<GosValue> = objs
-        .borrow_data()
-        .iter()
-        .filter_map(|o| o.to_gosv())
-        .collect();
-    //print!("objs left after GC: {}\n", result.len());
+    // drop the entries of the freed objects
+    let mut inner = objs.inner.borrow_mut();
+    inner.retain(|w| w.is_alive());
+    //print!("objs left after GC: {}\n", inner.len());
+    objs.next_gc.set(MIN_GC_THRESHOLD.max(inner.len() * 2));
 }
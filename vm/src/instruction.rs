@@ -106,6 +106,9 @@ pub enum Opcode {
     CAP,        // for built-in function cap
     APPEND,     // for built-in function append
     DELETE,     // for built-in function delete
+    CLEAR,      // for built-in function clear
+    MIN,        // for built-in function min
+    MAX,        // for built-in function max
     COPY,       // for built-in function copy
     CLOSE,      // for built-in function close
     PANIC,      // for built-in function panic
@@ -214,6 +217,9 @@ impl Opcode {
             Opcode::CAP => ("CAP", 0),
             Opcode::APPEND => ("APPEND", -128),
             Opcode::DELETE => ("DELETE", -2),
+            Opcode::CLEAR => ("CLEAR", -1),
+            Opcode::MIN => ("MIN", -128),
+            Opcode::MAX => ("MAX", -128),
             Opcode::COPY => ("COPY", -1),
             Opcode::CLOSE => ("CLOSE", -1),
             Opcode::PANIC => ("PANIC", -1),
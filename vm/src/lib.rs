@@ -17,3 +17,5 @@ mod stack;
 pub mod vm;
 
 pub mod gc;
+
+pub mod disasm;
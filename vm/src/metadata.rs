@@ -398,7 +398,7 @@ impl GosMetadata {
                 MetadataType::Channel(_, _) => GosValue::Nil(*self),
                 MetadataType::Named(_, gm) => {
                     let val = gm.zero_val_impl(mobjs, gcos);
-                    GosValue::Named(Box::new((val, *gm)))
+                    GosValue::Named(Box::new((val, *self)))
                 }
             },
             _ => GosValue::Nil(*self),
@@ -496,6 +496,68 @@ impl GosMetadata {
         }
     }
 
+    /// iface_bindings binds the methods of the interface iface to the methods of
+    /// this type, it returns None if this type does not implement iface.
+    pub fn iface_bindings(
+        &self,
+        iface: &GosMetadata,
+        metas: &MetadataObjs,
+    ) -> Option<Vec<IfaceBinding>> {
+        let fields = match &metas[iface.underlying(metas).as_non_ptr()] {
+            MetadataType::Interface(fields) => fields,
+            _ => unreachable!(),
+        };
+        let is_ptr = !matches!(self, GosMetadata::NonPtr(_, _));
+        fields
+            .fields
+            .iter()
+            .map(|f| {
+                let binding = self.get_iface_binding(&f.1, metas)?;
+                match &binding {
+                    // the method set of T does not contain the methods of *T
+                    IfaceBinding::Struct(m, None) if !is_ptr && m.borrow().pointer_recv => None,
+                    _ => Some(binding),
+                }
+            })
+            .collect()
+    }
+
+    /// Whether a value of this type is assignable to a variable of type t, by
+    /// Go's rules other than the ones for nil, untyped constants and channel
+    /// directions.
+    pub fn assignable_to(&self, t: &GosMetadata, metas: &MetadataObjs) -> bool {
+        if self.identical(t, metas) {
+            return true;
+        }
+        let (u, tu) = (self.underlying(metas), t.underlying(metas));
+        if (!self.is_defined(metas) || !t.is_defined(metas)) && u.identical(&tu, metas) {
+            return true;
+        }
+        match self {
+            GosMetadata::NonPtr(_, _) | GosMetadata::Ptr1(_, _) => {
+                tu.value_type(metas) == ValueType::Interface
+                    && self.iface_bindings(t, metas).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether it's a named type or a predeclared one like int
+    fn is_defined(&self, metas: &MetadataObjs) -> bool {
+        match self {
+            GosMetadata::NonPtr(k, _) => !matches!(
+                &metas[*k],
+                MetadataType::SliceOrArray(_, _)
+                    | MetadataType::Struct(_, _)
+                    | MetadataType::Signature(_)
+                    | MetadataType::Map(_, _)
+                    | MetadataType::Interface(_)
+                    | MetadataType::Channel(_, _)
+            ),
+            _ => false,
+        }
+    }
+
     #[inline]
     pub fn get_method(&self, index: OpIndex, metas: &MetadataObjs) -> Rc<RefCell<MethodDesc>> {
         let k = self.recv_meta_key();
@@ -770,6 +832,8 @@ impl MetadataType {
             (Self::Int16, Self::Int16) => true,
             (Self::Int32, Self::Int32) => true,
             (Self::Int64, Self::Int64) => true,
+            (Self::Uint, Self::Uint) => true,
+            (Self::UintPtr, Self::UintPtr) => true,
             (Self::Uint8, Self::Uint8) => true,
             (Self::Uint16, Self::Uint16) => true,
             (Self::Uint32, Self::Uint32) => true,
@@ -799,7 +863,8 @@ impl MetadataType {
             (Self::Channel(at, avt), Self::Channel(bt, bvt)) => {
                 at == bt && avt.identical(bvt, metas)
             }
-            (Self::Named(_, a), Self::Named(_, b)) => a.identical(b, metas),
+            // distinct named types are never identical, the keys were compared by the caller
+            (Self::Named(_, _), Self::Named(_, _)) => false,
             _ => false,
         }
     }
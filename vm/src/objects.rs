@@ -119,14 +119,32 @@ impl StringObj {
         self.as_str().as_bytes().get(i)
     }
 
+    /// begin and end are relative to self, a negative end means len.
+    /// The bounds are checked by the caller, they have to be at character
+    /// boundaries. The substring shares the data of self
     pub fn slice(&self, begin: isize, end: isize) -> StringObj {
-        let self_end = self.len() as isize + 1;
-        let bi = begin as usize;
-        let ei = ((self_end + end) % self_end) as usize;
+        let end = if end < 0 { self.len() } else { end as usize };
         StringObj {
             data: Rc::clone(&self.data),
-            begin: bi,
-            end: ei,
+            begin: self.begin + begin as usize,
+            end: self.begin + end,
+        }
+    }
+
+    /// Appends s in place if the data is not shared with other strings and
+    /// self ends where the data does, or else appends to a copy. Building a
+    /// string with += takes linear time this way.
+    pub fn push_str(&mut self, s: &str) {
+        match Rc::get_mut(&mut self.data) {
+            Some(data) if self.end == data.len() => {
+                data.push_str(s);
+                self.end = data.len();
+            }
+            _ => {
+                let mut data = self.as_str().to_owned();
+                data.push_str(s);
+                *self = StringObj::with_str(data);
+            }
         }
     }
 
@@ -214,24 +232,31 @@ impl MapObj {
 
     #[inline]
     pub fn get(&self, key: &GosValue) -> GosValue {
-        let mref = self.borrow_data();
-        let cell = match mref.get(key) {
+        match self.try_get(key) {
             Some(v) => v,
-            None => &self.default_val,
-        };
-        cell.clone().into_inner()
+            None => self.default_val.clone().into_inner(),
+        }
     }
 
+    /// a nil map behaves like an empty map when read from
     #[inline]
     pub fn try_get(&self, key: &GosValue) -> Option<GosValue> {
-        let mref = self.borrow_data();
+        let mref = self.map.as_ref()?.borrow();
         mref.get(key).map(|x| x.clone().into_inner())
     }
 
     #[inline]
     pub fn delete(&self, key: &GosValue) {
-        let mut mref = self.borrow_data_mut();
-        mref.remove(key);
+        if let Some(m) = &self.map {
+            m.borrow_mut().remove(key);
+        }
+    }
+
+    #[inline]
+    pub fn clear(&self) {
+        if let Some(m) = &self.map {
+            m.borrow_mut().clear();
+        }
     }
 
     /// touch_key makes sure there is a value for the 'key', a default value is set if
@@ -246,7 +271,7 @@ impl MapObj {
 
     #[inline]
     pub fn len(&self) -> usize {
-        self.borrow_data().len()
+        self.map.as_ref().map_or(0, |m| m.borrow().len())
     }
 
     #[inline]
@@ -287,7 +312,11 @@ impl Display for MapObj {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("map[")?;
         if let Some(m) = &self.map {
-            for (i, kv) in m.borrow().iter().enumerate() {
+            // as in Go, the entries are printed in key order
+            let m = m.borrow();
+            let mut entries: Vec<_> = m.iter().collect();
+            entries.sort_by(|a, b| key_order(a.0, b.0));
+            for (i, kv) in entries.into_iter().enumerate() {
                 if i > 0 {
                     f.write_char(' ')?;
                 }
@@ -299,6 +328,51 @@ impl Display for MapObj {
     }
 }
 
+/// The order of map keys when printing, following Go's internal/fmtsort:
+/// values of different types are ordered by type, structs and arrays
+/// element by element
+fn key_order(a: &GosValue, b: &GosValue) -> Ordering {
+    let unwrap = |v: &GosValue| -> GosValue {
+        match v.unwrap_named_ref() {
+            GosValue::Interface(i) => match i.borrow().underlying_value() {
+                Some(u) => u.unwrap_named_ref().clone(),
+                None => GosValue::new_nil(),
+            },
+            v => v.clone(),
+        }
+    };
+    let (a, b) = (unwrap(a), unwrap(b));
+    let elements_order = |x: &[GosValue], y: &[GosValue]| {
+        x.iter()
+            .zip(y.iter())
+            .map(|(x, y)| key_order(x, y))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    };
+    match (&a, &b) {
+        _ if a.typ() != b.typ() => a.typ().cmp(&b.typ()),
+        (GosValue::Nil(_), GosValue::Nil(_)) => Ordering::Equal,
+        (GosValue::Struct(x), GosValue::Struct(y)) => {
+            elements_order(&x.0.borrow().fields, &y.0.borrow().fields)
+        }
+        (GosValue::Array(x), GosValue::Array(y)) => elements_order(
+            &x.0.borrow_data()
+                .iter()
+                .map(|v| v.borrow().clone())
+                .collect::<Vec<_>>(),
+            &y.0.borrow_data()
+                .iter()
+                .map(|v| v.borrow().clone())
+                .collect::<Vec<_>>(),
+        ),
+        (GosValue::Complex64(xr, xi), GosValue::Complex64(yr, yi)) => (xr, xi).cmp(&(yr, yi)),
+        (GosValue::Complex128(x), GosValue::Complex128(y)) => x.cmp(y),
+        (GosValue::Pointer(x), GosValue::Pointer(y)) => x.addr().cmp(&y.addr()),
+        (GosValue::Channel(x), GosValue::Channel(y)) => Rc::as_ptr(x).cmp(&Rc::as_ptr(y)),
+        _ => a.cmp(&b),
+    }
+}
+
 // ----------------------------------------------------------------------------
 // ArrayObj
 
@@ -351,9 +425,26 @@ impl ArrayObj {
         self.borrow_data().get(i).map(|x| x.clone().into_inner())
     }
 
+    /// A copy with its own elements, the arrays and structs in it are copied
+    /// as well, like assigning an array in Go
+    pub fn deep_clone(&self, gcv: &GcoVec) -> ArrayObj {
+        ArrayObj {
+            meta: self.meta,
+            vec: Rc::new(RefCell::new(self.copy_data(gcv))),
+        }
+    }
+
     #[inline]
-    pub fn set_from(&self, other: &ArrayObj) {
-        *self.borrow_data_mut() = other.borrow_data().clone()
+    pub fn set_from(&self, other: &ArrayObj, gcv: &GcoVec) {
+        let data = other.copy_data(gcv);
+        *self.borrow_data_mut() = data;
+    }
+
+    fn copy_data(&self, gcv: &GcoVec) -> GosVec {
+        self.borrow_data()
+            .iter()
+            .map(|x| RefCell::new(x.borrow().copy_semantic(gcv)))
+            .collect()
     }
 }
 
@@ -406,6 +497,27 @@ impl Clone for ArrayObj {
 // ----------------------------------------------------------------------------
 // SliceObj
 
+/// The capacity of the new array when appending to a slice needs new_len
+/// elements, the same as Go's nextslicecap: it doubles below 256, then grows
+/// by 1.25x plus 192, to smooth the transition. Unlike Go, the capacity is not
+/// rounded up to the size classes of the allocator, which depend on the byte
+/// size of the elements, so the large capacities are a bit smaller than Go's.
+pub fn next_slice_cap(new_len: usize, old_cap: usize) -> usize {
+    const THRESHOLD: usize = 256;
+    let double_cap = old_cap * 2;
+    if new_len > double_cap {
+        return new_len;
+    }
+    if old_cap < THRESHOLD {
+        return double_cap;
+    }
+    let mut new_cap = old_cap;
+    while new_cap < new_len {
+        new_cap += (new_cap + 3 * THRESHOLD) >> 2;
+    }
+    new_cap
+}
+
 #[derive(Debug)]
 pub struct SliceObj {
     pub meta: GosMetadata,
@@ -421,11 +533,12 @@ impl<'a> SliceObj {
         cap: usize,
         meta: GosMetadata,
         default_val: Option<&GosValue>,
+        gcos: &GcoVec,
     ) -> SliceObj {
         assert!(cap >= len);
         let mut val: GosVec = Vec::with_capacity(cap);
         for _ in 0..cap {
-            val.push(RefCell::new(default_val.unwrap().clone()));
+            val.push(RefCell::new(default_val.unwrap().copy_semantic(gcos)));
         }
         SliceObj {
             meta: meta,
@@ -448,17 +561,17 @@ impl<'a> SliceObj {
         }
     }
 
+    /// A negative end means the length of the array.
+    /// The bounds are checked by the caller
     pub fn with_array(arr: &ArrayObj, begin: isize, end: isize) -> SliceObj {
         let elem_meta = GosMetadata::new_slice_from_array(arr.meta);
         let len = arr.len();
-        let self_end = len as isize + 1;
-        let bi = begin as usize;
-        let ei = ((self_end + end) % self_end) as usize;
+        let ei = if end < 0 { len } else { end as usize };
         SliceObj {
             meta: elem_meta,
-            begin: Cell::from(bi),
+            begin: Cell::from(begin as usize),
             end: Cell::from(ei),
-            cap_end: Cell::from(bi + len),
+            cap_end: Cell::from(len),
             vec: Some(arr.vec.clone()),
         }
     }
@@ -525,26 +638,52 @@ impl<'a> SliceObj {
         }
     }
 
-    #[inline]
-    pub fn append(&mut self, other: &SliceObj) {
-        let mut data = self.borrow_all_data_mut();
-        let new_end = self.end() + other.len();
-        let after_end_len = data.len() - self.end();
-        if after_end_len <= other.len() {
-            data.truncate(self.end());
-            data.extend_from_slice(other.borrow().as_slice());
+    /// Appends the elements of other like Go's append does: if the capacity
+    /// is large enough, the elements are written to the shared underlying
+    /// array, otherwise a new array is allocated, with its spare capacity
+    /// filled with zero values. Only the header of self is changed, the
+    /// headers of other slices sharing the array are not.
+    #[inline]
+    pub fn append(&mut self, other: &SliceObj, metas: &MetadataObjs, gcos: &GcoVec) {
+        // other may share the underlying array with self
+        let vals: Vec<RefCell<GosValue>> = other.borrow().as_slice().to_vec();
+        let new_end = self.end() + vals.len();
+        if vals.is_empty() {
+            return;
+        } else if new_end <= self.cap_end.get() {
+            let mut data = self.borrow_all_data_mut();
+            for (i, v) in vals.into_iter().enumerate() {
+                let pos = self.end() + i;
+                if pos < data.len() {
+                    data[pos] = v;
+                } else {
+                    data.push(v);
+                }
+            }
+            drop(data);
+            *self.end.get_mut() = new_end;
         } else {
-            data[self.end()..new_end].clone_from_slice(other.borrow().as_slice());
-        }
-        drop(data);
-        *self.end.get_mut() = new_end;
-        if self.cap_end.get() < self.end.get() {
-            *self.cap_end.get_mut() = self.end.get();
+            let new_len = self.len() + vals.len();
+            let new_cap = next_slice_cap(new_len, self.cap());
+            let mut data: GosVec = Vec::with_capacity(new_cap);
+            data.extend_from_slice(self.borrow().as_slice());
+            data.extend(vals);
+            // the spare capacity can be resliced into
+            let elem = metas[self.meta.as_non_ptr()].as_slice_or_array().0;
+            let zero = elem.zero_val(metas, gcos);
+            data.resize_with(new_cap, || RefCell::new(zero.copy_semantic(gcos)));
+            self.vec = Some(Rc::new(RefCell::new(data)));
+            *self.begin.get_mut() = 0;
+            *self.end.get_mut() = new_len;
+            *self.cap_end.get_mut() = new_cap;
         }
     }
 
     #[inline]
     pub fn copy_from(&self, other: &SliceObj) -> usize {
+        if self.is_nil() {
+            return 0;
+        }
         let mut data = self.borrow_all_data_mut();
         let ref_other = other.borrow();
         let data_other = ref_other.as_slice();
@@ -564,6 +703,9 @@ impl<'a> SliceObj {
 
     #[inline]
     pub fn get(&self, i: usize) -> Option<GosValue> {
+        if i >= self.len() {
+            return None;
+        }
         self.borrow_all_data()
             .get(self.begin() + i)
             .map(|x| x.clone().into_inner())
@@ -574,26 +716,31 @@ impl<'a> SliceObj {
         self.borrow_all_data()[self.begin() + i].replace(val);
     }
 
+    /// begin, end and max are relative to self, a negative end means len
+    /// and a negative max means cap. The bounds are checked by the caller
     #[inline]
     pub fn slice(&self, begin: isize, end: isize, max: isize) -> SliceObj {
-        let self_len = self.len() as isize + 1;
+        // the only valid bounds for a nil slice are zeros
+        if self.is_nil() {
+            return self.clone();
+        }
         let bi = self.begin() + begin as usize;
-        let ei = self.begin() + ((self_len + end) % self_len) as usize;
-        let cap = if max < 0 {
+        let ei = self.begin() + if end < 0 { self.len() } else { end as usize };
+        let cap_end = if max < 0 {
             self.cap_end.get()
         } else {
-            max as usize
+            self.begin() + max as usize
         };
         let mut data = self.borrow_all_data_mut();
-        let more_cap = cap as isize - data.len() as isize;
+        let more_cap = cap_end as isize - data.len() as isize;
         for _ in 0..more_cap {
             data.push(RefCell::new(GosValue::new_nil())); // todo: is nil ok?
         }
         SliceObj {
             meta: self.meta,
-            begin: Cell::from(self.begin() + bi),
-            end: Cell::from(self.begin() + ei),
-            cap_end: Cell::from(cap),
+            begin: Cell::from(bi),
+            end: Cell::from(ei),
+            cap_end: Cell::from(cap_end),
             vec: self.vec.clone(),
         }
     }
@@ -647,8 +794,9 @@ impl PartialEq for SliceObj {
 
 impl Eq for SliceObj {}
 
+/// A borrowed view of a slice, a nil slice is viewed as an empty slice
 pub struct SliceRef<'a> {
-    vec_ref: Ref<'a, GosVec>,
+    vec_ref: Option<Ref<'a, GosVec>>,
     begin: usize,
     end: usize,
 }
@@ -660,7 +808,7 @@ pub type SliceEnumIter<'a> = std::iter::Enumerate<SliceIter<'a>>;
 impl<'a> SliceRef<'a> {
     pub fn new(s: &SliceObj) -> SliceRef {
         SliceRef {
-            vec_ref: s.borrow_all_data(),
+            vec_ref: s.vec.as_ref().map(|v| v.borrow()),
             begin: s.begin(),
             end: s.end(),
         }
@@ -668,16 +816,19 @@ impl<'a> SliceRef<'a> {
 
     #[inline]
     pub fn iter(&self) -> SliceIter {
-        self.vec_ref[self.begin..self.end].iter()
+        self.as_slice().iter()
     }
 
     #[inline]
     pub fn get(&self, i: usize) -> Option<&RefCell<GosValue>> {
-        self.vec_ref.get(self.begin + i)
+        self.as_slice().get(i)
     }
 
     pub fn as_slice(&self) -> &[RefCell<GosValue>] {
-        &self.vec_ref[self.begin..self.end]
+        match &self.vec_ref {
+            Some(v) => &v[self.begin..self.end],
+            None => &[],
+        }
     }
 }
 
@@ -699,6 +850,15 @@ pub struct StructObj {
 }
 
 impl StructObj {
+    /// A copy with its own fields, the arrays and structs in it are copied
+    /// as well, like assigning a struct in Go
+    pub fn deep_clone(&self, gcv: &GcoVec) -> StructObj {
+        StructObj {
+            meta: self.meta,
+            fields: self.fields.iter().map(|x| x.copy_semantic(gcv)).collect(),
+        }
+    }
+
     pub fn is_exported(&self, index: usize, metas: &MetadataObjs) -> bool {
         metas[self.meta.as_non_ptr()]
             .as_struct()
@@ -804,6 +964,32 @@ pub enum IfaceUnderlying {
 }
 
 impl IfaceUnderlying {
+    /// rebind returns the underlying of the interface this one is converted to,
+    /// mapping binds each method of the target interface to a method of this one.
+    pub fn rebind(&self, mapping: &Option<Vec<Binding4Runtime>>) -> IfaceUnderlying {
+        let index = |b: &Binding4Runtime| match b {
+            Binding4Runtime::Iface(i, None) => *i,
+            _ => unreachable!(),
+        };
+        match self {
+            IfaceUnderlying::None => IfaceUnderlying::None,
+            IfaceUnderlying::Gos(obj, b) => {
+                let bindings = mapping.as_ref().map(|m| {
+                    m.iter()
+                        .map(|x| b.as_ref().unwrap()[index(x)].clone())
+                        .collect()
+                });
+                IfaceUnderlying::Gos(obj.clone(), bindings)
+            }
+            IfaceUnderlying::Ffi(ffi) => {
+                let methods = mapping.as_ref().map_or(vec![], |m| {
+                    m.iter().map(|x| ffi.methods[index(x)].clone()).collect()
+                });
+                IfaceUnderlying::Ffi(UnderlyingFfi::new(ffi.ffi_obj.clone(), methods))
+            }
+        }
+    }
+
     pub fn bind_method(
         &self,
         index: usize,
@@ -988,7 +1174,7 @@ impl ChannelObj {
     }
 
     #[inline]
-    pub fn close(&self) {
+    pub fn close(&self) -> RuntimeResult<()> {
         self.chan.close()
     }
 
@@ -1162,6 +1348,31 @@ impl PointerObj {
         }
     }
 
+    /// A stable identifier of what the pointer points to, used as the
+    /// address when printing pointers. Two pointers have the same addr
+    /// if and only if they are equal.
+    pub fn addr(&self) -> usize {
+        match self {
+            PointerObj::UpVal(uv) => Rc::as_ptr(&uv.inner) as usize,
+            PointerObj::Struct(s, _) => Rc::as_ptr(s) as usize,
+            PointerObj::Array(a, _) => Rc::as_ptr(a) as usize,
+            PointerObj::Slice(s, _) => Rc::as_ptr(s) as usize,
+            PointerObj::Map(m, _) => Rc::as_ptr(m) as usize,
+            PointerObj::SliceMember(s, index) => {
+                let vborrow = s.0.borrow();
+                &vborrow[s.0.begin() + *index as usize] as *const _ as usize
+            }
+            PointerObj::StructField(s, index) => {
+                &s.0.borrow().fields[*index as usize] as *const _ as usize
+            }
+            PointerObj::PkgMember(pkg, index) => {
+                (key_to_u64(*pkg) as usize) << 16 | *index as usize
+            }
+            PointerObj::UserData(ud) => Rc::as_ptr(ud) as *const () as usize,
+            PointerObj::Released => 0,
+        }
+    }
+
     /// set_value is not used by VM, it's for FFI
     pub fn set_value(&self, val: GosValue, stack: &mut Stack, pkgs: &PackageObjs, gcv: &GcoVec) {
         match self {
@@ -1175,7 +1386,7 @@ impl PointerObj {
                     .borrow()
                     .clone()
             }
-            PointerObj::Array(a, _) => a.0.set_from(&val.as_array().0),
+            PointerObj::Array(a, _) => a.0.set_from(&val.as_array().0, gcv),
             PointerObj::Slice(s, _) => s.0.set_from(&val.as_slice().0),
             PointerObj::Map(m, _) => *m.0.borrow_data_mut() = val.as_map().0.borrow_data().clone(),
             PointerObj::SliceMember(s, index) => {
@@ -1276,16 +1487,8 @@ impl Hash for PointerObj {
 impl Display for PointerObj {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::UpVal(uv) => f.write_fmt(format_args!("{:p}", Rc::as_ptr(&uv.inner))),
-            Self::Struct(s, _) => f.write_fmt(format_args!("{:p}", Rc::as_ptr(&s))),
-            Self::Array(s, _) => f.write_fmt(format_args!("{:p}", Rc::as_ptr(&s))),
-            Self::Slice(s, _) => f.write_fmt(format_args!("{:p}", Rc::as_ptr(&s))),
-            Self::Map(m, _) => f.write_fmt(format_args!("{:p}", Rc::as_ptr(&m))),
-            Self::SliceMember(s, i) => f.write_fmt(format_args!("{:p}i{}", Rc::as_ptr(&s), i)),
-            Self::StructField(s, i) => f.write_fmt(format_args!("{:p}i{}", Rc::as_ptr(&s), i)),
-            Self::PkgMember(p, i) => f.write_fmt(format_args!("{:x}i{}", key_to_u64(*p), i)),
-            Self::UserData(ud) => f.write_fmt(format_args!("{:p}", Rc::as_ptr(&ud))),
             Self::Released => f.write_str("released!!!"),
+            _ => f.write_fmt(format_args!("{:#x}", self.addr())),
         }
     }
 }
@@ -1551,16 +1754,26 @@ impl ClosureObj {
 // ----------------------------------------------------------------------------
 // PackageVal
 
+/// What a package member is declared as
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemberKind {
+    Const,
+    Var,
+    Func,
+    Type,
+}
+
 /// PackageVal is part of the generated Bytecode, it stores imports, consts,
 /// vars, funcs declared in a package
 #[derive(Clone, Debug)]
 pub struct PackageVal {
     name: String,
     members: Vec<Rc<RefCell<GosValue>>>, // imports, const, var, func are all stored here
+    member_kinds: Vec<MemberKind>,
     member_indices: HashMap<String, OpIndex>,
     init_funcs: Vec<GosValue>,
-    // maps func_member_index of the constructor to pkg_member_index
-    var_mapping: Option<HashMap<OpIndex, OpIndex>>,
+    // set when the package starts initializing, so that it's initialized only once
+    inited: Cell<bool>,
 }
 
 impl PackageVal {
@@ -1568,51 +1781,62 @@ impl PackageVal {
         PackageVal {
             name: name,
             members: Vec::new(),
+            member_kinds: Vec::new(),
             member_indices: HashMap::new(),
             init_funcs: Vec::new(),
-            var_mapping: Some(HashMap::new()),
+            inited: Cell::new(false),
         }
     }
 
-    pub fn add_member(&mut self, name: String, val: GosValue) -> OpIndex {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn add_member(&mut self, name: String, val: GosValue, kind: MemberKind) -> OpIndex {
         self.members.push(Rc::new(RefCell::new(val)));
+        self.member_kinds.push(kind);
         let index = (self.members.len() - 1) as OpIndex;
         self.member_indices.insert(name, index);
         index as OpIndex
     }
 
-    pub fn add_var_mapping(&mut self, name: String, fn_index: OpIndex) -> OpIndex {
-        let index = *self.get_member_index(&name).unwrap();
-        self.var_mapping
-            .as_mut()
-            .unwrap()
-            .insert(fn_index.into(), index);
-        index
-    }
-
     pub fn add_init_func(&mut self, func: GosValue) {
         self.init_funcs.push(func);
     }
 
-    pub fn var_mut(&self, fn_member_index: OpIndex) -> RefMut<GosValue> {
-        let index = self.var_mapping.as_ref().unwrap()[&fn_member_index];
-        self.members[index as usize].borrow_mut()
+    pub fn get_member_index(&self, name: &str) -> Option<&OpIndex> {
+        self.member_indices.get(name)
     }
 
-    pub fn var_count(&self) -> usize {
-        self.var_mapping.as_ref().unwrap().len()
+    pub fn member_name(&self, index: OpIndex) -> Option<&str> {
+        self.member_indices
+            .iter()
+            .find(|(_, i)| **i == index)
+            .map(|(name, _)| name.as_str())
     }
 
-    pub fn get_member_index(&self, name: &str) -> Option<&OpIndex> {
-        self.member_indices.get(name)
+    /// Returns the names and kinds of the members in the order they were
+    /// added, the constructor of the package is not included
+    pub fn members(&self) -> Vec<(&str, MemberKind)> {
+        let mut members: Vec<(&str, OpIndex)> = self
+            .member_indices
+            .iter()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, i)| (name.as_str(), *i))
+            .collect();
+        members.sort_by_key(|(_, i)| *i);
+        members
+            .into_iter()
+            .map(|(name, i)| (name, self.member_kinds[i as usize]))
+            .collect()
     }
 
     pub fn inited(&self) -> bool {
-        self.var_mapping.is_none()
+        self.inited.get()
     }
 
-    pub fn set_inited(&mut self) {
-        self.var_mapping = None
+    pub fn set_inited(&self) {
+        self.inited.set(true)
     }
 
     #[inline]
@@ -1682,6 +1906,9 @@ pub struct FunctionVal {
     pub flag: FuncFlag,
 
     param_count: usize,
+    // the indices of the params of struct or array types, the receiver
+    // included, which the callee gets its own copies of
+    value_params: Vec<usize>,
     entities: HashMap<KeyData, EntIndex>,
     uv_entities: HashMap<KeyData, EntIndex>,
     local_alloc: u16,
@@ -1701,6 +1928,17 @@ impl FunctionVal {
             returns.push(zero_val!(m, objs, gcv));
         }
         let params = s.params.len() + s.recv.map_or(0, |_| 1);
+        let value_params = s
+            .recv
+            .iter()
+            .chain(s.params.iter())
+            .enumerate()
+            .filter(|(_, m)| {
+                let t = m.underlying(&objs.metas).value_type(&objs.metas);
+                t == ValueType::Struct || t == ValueType::Array
+            })
+            .map(|(i, _)| i)
+            .collect();
         FunctionVal {
             package: package,
             meta: meta,
@@ -1712,6 +1950,7 @@ impl FunctionVal {
             local_zeros: Vec::new(),
             flag: flag,
             param_count: params,
+            value_params: value_params,
             entities: HashMap::new(),
             uv_entities: HashMap::new(),
             local_alloc: 0,
@@ -1743,6 +1982,11 @@ impl FunctionVal {
         self.ret_zeros.len()
     }
 
+    #[inline]
+    pub fn value_params(&self) -> &[usize] {
+        &self.value_params
+    }
+
     #[inline]
     pub fn is_ctor(&self) -> bool {
         self.flag == FuncFlag::PkgCtor
@@ -1764,9 +2008,10 @@ impl FunctionVal {
     }
 
     #[inline]
+    /// returns the distance from loc to the end of the code, the code of a
+    /// function can't get anywhere near OpIndex::MAX instructions
     pub fn offset(&self, loc: usize) -> OpIndex {
-        // todo: don't crash if OpIndex overflows
-        OpIndex::try_from((self.code.len() - loc) as isize).unwrap()
+        OpIndex::try_from(self.code.len() - loc).unwrap()
     }
 
     #[inline]
@@ -91,8 +91,11 @@ macro_rules! store_local_val {
                 *$to.get_c_mut($s_index) =
                     $stack.read_copyable_ops($to.get_c($s_index), $r_index, $t);
             } else {
-                *$to.get_rc_mut($s_index) =
-                    $stack.read_non_copyable_ops($to.get_rc($s_index), $r_index, $t);
+                let rhs = $stack.get_rc(Stack::offset($stack.len(), -1)).clone();
+                if !Stack::append_str($to.get_rc_mut($s_index), &rhs) {
+                    *$to.get_rc_mut($s_index) =
+                        $stack.read_non_copyable_ops($to.get_rc($s_index), $r_index, $t);
+                }
             }
         }
     }};
@@ -402,24 +405,40 @@ impl Stack {
         self.get_rc(rhs_s_index).copy_semantic(gcos)
     }
 
+    // the ops of named values with copyable underlying types
     #[inline]
     fn read_non_copyable_ops(&self, lhs: &GosValue, r_index: OpIndex, t: ValueType) -> GosValue {
         let ri = Stack::offset(self.len(), -1);
-        if t == ValueType::Named {
-            let op = Instruction::index2code(r_index);
-            let l = lhs.as_named();
-            let (a, t) = GosValue64::from_v128(&l.0);
-            let v = read_with_ops!(
-                op,
-                &a,
-                &GosValue64::from_v128(&self.get_rc(ri).as_named().0).0,
-                self.get_c(ri),
-                t
-            );
-            GosValue::Named(Box::new((v.v128(t), l.1)))
-        } else {
-            GosValue::add_str(lhs, self.get_rc(ri))
+        debug_assert!(t == ValueType::Named);
+        let op = Instruction::index2code(r_index);
+        let l = lhs.as_named();
+        let (a, t) = GosValue64::from_v128(&l.0);
+        let v = read_with_ops!(
+            op,
+            &a,
+            &GosValue64::from_v128(&self.get_rc(ri).as_named().0).0,
+            self.get_c(ri),
+            t
+        );
+        GosValue::Named(Box::new((v.v128(t), l.1)))
+    }
+
+    /// target += rhs if target is a string or a named string, which is the
+    /// only op on strings, returns false if target is not one
+    #[inline]
+    fn append_str(target: &mut GosValue, rhs: &GosValue) -> bool {
+        let rhs = match rhs {
+            GosValue::Named(n) => &n.0,
+            _ => rhs,
+        };
+        match target {
+            GosValue::Str(_) => GosValue::append_str(target, rhs),
+            GosValue::Named(n) if matches!(n.0, GosValue::Str(_)) => {
+                GosValue::append_str(&mut n.0, rhs)
+            }
+            _ => return false,
         }
+        true
     }
 
     #[inline]
@@ -431,6 +450,13 @@ impl Stack {
 
     #[inline]
     pub fn store_val(&self, target: &mut GosValue, r_index: OpIndex, t: ValueType, gcos: &GcoVec) {
+        if r_index >= 0 && !t.copyable() {
+            let rhs = self.get_rc(Stack::offset(self.len(), -1));
+            if !Stack::append_str(target, rhs) {
+                *target = self.read_non_copyable_ops(target, r_index, t);
+            }
+            return;
+        }
         *target = if r_index < 0 {
             if t.copyable() {
                 self.read_copyable(r_index).v128(t)
@@ -438,12 +464,8 @@ impl Stack {
                 self.read_non_copyable(r_index, gcos)
             }
         } else {
-            if t.copyable() {
-                self.read_copyable_ops(&GosValue64::from_v128(target).0, r_index, t)
-                    .v128(t)
-            } else {
-                self.read_non_copyable_ops(target, r_index, t)
-            }
+            self.read_copyable_ops(&GosValue64::from_v128(target).0, r_index, t)
+                .v128(t)
         };
     }
 
@@ -491,21 +513,22 @@ impl Stack {
         t: ValueType,
         gcos: &GcoVec,
     ) -> RuntimeResult<()> {
-        let err = Err("assignment to entry in nil map or slice".to_owned());
+        let err = Err("assignment to entry in nil map".to_owned());
         match target {
             GosValue::Array(arr) => {
-                let target_cell = &arr.0.borrow_data()[*key.as_int() as usize];
+                let i = key.as_index();
+                let data = arr.0.borrow_data();
+                let target_cell = data.get(i).ok_or_else(|| index_err(i, data.len()))?;
+                self.store_val(&mut target_cell.borrow_mut(), r_index, t, gcos);
+                Ok(())
+            }
+            GosValue::Slice(s) => {
+                let i = key.as_index();
+                let data = s.0.borrow();
+                let target_cell = data.get(i).ok_or_else(|| index_err(i, s.0.len()))?;
                 self.store_val(&mut target_cell.borrow_mut(), r_index, t, gcos);
                 Ok(())
             }
-            GosValue::Slice(s) => match s.0.is_nil() {
-                false => {
-                    let target_cell = &s.0.borrow()[*key.as_int() as usize];
-                    self.store_val(&mut target_cell.borrow_mut(), r_index, t, gcos);
-                    Ok(())
-                }
-                true => err,
-            },
             GosValue::Map(map) => match map.0.is_nil() {
                 false => {
                     map.0.touch_key(&key);
@@ -590,7 +613,7 @@ impl Stack {
             PointerObj::Array(a, _) => {
                 let rhs_s_index = Stack::offset(self.len(), rhs_index);
                 let val = self.get_with_type(rhs_s_index, typ);
-                a.0.set_from(&val.as_array().0);
+                a.0.set_from(&val.as_array().0, gcv);
             }
             PointerObj::Slice(r, _) => {
                 let rhs_s_index = Stack::offset(self.len(), rhs_index);
@@ -636,22 +659,16 @@ impl Stack {
     #[inline]
     pub fn pack_variadic(&mut self, index: usize, meta: GosMetadata, t: ValueType, gcos: &GcoVec) {
         if index <= self.len() {
-            let mut v = Vec::new();
-            v.append(&mut self.split_off_with_type(index, t));
+            // the slice gets its own copies of the structs and arrays passed
+            let v = self
+                .split_off_with_type(index, t)
+                .into_iter()
+                .map(|x| x.copy_semantic(gcos))
+                .collect();
             self.push(GosValue::slice_with_val(v, meta, gcos))
         }
     }
 
-    #[inline]
-    pub fn init_pkg_vars(&mut self, pkg: &PackageVal, count: usize) {
-        for i in 0..count {
-            let var_index = (count - 1 - i) as OpIndex;
-            let var: &mut GosValue = &mut pkg.var_mut(var_index);
-            let t = var.typ();
-            *var = self.pop_with_type(t);
-        }
-    }
-
     #[inline]
     pub fn unwrap_named(&mut self, i: usize) -> ValueType {
         self.set(i, self.get_rc(i).as_named().0.clone())
@@ -680,6 +697,20 @@ impl Stack {
                     *self.get_rc_mut(self.len() - 2) = GosValue::add_str(a, b);
                     self.pop_discard();
                 }
+                ValueType::Named
+                    if matches!(self.get_rc(self.len() - 2).as_named().0, GosValue::Str(_)) =>
+                {
+                    // named strings are not copyable, the sum has the type of a
+                    let a = self.get_rc(self.len() - 2).as_named();
+                    let b = self.get_rc(self.len() - 1);
+                    let b = match b {
+                        GosValue::Named(n) => &n.0,
+                        _ => b,
+                    };
+                    let v = GosValue::Named(Box::new((GosValue::add_str(&a.0, b), a.1)));
+                    *self.get_rc_mut(self.len() - 2) = v;
+                    self.pop_discard();
+                }
                 ValueType::Named => {
                     let t = self.unwrap_named(self.len() - 2);
                     self.unwrap_named(self.len() - 1);
@@ -704,7 +735,22 @@ impl Stack {
             if t != ValueType::Metadata {
                 a.eq(&b)
             } else {
-                a.as_meta().identical(b.as_meta(), &objs.metas)
+                match (&a, &b) {
+                    (GosValue::Metadata(GosMetadata::Untyped), GosValue::Metadata(_)) => false,
+                    (GosValue::Metadata(x), GosValue::Metadata(y)) => {
+                        let y = y.into_value_category();
+                        match y.underlying(&objs.metas).value_type(&objs.metas) {
+                            // an interface case matches the types implementing it
+                            ValueType::Interface => x.iface_bindings(&y, &objs.metas).is_some(),
+                            _ => x.identical(&y, &objs.metas),
+                        }
+                    }
+                    // "case nil" of a type switch, TYPE gives Untyped for nil interfaces
+                    (GosValue::Metadata(x), GosValue::Nil(_)) => {
+                        matches!(x, GosMetadata::Untyped)
+                    }
+                    _ => false,
+                }
             }
         };
         self.pop_discard();
@@ -874,11 +920,15 @@ impl Stack {
     }
 }
 
-/// store iterators for Opcode::RANGE
+/// store iterators for Opcode::RANGE, with the ranged values so that the
+/// data the iterators borrow stays alive until the loop is done
 pub struct RangeStack {
-    maps: Vec<GosHashMapIter<'static>>,
-    slices: Vec<SliceEnumIter<'static>>,
-    strings: Vec<StringEnumIter<'static>>,
+    // None for a nil map
+    maps: Vec<(GosValue, Option<GosHashMapIter<'static>>)>,
+    // the slice, the next index and the length when the loop started, the
+    // elements are read by index as the slice can be appended to meanwhile
+    slices: Vec<(GosValue, usize, usize)>,
+    strings: Vec<(GosValue, StringEnumIter<'static>)>,
 }
 
 impl RangeStack {
@@ -893,18 +943,18 @@ impl RangeStack {
     pub fn range_init(&mut self, target: &GosValue) {
         match target {
             GosValue::Map(m) => {
-                let map = m.0.borrow_data();
-                let iter = unsafe { mem::transmute(map.iter()) };
-                self.maps.push(iter);
+                let iter = m.0.map.as_ref().map(|map| {
+                    let map = map.borrow();
+                    unsafe { mem::transmute(map.iter()) }
+                });
+                self.maps.push((target.clone(), iter));
             }
             GosValue::Slice(sl) => {
-                let slice = sl.0.borrow();
-                let iter = unsafe { mem::transmute(slice.iter().enumerate()) };
-                self.slices.push(iter);
+                self.slices.push((target.clone(), 0, sl.0.len()));
             }
             GosValue::Str(s) => {
                 let iter = unsafe { mem::transmute(s.iter().enumerate()) };
-                self.strings.push(iter);
+                self.strings.push((target.clone(), iter));
             }
             _ => unreachable!(),
         }
@@ -912,7 +962,14 @@ impl RangeStack {
 
     pub fn range_body(&mut self, typ: ValueType, stack: &mut Stack) -> bool {
         match typ {
-            ValueType::Map => match self.maps.last_mut().unwrap().next() {
+            ValueType::Map => match self
+                .maps
+                .last_mut()
+                .unwrap()
+                .1
+                .as_mut()
+                .and_then(|x| x.next())
+            {
                 Some((k, v)) => {
                     stack.push(k.clone());
                     stack.push(v.clone().into_inner());
@@ -923,18 +980,26 @@ impl RangeStack {
                     true
                 }
             },
-            ValueType::Slice => match self.slices.last_mut().unwrap().next() {
-                Some((k, v)) => {
-                    stack.push_int(k as isize);
-                    stack.push(v.clone().into_inner());
-                    false
-                }
-                None => {
-                    self.slices.pop();
-                    true
+            ValueType::Slice => {
+                let (sl, next, len) = self.slices.last_mut().unwrap();
+                let v = match *next < *len {
+                    true => sl.as_slice().0.get(*next),
+                    false => None,
+                };
+                match v {
+                    Some(v) => {
+                        stack.push_int(*next as isize);
+                        stack.push(v);
+                        *next += 1;
+                        false
+                    }
+                    None => {
+                        self.slices.pop();
+                        true
+                    }
                 }
-            },
-            ValueType::Str => match self.strings.last_mut().unwrap().next() {
+            }
+            ValueType::Str => match self.strings.last_mut().unwrap().1.next() {
                 Some((k, v)) => {
                     stack.push_int(k as isize);
                     stack.push_int(v as isize);
@@ -18,13 +18,15 @@ type F32 = ordered_float::OrderedFloat<f32>;
 type F64 = ordered_float::OrderedFloat<f64>;
 pub type IRC = i32;
 pub type RCount = Cell<IRC>;
-pub type RCQueue = VecDeque<IRC>;
+// the counter is kept with its value, as the value of an object that is not
+// tracked by the GC is not an index into the objects being scanned
+pub type RCQueue = VecDeque<(IRC, *const RCount)>;
 
 #[inline]
 pub fn rcount_mark_and_queue(rc: &RCount, queue: &mut RCQueue) {
     let i = rc.get();
     if i <= 0 {
-        queue.push_back(i);
+        queue.push_back((i, rc));
         rc.set(1);
     }
 }
@@ -130,6 +132,7 @@ macro_rules! cmp_bool_int_float {
             ValueType::Uint64 => union_cmp!($a, $b, uint64, $op),
             ValueType::Float32 => union_cmp!($a, $b, float32, $op),
             ValueType::Float64 => union_cmp!($a, $b, float64, $op),
+            ValueType::Complex64 => union_cmp!($a, $b, complex64, $op),
             _ => unreachable!(),
         }
     };
@@ -313,7 +316,7 @@ impl GosValue {
         dval: Option<&GosValue>,
         gcobjs: &GcoVec,
     ) -> GosValue {
-        let s = Rc::new((SliceObj::new(len, cap, meta, dval), Cell::new(0)));
+        let s = Rc::new((SliceObj::new(len, cap, meta, dval, gcobjs), Cell::new(0)));
         let v = GosValue::Slice(s);
         gcobjs.add(&v);
         v
@@ -378,6 +381,145 @@ impl GosValue {
         v
     }
 
+    /// Creates a slice of the values with the element type elem, for embedders.
+    /// The values are checked and converted the way assign_to does.
+    pub fn slice_of(
+        elem: GosMetadata,
+        vals: Vec<GosValue>,
+        objs: &mut VMObjects,
+        gcobjs: &GcoVec,
+    ) -> RuntimeResult<GosValue> {
+        let vals = vals
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| {
+                v.assign_to(&elem, objs, gcobjs)
+                    .map_err(|e| format!("element {}: {}", i, e))
+            })
+            .collect::<RuntimeResult<Vec<GosValue>>>()?;
+        let meta = GosMetadata::new_slice(elem, &mut objs.metas);
+        Ok(GosValue::slice_with_val(vals, meta, gcobjs))
+    }
+
+    /// Creates a map of the key-value pairs with the key type kmeta and the
+    /// value type vmeta, for embedders
+    pub fn map_of(
+        kmeta: GosMetadata,
+        vmeta: GosMetadata,
+        pairs: Vec<(GosValue, GosValue)>,
+        objs: &mut VMObjects,
+        gcobjs: &GcoVec,
+    ) -> RuntimeResult<GosValue> {
+        let meta = GosMetadata::new_map(kmeta, vmeta, &mut objs.metas);
+        let val = GosValue::new_map(meta, vmeta.zero_val(&objs.metas, gcobjs), gcobjs);
+        let map = val.as_map();
+        for (k, v) in pairs.into_iter() {
+            let k = k
+                .assign_to(&kmeta, objs, gcobjs)
+                .map_err(|e| format!("key: {}", e))?;
+            let v = v
+                .assign_to(&vmeta, objs, gcobjs)
+                .map_err(|e| format!("value: {}", e))?;
+            map.0.insert(k, v);
+        }
+        Ok(val)
+    }
+
+    /// Creates a struct of the type meta from the values of all its fields,
+    /// for embedders. meta can be a struct type or a named struct type.
+    pub fn struct_of(
+        meta: GosMetadata,
+        fields: Vec<GosValue>,
+        objs: &VMObjects,
+        gcobjs: &GcoVec,
+    ) -> RuntimeResult<GosValue> {
+        let underlying = meta.underlying(&objs.metas);
+        let (f, zero) = match underlying {
+            GosMetadata::NonPtr(k, MetaCategory::Default) => match &objs.metas[k] {
+                MetadataType::Struct(f, zero) => (f, zero),
+                _ => return Err("not a struct type".to_owned()),
+            },
+            _ => return Err("not a struct type".to_owned()),
+        };
+        if fields.len() != f.fields.len() {
+            return Err(format!(
+                "expected {} fields, got {}",
+                f.fields.len(),
+                fields.len()
+            ));
+        }
+        let val = zero.copy_semantic(gcobjs);
+        let mut sref = val.as_struct().0.borrow_mut();
+        for (i, v) in fields.into_iter().enumerate() {
+            sref.fields[i] = v
+                .assign_to(&f.fields[i].0, objs, gcobjs)
+                .map_err(|e| format!("field {}: {}", i, e))?;
+        }
+        drop(sref);
+        Ok(match underlying == meta {
+            true => val,
+            false => GosValue::Named(Box::new((val, meta))),
+        })
+    }
+
+    /// Converts a value from the embedder for assignment to a variable of the
+    /// type meta: the value is copied like a Go assignment, nil becomes the zero
+    /// value of the type, and values assigned to an empty interface are boxed.
+    /// Values of other types are rejected.
+    pub fn assign_to(
+        self,
+        meta: &GosMetadata,
+        objs: &VMObjects,
+        gcobjs: &GcoVec,
+    ) -> RuntimeResult<GosValue> {
+        let expected = meta.value_type(&objs.metas);
+        let actual = self.typ();
+        match (expected, actual) {
+            _ if expected == actual => match self.host_meta(objs) {
+                Some(m) if !m.assignable_to(meta, &objs.metas) => {
+                    Err(format!("mismatched {:?} types", expected))
+                }
+                _ => Ok(self.copy_semantic(gcobjs)),
+            },
+            (
+                ValueType::Pointer
+                | ValueType::Slice
+                | ValueType::Map
+                | ValueType::Channel
+                | ValueType::Closure
+                | ValueType::Interface,
+                ValueType::Nil,
+            ) => Ok(meta.zero_val(&objs.metas, gcobjs)),
+            (ValueType::Interface, _) => {
+                let underlying = meta.underlying(&objs.metas);
+                if objs.metas[underlying.as_non_ptr()]
+                    .as_interface()
+                    .fields
+                    .is_empty()
+                {
+                    let val = self.copy_semantic(gcobjs);
+                    Ok(GosValue::new_empty_iface(&objs.metadata, val))
+                } else {
+                    Err(format!("cannot pass {:?} as a non-empty interface", actual))
+                }
+            }
+            _ => Err(format!("expected {:?}, got {:?}", expected, actual)),
+        }
+    }
+
+    /// The type of a value from the embedder, None if it can't be told
+    /// without the stack of a running fiber
+    fn host_meta(&self, objs: &VMObjects) -> Option<GosMetadata> {
+        match self {
+            GosValue::Function(_) | GosValue::Package(_) | GosValue::Metadata(_) => None,
+            GosValue::Pointer(p) => match p.as_ref() {
+                PointerObj::UpVal(uv) if uv.is_open() => None,
+                _ => Some(self.meta(objs, &Stack::new())),
+            },
+            _ => Some(self.meta(objs, &Stack::new())),
+        }
+    }
+
     #[inline]
     pub fn new_function(
         package: PackageKey,
@@ -409,6 +551,22 @@ impl GosValue {
         GosValue::Interface(val)
     }
 
+    /// new_iface_of_type creates an interface value of type meta, which is
+    /// either an interface type or a named one.
+    pub fn new_iface_of_type(
+        meta: GosMetadata,
+        underlying: IfaceUnderlying,
+        metas: &MetadataObjs,
+    ) -> GosValue {
+        match &metas[meta.as_non_ptr()] {
+            MetadataType::Named(_, md) => {
+                GosValue::Named(Box::new((GosValue::new_iface(*md, underlying), meta)))
+            }
+            MetadataType::Interface(_) => GosValue::new_iface(meta, underlying),
+            _ => unreachable!(),
+        }
+    }
+
     #[inline]
     pub fn new_empty_iface(mdata: &Metadata, underlying: GosValue) -> GosValue {
         let val = Rc::new(RefCell::new(InterfaceObj::new(
@@ -468,6 +626,85 @@ impl GosValue {
         unwrap_gos_val!(Int64, self)
     }
 
+    /// Converts an integer of any size to i64, for embedders consuming results.
+    /// Unlike as_int64 it returns an error if the value is not an integer or
+    /// does not fit. Named types and interfaces are unwrapped first.
+    pub fn as_i64(&self) -> RuntimeResult<i64> {
+        let val = self.unwrap_all();
+        let result = match &val {
+            GosValue::Int(i) => i64::try_from(*i).ok(),
+            GosValue::Int8(i) => Some(*i as i64),
+            GosValue::Int16(i) => Some(*i as i64),
+            GosValue::Int32(i) => Some(*i as i64),
+            GosValue::Int64(i) => Some(*i),
+            GosValue::Uint(i) => i64::try_from(*i).ok(),
+            GosValue::UintPtr(i) => i64::try_from(*i).ok(),
+            GosValue::Uint8(i) => Some(*i as i64),
+            GosValue::Uint16(i) => Some(*i as i64),
+            GosValue::Uint32(i) => Some(*i as i64),
+            GosValue::Uint64(i) => i64::try_from(*i).ok(),
+            _ => return Err(format!("expected an integer, got {:?}", val.typ())),
+        };
+        result.ok_or_else(|| format!("{} overflows i64", val))
+    }
+
+    /// Converts a float32 or float64 to f64, returns an error for other types
+    pub fn as_f64(&self) -> RuntimeResult<f64> {
+        match self.unwrap_all() {
+            GosValue::Float32(f) => Ok(f.into_inner() as f64),
+            GosValue::Float64(f) => Ok(f.into_inner()),
+            v => Err(format!("expected a float, got {:?}", v.typ())),
+        }
+    }
+
+    /// Converts a string or a byte slice to a String, invalid UTF-8 in a byte
+    /// slice is replaced with U+FFFD
+    pub fn as_string_lossy(&self) -> RuntimeResult<String> {
+        match self.unwrap_all() {
+            GosValue::Str(s) => Ok(s.as_str().to_owned()),
+            v @ GosValue::Slice(_) => {
+                let bytes = v
+                    .try_into_vec()?
+                    .iter()
+                    .map(|b| match b {
+                        GosValue::Uint8(b) => Ok(*b),
+                        _ => Err(format!("expected a string, got slice of {:?}", b.typ())),
+                    })
+                    .collect::<RuntimeResult<Vec<u8>>>()?;
+                Ok(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            v => Err(format!("expected a string, got {:?}", v.typ())),
+        }
+    }
+
+    /// Returns the elements of a slice or an array, a nil slice has none
+    pub fn try_into_vec(&self) -> RuntimeResult<Vec<GosValue>> {
+        match self.unwrap_all() {
+            GosValue::Slice(s) if s.0.is_nil() => Ok(vec![]),
+            GosValue::Slice(s) => Ok(s.0.get_vec()),
+            GosValue::Array(a) => Ok(a
+                .0
+                .borrow_data()
+                .iter()
+                .map(|x| x.borrow().clone())
+                .collect()),
+            GosValue::Nil(_) => Ok(vec![]),
+            v => Err(format!("expected a slice, got {:?}", v.typ())),
+        }
+    }
+
+    /// Returns the value with named types and interfaces unwrapped
+    fn unwrap_all(&self) -> GosValue {
+        match self {
+            GosValue::Named(n) => n.0.unwrap_all(),
+            GosValue::Interface(i) => match i.borrow().underlying_value() {
+                Some(v) => v.unwrap_all(),
+                None => GosValue::new_nil(),
+            },
+            _ => self.clone(),
+        }
+    }
+
     #[inline]
     pub fn as_int_mut(&mut self) -> &mut isize {
         unwrap_gos_val!(Int, self)
@@ -635,7 +872,7 @@ impl GosValue {
     pub fn equals_nil(&self) -> bool {
         match &self {
             GosValue::Nil(_) => true,
-            GosValue::Named(n) => n.0.is_nil(),
+            GosValue::Named(n) => n.0.equals_nil(),
             GosValue::Slice(s) => s.0.is_nil(),
             GosValue::Map(m) => m.0.is_nil(),
             GosValue::Interface(iface) => iface.borrow().is_nil(),
@@ -680,7 +917,11 @@ impl GosValue {
     }
 
     pub fn identical(&self, other: &GosValue) -> bool {
-        self.typ() == other.typ() && self == other
+        match (self, other) {
+            // values of different named types are never identical
+            (GosValue::Named(a), GosValue::Named(b)) => a.1 == b.1 && a.0.identical(&b.0),
+            _ => self.typ() == other.typ() && self == other,
+        }
     }
 
     pub fn meta(&self, objs: &VMObjects, stack: &Stack) -> GosMetadata {
@@ -710,7 +951,7 @@ impl GosValue {
             GosValue::Map(m) => m.0.meta,
             GosValue::Interface(i) => i.borrow().meta,
             GosValue::Struct(s) => s.0.borrow().meta,
-            GosValue::Channel(_) => unimplemented!(),
+            GosValue::Channel(c) => c.meta,
             GosValue::Function(_) => unimplemented!(),
             GosValue::Package(_) => unimplemented!(),
             GosValue::Metadata(_) => unimplemented!(),
@@ -731,8 +972,13 @@ impl GosValue {
                 gcv.add_weak(GcWeak::Map(Rc::downgrade(&rc)));
                 GosValue::Map(rc)
             }
+            GosValue::Array(a) => {
+                let rc = Rc::new((a.0.deep_clone(gcv), Cell::new(0)));
+                gcv.add_weak(GcWeak::Array(Rc::downgrade(&rc)));
+                GosValue::Array(rc)
+            }
             GosValue::Struct(s) => {
-                let rc = Rc::new((RefCell::clone(&s.0), Cell::new(0)));
+                let rc = Rc::new((RefCell::new(s.0.borrow().deep_clone(gcv)), Cell::new(0)));
                 gcv.add_weak(GcWeak::Struct(Rc::downgrade(&rc)));
                 GosValue::Struct(rc)
             }
@@ -766,53 +1012,40 @@ impl GosValue {
         GosValue::new_str(s)
     }
 
+    /// a += b, b is appended to a in place if no other value shares a
+    #[inline]
+    pub fn append_str(a: &mut GosValue, b: &GosValue) {
+        if let GosValue::Str(s) = a {
+            if let Some(s) = Rc::get_mut(s) {
+                s.push_str(b.as_str().as_str());
+                return;
+            }
+        }
+        *a = GosValue::add_str(a, b);
+    }
+
     #[inline(always)]
     pub fn load_index(&self, ind: &GosValue) -> RuntimeResult<GosValue> {
         match self {
             GosValue::Map(map) => Ok(map.0.get(&ind).clone()),
-            GosValue::Slice(slice) => {
-                let index = ind.as_index();
-                slice
-                    .0
-                    .get(index)
-                    .map_or_else(|| Err(format!("index {} out of range", index)), |x| Ok(x))
-            }
-            GosValue::Str(s) => {
-                let index = ind.as_index();
-                s.get_byte(index).map_or_else(
-                    || Err(format!("index {} out of range", index)),
-                    |x| Ok(GosValue::Int((*x).into())),
-                )
-            }
-            GosValue::Array(arr) => {
-                let index = ind.as_index();
-                arr.0
-                    .get(index)
-                    .map_or_else(|| Err(format!("index {} out of range", index)), |x| Ok(x))
-            }
-            _ => unreachable!(),
+            _ => self.load_index_int(ind.as_index()),
         }
     }
 
+    /// Indexing a string gets a byte, the error messages are the same as Go's
     #[inline]
     pub fn load_index_int(&self, i: usize) -> RuntimeResult<GosValue> {
         match self {
-            GosValue::Slice(slice) => slice
-                .0
-                .get(i)
-                .map_or_else(|| Err(format!("index {} out of range", i)), |x| Ok(x)),
+            GosValue::Slice(slice) => slice.0.get(i).ok_or_else(|| index_err(i, slice.0.len())),
             GosValue::Map(map) => {
                 let ind = GosValue::Int(i as isize);
                 Ok(map.0.get(&ind).clone())
             }
-            GosValue::Str(s) => s.get_byte(i).map_or_else(
-                || Err(format!("index {} out of range", i)),
-                |x| Ok(GosValue::Int((*x).into())),
-            ),
-            GosValue::Array(arr) => arr
-                .0
-                .get(i)
-                .map_or_else(|| Err(format!("index {} out of range", i)), |x| Ok(x)),
+            GosValue::Str(s) => s
+                .get_byte(i)
+                .map(|x| GosValue::Uint8(*x))
+                .ok_or_else(|| index_err(i, s.len())),
+            GosValue::Array(arr) => arr.0.get(i).ok_or_else(|| index_err(i, arr.0.len())),
             GosValue::Named(n) => n.0.load_index_int(i),
             _ => {
                 dbg!(self);
@@ -866,6 +1099,22 @@ impl GosValue {
         };
     }
 
+    fn rc_cell(&self) -> &RCount {
+        match &self {
+            GosValue::Array(obj) => &obj.1,
+            GosValue::Closure(obj) => &obj.1,
+            GosValue::Slice(obj) => &obj.1,
+            GosValue::Map(obj) => &obj.1,
+            GosValue::Struct(obj) => &obj.1,
+            _ => unreachable!(),
+        }
+    }
+
+    /// for gc, if rc is the counter of the object
+    pub fn is_rc_of(&self, rc: *const RCount) -> bool {
+        std::ptr::eq(self.rc_cell(), rc)
+    }
+
     pub fn rc(&self) -> IRC {
         match &self {
             GosValue::Array(obj) => obj.1.get(),
@@ -997,8 +1246,8 @@ impl Hash for GosValue {
             GosValue::Uint16(i) => i.hash(state),
             GosValue::Uint32(i) => i.hash(state),
             GosValue::Uint64(i) => i.hash(state),
-            GosValue::Float32(f) => f.to_bits().hash(state),
-            GosValue::Float64(f) => f.to_bits().hash(state),
+            GosValue::Float32(f) => f.hash(state),
+            GosValue::Float64(f) => f.hash(state),
             GosValue::Str(s) => s.as_str().hash(state),
             GosValue::Array(a) => a.0.hash(state),
             GosValue::Complex64(i, r) => {
@@ -1042,6 +1291,7 @@ impl Ord for GosValue {
             (Self::Float32(x), Self::Float32(y)) => x.cmp(y),
             (Self::Float64(x), Self::Float64(y)) => x.cmp(y),
             (Self::Str(x), Self::Str(y)) => x.cmp(y),
+            (Self::Named(x), Self::Named(y)) => x.0.cmp(&y.0),
             _ => {
                 dbg!(self, b);
                 unreachable!()
@@ -1351,6 +1601,11 @@ impl GosValue64 {
         unsafe { self.data.int32 }
     }
 
+    #[inline]
+    pub fn get_int64(&self) -> i64 {
+        unsafe { self.data.int64 }
+    }
+
     #[inline]
     pub fn get_uint(&self) -> usize {
         unsafe { self.data.uint }
@@ -1464,6 +1719,7 @@ impl GosValue64 {
     pub fn unary_xor(&mut self, t: ValueType) {
         match t {
             ValueType::Uint => self.data.uint = unsafe { (!0) ^ self.data.uint },
+            ValueType::UintPtr => self.data.uint_ptr = unsafe { (!0) ^ self.data.uint_ptr },
             ValueType::Uint8 => self.data.uint8 = unsafe { (!0) ^ self.data.uint8 },
             ValueType::Uint16 => self.data.uint16 = unsafe { (!0) ^ self.data.uint16 },
             ValueType::Uint32 => self.data.uint32 = unsafe { (!0) ^ self.data.uint32 },
@@ -1498,6 +1754,7 @@ impl GosValue64 {
             ValueType::Uint16 => self.data.uint16 = unsafe { self.data.uint16 } + 1,
             ValueType::Uint32 => self.data.uint32 = unsafe { self.data.uint32 } + 1,
             ValueType::Uint64 => self.data.uint64 = unsafe { self.data.uint64 } + 1,
+            ValueType::UintPtr => self.data.uint_ptr = unsafe { self.data.uint_ptr } + 1,
             _ => unreachable!(),
         }
     }
@@ -1517,6 +1774,7 @@ impl GosValue64 {
             ValueType::Uint16 => self.data.uint16 = unsafe { self.data.uint16 } - 1,
             ValueType::Uint32 => self.data.uint32 = unsafe { self.data.uint32 } - 1,
             ValueType::Uint64 => self.data.uint64 = unsafe { self.data.uint64 } - 1,
+            ValueType::UintPtr => self.data.uint_ptr = unsafe { self.data.uint_ptr } - 1,
             _ => unreachable!(),
         }
     }
@@ -1595,6 +1853,9 @@ impl GosValue64 {
                     ValueType::Uint => V64Union {
                         uint: self.data.uint & !b.data.uint,
                     },
+                    ValueType::UintPtr => V64Union {
+                        uint_ptr: self.data.uint_ptr & !b.data.uint_ptr,
+                    },
                     ValueType::Uint8 => V64Union {
                         uint8: self.data.uint8 & !b.data.uint8,
                     },
@@ -1644,6 +1905,15 @@ impl GosValue64 {
     }
 }
 
+/// The error of an index out of the range of a string, slice or array of len,
+/// a negative index was converted to a large usize
+pub fn index_err(i: usize, len: usize) -> String {
+    match i as isize {
+        i if i < 0 => format!("index out of range [{}]", i),
+        i => format!("index out of range [{}] with length {}", i, len),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::super::value::*;
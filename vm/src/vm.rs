@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 use super::channel;
-use super::ffi::{FfiCallCtx, FfiFactory};
+use super::ffi::{FfiCallCtx, FfiFactory, GoCall, GoroutineLocals, HostLink, HostYield, Timers};
 use super::gc::{gc, GcoVec};
 use super::instruction::*;
 use super::metadata::*;
@@ -8,13 +8,15 @@ use super::objects::{u64_to_key, ClosureObj};
 use super::stack::{RangeStack, Stack};
 use super::value::*;
 use async_executor::LocalExecutor;
-use futures_lite::future;
+use futures_lite::future::{self, FutureExt};
 use goscript_parser::FileSet;
+use std::any::Any;
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
 use std::rc::Rc;
-use std::str;
+use std::result;
 
 // restore stack_ref after drop to allow code in block call yield
 macro_rules! restore_stack_ref {
@@ -24,6 +26,19 @@ macro_rules! restore_stack_ref {
     }};
 }
 
+// charges the instructions run since unit_start to the budget, before the
+// fiber awaits and at the end of a yield unit. The budget is read again as
+// other fibers may have used some of it meanwhile
+macro_rules! charge_budget {
+    ($self_:ident, $total_inst:ident, $unit_start:ident) => {{
+        if let Some(b) = $self_.context.budget.get() {
+            let used = $total_inst - $unit_start;
+            $self_.context.budget.set(Some(b.saturating_sub(used)));
+        }
+        $unit_start = $total_inst;
+    }};
+}
+
 macro_rules! go_panic {
     ($panic:ident, $msg:expr, $frame:ident, $code:ident) => {
         let mut data = PanicData::new($msg);
@@ -66,12 +81,54 @@ macro_rules! unwrap_recv_val {
 
 #[inline]
 fn char_from_u32(u: u32) -> char {
-    unsafe { char::from_u32_unchecked(u) }
+    char::from_u32(u).unwrap_or(char::REPLACEMENT_CHARACTER)
 }
 
 #[inline]
 fn char_from_i32(i: i32) -> char {
-    unsafe { char::from_u32_unchecked(i as u32) }
+    u32::try_from(i).map_or(char::REPLACEMENT_CHARACTER, char_from_u32)
+}
+
+#[inline]
+fn char_from_i64(i: i64) -> char {
+    u32::try_from(i).map_or(char::REPLACEMENT_CHARACTER, char_from_u32)
+}
+
+/// Checks the indices of s[begin:end] or s[begin:end:max],
+/// the error messages are the same as Go's
+fn check_slice_bounds(
+    begin: isize,
+    end: isize,
+    max: Option<isize>,
+    cap: usize,
+    cap_name: &str,
+) -> RuntimeResult<()> {
+    let err = |s: String| Err(format!("slice bounds out of range {}", s));
+    let cap = cap as isize;
+    if let Some(max) = max {
+        if max < 0 {
+            return err(format!("[::{}]", max));
+        } else if max > cap {
+            return err(format!("[::{}] with {} {}", max, cap_name, cap));
+        } else if end < 0 {
+            return err(format!("[:{}:]", end));
+        } else if end > max {
+            return err(format!("[:{}:{}]", end, max));
+        } else if begin < 0 {
+            return err(format!("[{}::]", begin));
+        } else if begin > end {
+            return err(format!("[{}:{}:]", begin, end));
+        }
+    } else if end < 0 {
+        return err(format!("[:{}]", end));
+    } else if end > cap {
+        return err(format!("[:{}] with {} {}", end, cap_name, cap));
+    } else if begin < 0 {
+        return err(format!("[{}:]", begin));
+    } else if begin > end {
+        return err(format!("[{}:{}]", begin, end));
+    }
+    Ok(())
 }
 
 #[inline]
@@ -79,6 +136,52 @@ fn deref_value(v: &GosValue, stack: &Stack, objs: &VMObjects) -> GosValue {
     v.as_pointer().deref(stack, &objs.packages)
 }
 
+// the value an index expression indexes, p[i] for a pointer to an array is (*p)[i]
+#[inline]
+fn index_target(v: GosValue, t: ValueType, stack: &Stack, objs: &VMObjects) -> GosValue {
+    match t {
+        ValueType::Named => v.unwrap_named(),
+        ValueType::Pointer => deref_value(&v, stack, objs).unwrap_named(),
+        _ => v,
+    }
+}
+
+// the Error method of the value of a panic, or else its String method, bound to
+// it, Go prints an unrecovered panic with them
+fn panic_msg_method(
+    msg: &GosValue,
+    stack: &Stack,
+    objs: &VMObjects,
+    gcv: &GcoVec,
+) -> Option<Rc<(RefCell<ClosureObj>, RCount)>> {
+    let val = match msg.unwrap_named_ref() {
+        GosValue::Interface(i) => i.borrow().underlying_value()?.clone(),
+        _ => return None,
+    };
+    let meta = val.meta(objs, stack);
+    let is_ptr = !matches!(meta, GosMetadata::NonPtr(_, _));
+    ["Error", "String"].iter().find_map(|name| {
+        let binding = meta.get_iface_binding(&name.to_string(), &objs.metas)?;
+        let fkey = match &binding {
+            // the method set of T does not contain the methods of *T
+            IfaceBinding::Struct(m, None) if !is_ptr && m.borrow().pointer_recv => return None,
+            IfaceBinding::Struct(m, _) => m.borrow().func?,
+            IfaceBinding::Iface(_, _) => return None,
+        };
+        let sig = objs.metas[objs.functions[fkey].meta.as_non_ptr()].as_signature();
+        let returns_str =
+            sig.results.len() == 1 && sig.results[0].value_type(&objs.metas) == ValueType::Str;
+        if !sig.params.is_empty() || sig.variadic.is_some() || !returns_str {
+            return None;
+        }
+        let under = IfaceUnderlying::Gos(val.clone(), Some(vec![binding.into()]));
+        match under.bind_method(0, &objs.functions, gcv).ok()? {
+            GosValue::Closure(cls) => Some(cls),
+            _ => None,
+        }
+    })
+}
+
 #[derive(Debug)]
 pub struct ByteCode {
     pub objects: Pin<Box<VMObjects>>,
@@ -143,6 +246,25 @@ impl CallFrame {
         }
     }
 
+    fn init_var_ptrs(&mut self, cls: &ClosureObj, func: &FunctionVal, stack: &Rc<RefCell<Stack>>) {
+        if let Some(uvs) = &cls.uvs {
+            let mut ptrs: Vec<UpValue> = Vec::with_capacity(func.up_ptrs.len());
+            for (i, p) in func.up_ptrs.iter().enumerate() {
+                ptrs.push(if p.is_up_value {
+                    uvs[&i].clone()
+                } else {
+                    // local pointers
+                    let uv = UpValue::new(
+                        p.clone_with_stack(Rc::downgrade(stack), self.stack_base as OpIndex),
+                    );
+                    self.add_referred_by(p.index, p.typ, &uv);
+                    uv
+                });
+            }
+            self.var_ptrs = Some(ptrs);
+        }
+    }
+
     fn add_referred_by(&mut self, index: OpIndex, typ: ValueType, uv: &UpValue) {
         if self.referred_by.is_none() {
             self.referred_by = Some(HashMap::new());
@@ -228,6 +350,34 @@ impl PanicData {
     }
 }
 
+/// The reason a script stopped before finishing, returned to the embedder
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptError {
+    /// The function could not be called, e.g. it does not exist or the
+    /// arguments do not match its signature
+    Call(String),
+    /// A goroutine panicked without recovering
+    Panic(String),
+    /// All goroutines are blocked
+    Deadlock,
+    /// The instruction budget ran out
+    Budget,
+    /// More heap objects are alive than allowed
+    Memory,
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ScriptError::Call(msg) => write!(f, "{}", msg),
+            ScriptError::Panic(msg) => write!(f, "panic: {}", msg),
+            ScriptError::Deadlock => write!(f, "all goroutines are asleep - deadlock!"),
+            ScriptError::Budget => write!(f, "instruction budget exhausted"),
+            ScriptError::Memory => write!(f, "heap object limit exceeded"),
+        }
+    }
+}
+
 #[derive(Clone)]
 struct Context<'a> {
     exec: Rc<LocalExecutor<'a>>,
@@ -236,6 +386,25 @@ struct Context<'a> {
     ffi_factory: &'a FfiFactory,
     fs: Option<&'a FileSet>,
     next_id: Cell<usize>,
+    // the executor swallows panics of detached tasks, so they are kept here
+    // and resumed after the executor is done
+    rust_panic: Rc<RefCell<Option<Box<dyn Any + Send>>>>,
+    // set when a goroutine panics without recovering, which aborts the program
+    aborted: Rc<Cell<bool>>,
+    // the reason the program was aborted
+    error: Rc<RefCell<Option<ScriptError>>>,
+    // the number of instructions left for all goroutines, unlimited if None
+    budget: Rc<Cell<Option<u64>>>,
+    // the number of heap objects that can be alive at the same time
+    max_objects: Option<usize>,
+    // the number of frames a goroutine's call stack can hold
+    max_depth: Option<usize>,
+    // the number of fibers that have not returned
+    num_fibers: Rc<Cell<usize>>,
+    // the link to the host if the program runs as a coroutine
+    host: Option<Rc<RefCell<HostLink>>>,
+    // the goroutines sleeping until a deadline
+    timers: Rc<RefCell<Timers>>,
 }
 
 impl<'a> Context<'a> {
@@ -245,6 +414,10 @@ impl<'a> Context<'a> {
         gcv: &'a GcoVec,
         ffi_factory: &'a FfiFactory,
         fs: Option<&'a FileSet>,
+        budget: Option<u64>,
+        max_objects: Option<usize>,
+        max_depth: Option<usize>,
+        host: Option<Rc<RefCell<HostLink>>>,
     ) -> Context<'a> {
         Context {
             exec: exec,
@@ -253,21 +426,79 @@ impl<'a> Context<'a> {
             ffi_factory: ffi_factory,
             fs: fs,
             next_id: Cell::new(0),
+            rust_panic: Rc::new(RefCell::new(None)),
+            aborted: Rc::new(Cell::new(false)),
+            error: Rc::new(RefCell::new(None)),
+            budget: Rc::new(Cell::new(budget)),
+            max_objects: max_objects,
+            max_depth: max_depth,
+            num_fibers: Rc::new(Cell::new(0)),
+            host: host,
+            timers: Rc::new(RefCell::new(Timers::new())),
         }
     }
 
-    fn new_entry_frame(&self, entry: FunctionKey) -> CallFrame {
-        let cls = GosValue::new_static_closure(entry, &self.code.objects.functions);
-        CallFrame::with_closure(cls.as_closure().clone(), 0)
+    // aborts all goroutines with the error, which is returned to the embedder
+    // and cannot be recovered by the Go code
+    fn abort(&self, e: ScriptError) {
+        self.aborted.set(true);
+        self.error.replace(Some(e));
+    }
+
+    /// Calls the closure in a new fiber, it's the call_go of FfiCallCtx
+    fn call_go(
+        &self,
+        cls: Rc<(RefCell<ClosureObj>, RCount)>,
+        args: Vec<GosValue>,
+    ) -> RuntimeResult<GoCall> {
+        let objs = &self.code.objects;
+        let args = check_args(&cls.0.borrow(), args, objs, self.gcv)?;
+        let (stack, frame, ret_types) = closure_fiber(cls, args, objs)?;
+        let done = Rc::new(RefCell::new(None));
+        self.spawn_fiber_with_done(stack, frame, Some(done.clone()));
+        Ok(GoCall::new(done, ret_types, self.aborted.clone()))
+    }
+
+    /// Passes the value to the host, it's the yield_to_host of FfiCallCtx
+    fn yield_to_host(&self, v: GosValue) -> RuntimeResult<HostYield> {
+        match &self.host {
+            Some(host) => {
+                let id = host.borrow_mut().put_yielded(v);
+                Ok(HostYield::new(id, host.clone(), self.aborted.clone()))
+            }
+            None => Err("cannot yield, not running as a coroutine".to_owned()),
+        }
     }
 
     fn spawn_fiber(&self, stack: Stack, first_frame: CallFrame) {
+        self.spawn_fiber_with_done(stack, first_frame, None);
+    }
+
+    // the stack of the fiber is put in done when the fiber returns,
+    // with the return values of the first frame left on it
+    fn spawn_fiber_with_done(
+        &self,
+        stack: Stack,
+        first_frame: CallFrame,
+        done: Option<Rc<RefCell<Option<Stack>>>>,
+    ) {
         let mut f = Fiber::new(self.clone(), stack, first_frame);
+        let rust_panic = self.rust_panic.clone();
+        let aborted = self.aborted.clone();
+        let num_fibers = self.num_fibers.clone();
+        num_fibers.set(num_fibers.get() + 1);
         self.exec
             .spawn(async move {
                 // let parent fiber go first
                 future::yield_now().await;
-                f.main_loop().await;
+                if let Err(e) = AssertUnwindSafe(f.main_loop()).catch_unwind().await {
+                    rust_panic.borrow_mut().get_or_insert(e);
+                    aborted.set(true);
+                }
+                num_fibers.set(num_fibers.get() - 1);
+                if let Some(d) = done {
+                    d.replace(Some(f.stack.replace(Stack::new())));
+                }
             })
             .detach();
     }
@@ -280,19 +511,30 @@ pub struct Fiber<'a> {
     next_frames: Vec<CallFrame>,
     context: Context<'a>,
     id: usize,
+    // the state of FFI code for this goroutine
+    locals: GoroutineLocals,
 }
 
 impl<'a> Fiber<'a> {
-    fn new(c: Context<'a>, stack: Stack, first_frame: CallFrame) -> Fiber<'a> {
+    fn new(c: Context<'a>, stack: Stack, mut first_frame: CallFrame) -> Fiber<'a> {
         let id = c.next_id.get();
         c.next_id.set(id + 1);
+        let stack = Rc::new(RefCell::new(stack));
+        if first_frame.var_ptrs.is_none() {
+            // the first frame is not set up by CALL when called from Rust
+            let cls = first_frame.closure().clone();
+            let cls = cls.0.borrow();
+            let func = &c.code.objects.functions[cls.func.unwrap()];
+            first_frame.init_var_ptrs(&cls, func, &stack);
+        }
         Fiber {
-            stack: Rc::new(RefCell::new(stack)),
+            stack: stack,
             rstack: RangeStack::new(),
             frames: vec![first_frame],
             next_frames: Vec::new(),
             context: c,
             id: id,
+            locals: GoroutineLocals::new(),
         }
     }
 
@@ -316,13 +558,32 @@ impl<'a> Fiber<'a> {
         let mut stack_base = frame.stack_base;
         let mut frame_height = self.frames.len();
 
-        let mut total_inst = 0;
+        let mut total_inst: u64 = 0;
+        // the instructions up to here are charged to the budget
+        let mut unit_start: u64 = 0;
         //let mut stats: HashMap<Opcode, usize> = HashMap::new();
+        // kept across yield units, unwinding a deep call stack can take many
+        let mut panic: Option<PanicData> = None;
         loop {
             let mut frame = self.frames.last_mut().unwrap();
             let mut result: Result = Result::Continue;
-            let mut panic: Option<PanicData> = None;
-            let yield_unit = 1024;
+            gcv.maybe_gc();
+            // the limits are only checked once per yield unit, so that they're
+            // free when there are none
+            if let Some(max) = self.context.max_objects {
+                if gcv.over_limit(max) {
+                    self.context.abort(ScriptError::Memory);
+                    break;
+                }
+            }
+            let yield_unit = match self.context.budget.get() {
+                None => 1024,
+                Some(0) => {
+                    self.context.abort(ScriptError::Budget);
+                    break;
+                }
+                Some(b) => b.min(1024),
+            };
             for _ in 0..yield_unit {
                 let inst = code[frame.pc];
                 let inst_op = inst.op();
@@ -369,10 +630,8 @@ impl<'a> Fiber<'a> {
                     }
                     Opcode::LOAD_INDEX => {
                         let ind = stack.pop_with_type(inst.t1());
-                        let mut val = &stack.pop_with_type(inst.t0());
-                        if inst.t0() == ValueType::Named {
-                            val = &val.as_named().0;
-                        }
+                        let val = stack.pop_with_type(inst.t0());
+                        let val = &index_target(val, inst.t0(), stack, objs);
                         if inst.t2_as_index() == 0 {
                             match val.load_index(&ind) {
                                 Ok(v) => stack.push(v),
@@ -385,10 +644,8 @@ impl<'a> Fiber<'a> {
                         }
                     }
                     Opcode::LOAD_INDEX_IMM => {
-                        let mut val = &stack.pop_with_type(inst.t0());
-                        if inst.t0() == ValueType::Named {
-                            val = &val.as_named().0;
-                        }
+                        let val = stack.pop_with_type(inst.t0());
+                        let val = &index_target(val, inst.t0(), stack, objs);
                         let index = inst.imm() as usize;
                         if inst.t2_as_index() == 0 {
                             match val.load_index_int(index) {
@@ -405,10 +662,8 @@ impl<'a> Fiber<'a> {
                         let (rhs_index, index) = inst.imm824();
                         let s_index = Stack::offset(stack.len(), index);
                         let key = stack.get_with_type(s_index + 1, inst.t2());
-                        let mut target = &stack.get_with_type(s_index, inst.t1());
-                        if inst.t1() == ValueType::Named {
-                            target = &target.as_named().0;
-                        }
+                        let target = stack.get_with_type(s_index, inst.t1());
+                        let target = &index_target(target, inst.t1(), stack, objs);
                         if let Err(e) = stack.store_index(target, &key, rhs_index, inst.t0(), gcv) {
                             go_panic_str!(panic, metadata, e, frame, code);
                         }
@@ -418,10 +673,8 @@ impl<'a> Fiber<'a> {
                         let (rhs_index, imm) = inst.imm824();
                         let index = inst.t2_as_index();
                         let s_index = Stack::offset(stack.len(), index);
-                        let mut target = &stack.get_with_type(s_index, inst.t1());
-                        if inst.t1() == ValueType::Named {
-                            target = &target.as_named().0;
-                        }
+                        let target = stack.get_with_type(s_index, inst.t1());
+                        let target = &index_target(target, inst.t1(), stack, objs);
                         if let Err(e) =
                             stack.store_index_int(target, imm, rhs_index, inst.t0(), gcv)
                         {
@@ -552,23 +805,31 @@ impl<'a> Fiber<'a> {
                         let (target, mapping) = inst.imm824();
                         let target_index = Stack::offset(stack.len(), target);
                         match inst.t0() {
+                            // untyped nil to a nil slice or map, mapping is the const
+                            // index of the metadata
+                            _ if inst.t1() == ValueType::Nil => {
+                                let meta = consts[mapping as usize].as_meta();
+                                stack.set(target_index, meta.zero_val(&objs.metas, gcv));
+                            }
                             ValueType::Interface => {
                                 let iface = ifaces[mapping as usize].clone();
                                 let under = stack.get_with_type(target_index, inst.t1());
-                                let val = match &objs.metas[iface.0.as_non_ptr()] {
-                                    MetadataType::Named(_, md) => GosValue::Named(Box::new((
-                                        GosValue::new_iface(
-                                            *md,
-                                            IfaceUnderlying::Gos(under, iface.1),
-                                        ),
-                                        iface.0,
-                                    ))),
-                                    MetadataType::Interface(_) => GosValue::new_iface(
-                                        iface.0,
-                                        IfaceUnderlying::Gos(under, iface.1),
-                                    ),
-                                    _ => unreachable!(),
+                                let underlying = match under.unwrap_named_ref() {
+                                    // converting from another interface
+                                    GosValue::Interface(i) => {
+                                        i.borrow().underlying().rebind(&iface.1)
+                                    }
+                                    // a nil interface stays nil, unlike a nil pointer
+                                    GosValue::Nil(GosMetadata::NonPtr(k, _))
+                                        if matches!(objs.metas[*k], MetadataType::Interface(_)) =>
+                                    {
+                                        IfaceUnderlying::None
+                                    }
+                                    // the interface holds its own copy of a struct or an array
+                                    _ => IfaceUnderlying::Gos(under.copy_semantic(gcv), iface.1),
                                 };
+                                let val =
+                                    GosValue::new_iface_of_type(iface.0, underlying, &objs.metas);
                                 stack.set(target_index, val);
                             }
                             ValueType::Str => {
@@ -576,6 +837,7 @@ impl<'a> Fiber<'a> {
                                     ValueType::Slice => {
                                         let slice = stack.get_rc(target_index).as_slice();
                                         match inst.t2() {
+                                            _ if slice.0.is_nil() => String::new(),
                                             ValueType::Int32 => slice
                                                 .0
                                                 .borrow()
@@ -589,16 +851,18 @@ impl<'a> Fiber<'a> {
                                                     .iter()
                                                     .map(|x| *(x.borrow().as_uint8()))
                                                     .collect();
-                                                // todo: error handling
-                                                str::from_utf8(&buf).unwrap().to_string()
+                                                // invalid UTF-8 becomes U+FFFD
+                                                String::from_utf8_lossy(&buf).into_owned()
                                             }
                                             _ => unreachable!(),
                                         }
                                     }
+                                    // an integer is a code point, without truncating it
+                                    // first, uint64s too large for int64 are invalid anyway
                                     _ => {
                                         let target = stack.get_c_mut(target_index);
-                                        target.to_uint32(inst.t1());
-                                        char_from_u32(target.get_uint32()).to_string()
+                                        target.to_int64(inst.t1());
+                                        char_from_i64(target.get_int64()).to_string()
                                     }
                                 };
                                 stack.set(target_index, GosValue::new_str(result));
@@ -718,9 +982,14 @@ impl<'a> Fiber<'a> {
                     Opcode::LEQ => stack.compare_leq(inst.t0()),
                     Opcode::GEQ => stack.compare_geq(inst.t0()),
                     Opcode::SEND => {
-                        let val = stack.pop_with_type(inst.t0());
+                        let val = stack.pop_with_type(inst.t0()).copy_semantic(gcv);
                         let chan = stack.pop_rc();
                         drop(stack_mut_ref);
+                        charge_budget!(self, total_inst, unit_start);
+                        if chan.is_nil() {
+                            // sending to a nil channel blocks forever
+                            future::pending::<()>().await;
+                        }
                         let re = chan.as_channel().send(&val).await;
                         restore_stack_ref!(self, stack, stack_mut_ref);
                         if let Err(e) = re {
@@ -729,8 +998,13 @@ impl<'a> Fiber<'a> {
                     }
                     Opcode::RECV => {
                         let chan_val = stack.pop_rc();
-                        let chan = chan_val.as_channel();
                         drop(stack_mut_ref);
+                        charge_budget!(self, total_inst, unit_start);
+                        if chan_val.is_nil() {
+                            // receiving from a nil channel blocks forever
+                            future::pending::<()>().await;
+                        }
+                        let chan = chan_val.as_channel();
                         let val = chan.recv().await;
                         restore_stack_ref!(self, stack, stack_mut_ref);
                         let (unwrapped, ok) = unwrap_recv_val!(chan, val, objs.metas, gcv);
@@ -758,12 +1032,18 @@ impl<'a> Fiber<'a> {
                     Opcode::REF_SLICE_MEMBER => {
                         let i = stack.pop_int() as OpIndex;
                         let typ = inst.t0();
-                        let arr_or_slice = stack.pop_with_type(typ);
-                        let v = match typ {
-                            ValueType::Array => PointerObj::new_array_member(&arr_or_slice, i, gcv),
-                            ValueType::Slice => {
-                                PointerObj::SliceMember(arr_or_slice.as_slice().clone(), i)
+                        let mut arr_or_slice = stack.pop_with_type(typ);
+                        // &p[i] for a pointer to an array is &(*p)[i]
+                        if typ == ValueType::Pointer {
+                            arr_or_slice = deref_value(&arr_or_slice, stack, objs);
+                        }
+                        // the array or slice may be of a named type
+                        let arr_or_slice = arr_or_slice.unwrap_named();
+                        let v = match &arr_or_slice {
+                            GosValue::Array(_) => {
+                                PointerObj::new_array_member(&arr_or_slice, i, gcv)
                             }
+                            GosValue::Slice(s) => PointerObj::SliceMember(s.clone(), i),
                             _ => unreachable!(),
                         };
                         stack.push(GosValue::new_pointer(v));
@@ -838,27 +1118,23 @@ impl<'a> Fiber<'a> {
                         match cls.func {
                             Some(key) => {
                                 let nfunc = &objs.functions[key];
-                                if let Some(uvs) = &cls.uvs {
-                                    let mut ptrs: Vec<UpValue> =
-                                        Vec::with_capacity(nfunc.up_ptrs.len());
-                                    for (i, p) in nfunc.up_ptrs.iter().enumerate() {
-                                        ptrs.push(if p.is_up_value {
-                                            uvs[&i].clone()
-                                        } else {
-                                            // local pointers
-                                            let uv = UpValue::new(p.clone_with_stack(
-                                                Rc::downgrade(&self.stack),
-                                                nframe.stack_base as OpIndex,
-                                            ));
-                                            nframe.add_referred_by(p.index, p.typ, &uv);
-                                            uv
-                                        });
-                                    }
-                                    nframe.var_ptrs = Some(ptrs);
+                                nframe.init_var_ptrs(cls, nfunc, &self.stack);
+                                // structs and arrays are passed by value
+                                for i in nfunc.value_params() {
+                                    let index = nframe.stack_base + nfunc.ret_count() + i;
+                                    let val = stack.get_rc(index).copy_semantic(gcv);
+                                    *stack.get_rc_mut(index) = val;
                                 }
                                 match call_style {
                                     ValueType::Zero => {
                                         // default call
+                                        if let Some(max) = self.context.max_depth {
+                                            if frame_height >= max {
+                                                let e = "stack overflow".to_owned();
+                                                go_panic_str!(panic, metadata, e, frame, code);
+                                                continue;
+                                            }
+                                        }
                                         self.frames.push(nframe);
                                         frame_height += 1;
                                         frame = self.frames.last_mut().unwrap();
@@ -881,6 +1157,8 @@ impl<'a> Fiber<'a> {
                                     }
                                     ValueType::FlagB => {
                                         let (c, rc) = stack.pop_n(nfunc.param_count());
+                                        // the return values are pushed again when it's called
+                                        stack.truncate(nframe.stack_base);
                                         let deferred = DeferredCall {
                                             frame: nframe,
                                             stack_c: c,
@@ -893,24 +1171,50 @@ impl<'a> Fiber<'a> {
                             }
                             None => {
                                 let call = cls.ffi.as_ref().unwrap();
-                                let ptypes = &objs.metas[call.meta.as_non_ptr()]
-                                    .as_signature()
-                                    .params_type;
-                                let params = stack.pop_with_type_n(ptypes);
+                                let sig = objs.metas[call.meta.as_non_ptr()].as_signature();
+                                let params = stack.pop_with_type_n(&sig.params_type);
                                 // release stack so that code in ffi can yield
                                 drop(stack_mut_ref);
+                                charge_budget!(self, total_inst, unit_start);
                                 let returns = {
                                     let ffi_ref = call.ffi.borrow();
-                                    let mut ctx = FfiCallCtx {
-                                        func_name: &call.func_name,
-                                        vm_objs: objs,
-                                        stack: &mut self.stack.borrow_mut(),
-                                        gcv: gcv,
+                                    // the stack is released before awaiting, the Go
+                                    // closures called meanwhile may load upvalues on it
+                                    let fut = {
+                                        let call_go = |cls, args| self.context.call_go(cls, args);
+                                        let yield_to_host = |v| self.context.yield_to_host(v);
+                                        let sleep_until = |when| {
+                                            self.context.timers.borrow_mut().sleep_until(when)
+                                        };
+                                        let mut ctx = FfiCallCtx {
+                                            func_name: &call.func_name,
+                                            vm_objs: objs,
+                                            stack: &mut self.stack.borrow_mut(),
+                                            gcv: gcv,
+                                            num_goroutines: self.context.num_fibers.get(),
+                                            call_go: &call_go,
+                                            yield_to_host: &yield_to_host,
+                                            sleep_until: &sleep_until,
+                                            locals: &mut self.locals,
+                                        };
+                                        ffi_ref.call(&mut ctx, params)
                                     };
-                                    let fut = ffi_ref.call(&mut ctx, params);
                                     fut.await
                                 };
                                 restore_stack_ref!(self, stack, stack_mut_ref);
+                                // the FFI is not type checked, so the number of the
+                                // results has to be checked here
+                                let returns = returns.and_then(|result| {
+                                    match result.len() == sig.results.len() {
+                                        true => Ok(result),
+                                        false => Err(format!(
+                                            "ffi method {} returned {} values, expected {}",
+                                            call.func_name,
+                                            result.len(),
+                                            sig.results.len()
+                                        )),
+                                    }
+                                });
                                 match returns {
                                     Ok(result) => stack.append(result),
                                     Err(e) => {
@@ -932,14 +1236,8 @@ impl<'a> Fiber<'a> {
                             }
                             // init_package func
                             ValueType::FlagA => {
-                                let index = inst.imm() as usize;
-                                let pkey = pkgs[index];
-                                let pkg = &objs.packages[pkey];
-                                let count = pkg.var_count();
-                                // remove garbage first
-                                debug_assert!(stack.len() == stack_base + count);
-                                // the var values left on the stack are for pkg members
-                                stack.init_pkg_vars(pkg, count);
+                                // the package vars have been stored to the members
+                                stack.truncate(stack_base);
                             }
                             // func with deferred calls
                             ValueType::FlagB => {
@@ -948,14 +1246,24 @@ impl<'a> Fiber<'a> {
                                         // run Opcode::RETURN to check if deferred_stack is empty
                                         frame.pc -= 1;
 
-                                        stack.push_n(call.stack_c, call.stack_rc);
+                                        // discard the results of the previous deferred call,
+                                        // and put the return values and params back to
+                                        // where they were when the call was deferred
                                         let nframe = call.frame;
+                                        if stack.len() > nframe.stack_base {
+                                            stack.truncate(nframe.stack_base);
+                                        }
+                                        while stack.len() < nframe.stack_base {
+                                            stack.push_nil();
+                                        }
+                                        let fkey = nframe.func();
+                                        func = &objs.functions[fkey];
+                                        stack.append(func.ret_zeros.clone());
+                                        stack.push_n(call.stack_c, call.stack_rc);
 
                                         self.frames.push(nframe);
                                         frame_height += 1;
                                         frame = self.frames.last_mut().unwrap();
-                                        let fkey = frame.closure.0.borrow().func.unwrap();
-                                        func = &objs.functions[fkey];
                                         stack_base = frame.stack_base;
                                         consts = &func.consts;
                                         code = func.code();
@@ -1055,6 +1363,7 @@ impl<'a> Fiber<'a> {
                                 match &flag {
                                     ValueType::FlagA => {
                                         let val = stack.pop_with_type(sel_code.t1());
+                                        let val = val.copy_semantic(gcv);
                                         let chan = stack.pop_rc();
                                         channel::SelectComm::Send(chan, val, offset)
                                     }
@@ -1069,6 +1378,7 @@ impl<'a> Fiber<'a> {
                         let selector = channel::Selector::new(comms, default_offset);
 
                         drop(stack_mut_ref);
+                        charge_budget!(self, total_inst, unit_start);
                         let re = selector.select().await;
                         restore_stack_ref!(self, stack, stack_mut_ref);
 
@@ -1123,26 +1433,64 @@ impl<'a> Fiber<'a> {
                     }
 
                     Opcode::TYPE_ASSERT => {
-                        let val = match stack.pop_interface().borrow().underlying() {
-                            IfaceUnderlying::Gos(v, _) => v.copy_semantic(gcv),
+                        // a nil interface is either Nil or an interface without underlying
+                        let val = match stack.pop_rc().unwrap_named() {
+                            GosValue::Interface(i) => match i.borrow().underlying() {
+                                IfaceUnderlying::Gos(v, _) => v.copy_semantic(gcv),
+                                _ => GosValue::new_nil(),
+                            },
                             _ => GosValue::new_nil(),
                         };
-                        let meta = GosValue::Metadata(val.meta(objs, stack));
+                        let target = consts[inst.imm() as usize].as_meta();
+                        let (val, ok) = match target.underlying(&objs.metas).value_type(&objs.metas)
+                        {
+                            // asserting to an interface type, which val has to implement
+                            ValueType::Interface => {
+                                let bindings = match &val {
+                                    GosValue::Nil(_) => None,
+                                    _ => val.meta(objs, stack).iface_bindings(target, &objs.metas),
+                                };
+                                match bindings {
+                                    Some(b) => {
+                                        let b = b.into_iter().map(|x| x.into()).collect();
+                                        let under = IfaceUnderlying::Gos(val, Some(b));
+                                        let v = GosValue::new_iface_of_type(
+                                            *target,
+                                            under,
+                                            &objs.metas,
+                                        );
+                                        (v, true)
+                                    }
+                                    None => (target.zero_val(&objs.metas, gcv), false),
+                                }
+                            }
+                            _ => {
+                                let ok = val.meta(objs, stack).identical(target, &objs.metas);
+                                (val, ok)
+                            }
+                        };
+                        let is_nil = matches!(val, GosValue::Nil(_));
                         stack.push(val);
-                        let ok = &consts[inst.imm() as usize] == &meta;
                         let do_try = inst.t2_as_index() > 0;
                         if !do_try {
                             if !ok {
-                                // todo go_panic
-                                unimplemented!()
+                                let msg = match is_nil {
+                                    true => "interface conversion: interface is nil",
+                                    false => "interface conversion: type assertion failed",
+                                };
+                                go_panic_str!(panic, &objs.metadata, msg.to_owned(), frame, code);
                             }
                         } else {
                             stack.push_bool(ok);
                         }
                     }
                     Opcode::TYPE => {
-                        let val = match stack.pop_interface().borrow().underlying() {
-                            IfaceUnderlying::Gos(v, _) => v.copy_semantic(gcv),
+                        // a nil interface is either Nil or an interface without underlying
+                        let val = match stack.pop_rc().unwrap_named() {
+                            GosValue::Interface(i) => match i.borrow().underlying() {
+                                IfaceUnderlying::Gos(v, _) => v.copy_semantic(gcv),
+                                _ => GosValue::new_nil(),
+                            },
                             _ => GosValue::new_nil(),
                         };
                         stack.push(GosValue::Metadata(val.meta(objs, stack)));
@@ -1153,35 +1501,75 @@ impl<'a> Fiber<'a> {
                         }
                     }
                     Opcode::IMPORT => {
-                        let pkey = pkgs[inst.imm() as usize];
-                        stack.push(GosValue::Bool(!objs.packages[pkey].inited()));
+                        let pkg = &objs.packages[pkgs[inst.imm() as usize]];
+                        stack.push(GosValue::Bool(!pkg.inited()));
+                        pkg.set_inited();
                     }
                     Opcode::SLICE | Opcode::SLICE_FULL => {
-                        let max = if inst_op == Opcode::SLICE_FULL {
-                            stack.pop_int()
-                        } else {
-                            -1
+                        let max = match inst_op == Opcode::SLICE_FULL {
+                            true => Some(stack.pop_int()),
+                            false => None,
                         };
                         let end = stack.pop_int();
                         let begin = stack.pop_int();
-                        let target = stack.pop_with_type(inst.t0());
-                        let result = match &target {
-                            GosValue::Slice(sl) => {
-                                if max > sl.0.cap() as isize {
-                                    let msg = format!("index {} out of range", max).to_string();
-                                    go_panic_str!(panic, metadata, msg, frame, code);
-                                }
-                                GosValue::Slice(Rc::new((
-                                    sl.0.slice(begin, end, max),
-                                    Cell::new(0),
-                                )))
+                        let (target, named) = match stack.pop_with_type(inst.t0()) {
+                            GosValue::Named(n) => (n.0, Some(n.1)),
+                            v => (v, None),
+                        };
+                        let (len, cap) = match &target {
+                            GosValue::Slice(sl) => (sl.0.len(), sl.0.cap()),
+                            GosValue::Str(s) => (s.len(), s.len()),
+                            GosValue::Array(a) => (a.0.len(), a.0.len()),
+                            _ => unreachable!(),
+                        };
+                        let end = match inst.t1() == ValueType::FlagA {
+                            true => len as isize,
+                            false => end,
+                        };
+                        let cap_name = match &target {
+                            GosValue::Slice(_) => "capacity",
+                            _ => "length",
+                        };
+                        if let Err(msg) = check_slice_bounds(begin, end, max, cap, cap_name) {
+                            go_panic_str!(panic, metadata, msg, frame, code);
+                            continue;
+                        }
+                        // strings hold UTF-8 text, which can't be cut inside a character
+                        if let GosValue::Str(s) = &target {
+                            let s = s.as_str();
+                            if !s.is_char_boundary(begin as usize)
+                                || !s.is_char_boundary(end as usize)
+                            {
+                                let msg = format!(
+                                    "slice bounds [{}:{}] are not at character boundaries",
+                                    begin, end
+                                );
+                                go_panic_str!(panic, metadata, msg, frame, code);
+                                continue;
                             }
+                        }
+                        let max = max.unwrap_or(-1);
+                        let result = match &target {
+                            GosValue::Slice(sl) => GosValue::Slice(Rc::new((
+                                sl.0.slice(begin, end, max),
+                                Cell::new(0),
+                            ))),
                             GosValue::Str(s) => GosValue::Str(Rc::new(s.slice(begin, end))),
                             GosValue::Array(_) => {
-                                GosValue::slice_with_array(&target, begin, end, gcv)
+                                let s = GosValue::slice_with_array(&target, 0, -1, gcv);
+                                let s = s.as_slice().0.slice(begin, end, max);
+                                GosValue::slice_with_obj(s, gcv)
                             }
                             _ => unreachable!(),
                         };
+                        // slicing a string or a slice keeps its type, slicing an
+                        // array makes a slice of its element type
+                        let result = match (named, &target) {
+                            (Some(m), GosValue::Slice(_) | GosValue::Str(_)) => {
+                                GosValue::Named(Box::new((result, m)))
+                            }
+                            _ => result,
+                        };
                         stack.push(result);
                     }
                     Opcode::LITERAL => {
@@ -1193,22 +1581,39 @@ impl<'a> Fiber<'a> {
                                 let mut val = ClosureObj::new_gos(*fkey, &objs.functions, None);
                                 if let Some(uvs) = &mut val.uvs {
                                     drop(frame);
-                                    for (_, uv) in uvs.iter_mut() {
-                                        let r: &mut UpValueState = &mut uv.inner.borrow_mut();
-                                        if let UpValueState::Open(d) = r {
+                                    for (i, uv) in uvs.iter_mut() {
+                                        let mut found = false;
+                                        let mut r = uv.inner.borrow_mut();
+                                        if let UpValueState::Open(d) = &mut *r {
                                             // get frame index, and add_referred_by
-                                            for i in 1..frame_height {
+                                            for i in 1..=frame_height {
                                                 let index = frame_height - i;
                                                 if self.frames[index].func() == d.func {
                                                     let upframe = &mut self.frames[index];
                                                     d.stack = Rc::downgrade(&self.stack);
                                                     d.stack_base = upframe.stack_base as OpIndex;
                                                     upframe.add_referred_by(d.index, d.typ, uv);
-                                                    // if not found, the upvalue is already closed, nothing to be done
+                                                    found = true;
                                                     break;
                                                 }
                                             }
                                         }
+                                        drop(r);
+                                        // if not found, the frame has returned or belongs to another
+                                        // goroutine, share the upvalue of the current closure
+                                        if !found {
+                                            let cur = &self.frames[frame_height - 1];
+                                            let ptrs = cur.var_ptrs.as_ref().unwrap();
+                                            let d = &objs.functions[*fkey].up_ptrs[*i];
+                                            let j = objs.functions[cur.func()]
+                                                .up_ptrs
+                                                .iter()
+                                                .position(|p| {
+                                                    p.func == d.func && p.index == d.index
+                                                })
+                                                .unwrap();
+                                            *uv = ptrs[j].clone();
+                                        }
                                         //dbg!(&desc, &upframe);
                                     }
                                     frame = self.frames.last_mut().unwrap();
@@ -1222,14 +1627,17 @@ impl<'a> Fiber<'a> {
                                 let (key, mc) = umd.unwrap_non_ptr();
                                 let count = stack.pop_int32();
                                 let val = match &objs.metas[key] {
-                                    MetadataType::SliceOrArray(asm, _) => {
+                                    MetadataType::SliceOrArray(asm, size) => {
                                         let elem_type = asm.value_type(&objs.metas);
-                                        let zero_val = asm.zero_val(&objs.metas, gcv);
+                                        // every zero value in the gaps is a new one,
+                                        // so that structs are not shared
+                                        let zero_val = || asm.zero_val(&objs.metas, gcv);
                                         let mut val = vec![];
                                         let mut cur_index = -1;
                                         for _ in 0..count {
                                             let i = stack.pop_int();
-                                            let elem = stack.pop_with_type(elem_type);
+                                            let elem =
+                                                stack.pop_with_type(elem_type).copy_semantic(gcv);
                                             if i < 0 {
                                                 cur_index += 1;
                                             } else {
@@ -1240,7 +1648,7 @@ impl<'a> Fiber<'a> {
                                                 val.push(elem);
                                             } else if gap > 0 {
                                                 for _ in 0..gap {
-                                                    val.push(zero_val.clone());
+                                                    val.push(zero_val());
                                                 }
                                                 val.push(elem);
                                             } else {
@@ -1252,6 +1660,8 @@ impl<'a> Fiber<'a> {
                                                 GosValue::slice_with_val(val, *md, gcv)
                                             }
                                             MetaCategory::Array => {
+                                                // the elements after the last one are zeros
+                                                val.resize_with(*size, zero_val);
                                                 GosValue::array_with_val(val, *md, gcv)
                                             }
                                             _ => unreachable!(),
@@ -1264,8 +1674,8 @@ impl<'a> Fiber<'a> {
                                         let tk = km.value_type(&objs.metas);
                                         let tv = vm.value_type(&objs.metas);
                                         for _ in 0..count {
-                                            let k = stack.pop_with_type(tk);
-                                            let v = stack.pop_with_type(tv);
+                                            let k = stack.pop_with_type(tk).copy_semantic(gcv);
+                                            let v = stack.pop_with_type(tv).copy_semantic(gcv);
                                             map.0.insert(k, v);
                                         }
                                         gosv
@@ -1276,7 +1686,8 @@ impl<'a> Fiber<'a> {
                                         for _ in 0..count {
                                             let index = stack.pop_uint();
                                             let tv = f.fields[index].0.value_type(&objs.metas);
-                                            sref.fields[index] = stack.pop_with_type(tv);
+                                            sref.fields[index] =
+                                                stack.pop_with_type(tv).copy_semantic(gcv);
                                         }
                                         drop(sref);
                                         struct_val
@@ -1315,7 +1726,7 @@ impl<'a> Fiber<'a> {
                         let index = inst.imm() - 1;
                         let i = Stack::offset(stack.len(), index - 1);
                         let meta_val = stack.get_with_type(i, ValueType::Metadata);
-                        let md = meta_val.as_meta();
+                        let md = &meta_val.as_meta().into_value_category();
                         let is_named = inst.t1() == ValueType::Named;
                         let umd = is_named.then(|| md.underlying(&objs.metas)).unwrap_or(*md);
                         let metadata = &objs.metas[umd.as_non_ptr()];
@@ -1408,6 +1819,8 @@ impl<'a> Fiber<'a> {
                             GosValue::Map(map) => map.0.len(),
                             GosValue::Str(sval) => sval.len(),
                             GosValue::Channel(chan) => chan.len(),
+                            // a nil channel
+                            GosValue::Nil(_) => 0,
                             _ => unreachable!(),
                         };
                         stack.push(GosValue::Int(l as isize));
@@ -1416,6 +1829,7 @@ impl<'a> Fiber<'a> {
                         let l = match &stack.pop_with_type(inst.t0()).unwrap_named_ref() {
                             GosValue::Slice(slice) => slice.0.cap(),
                             GosValue::Channel(chan) => chan.cap(),
+                            GosValue::Nil(_) => 0,
                             _ => unreachable!(),
                         };
                         stack.push(GosValue::Int(l as isize));
@@ -1423,6 +1837,11 @@ impl<'a> Fiber<'a> {
                     Opcode::APPEND => {
                         let index = Stack::offset(stack.len(), inst.imm() - 2);
                         let a = stack.get_with_type(index, inst.t0());
+                        // the result keeps the named type of a
+                        let named = match &a {
+                            GosValue::Named(n) => Some(n.1),
+                            _ => None,
+                        };
                         let a = a.unwrap_named();
                         let vala = a.as_slice();
                         match inst.t2() {
@@ -1448,9 +1867,16 @@ impl<'a> Fiber<'a> {
                         let mut result = vala.0.clone();
                         let b = stack.pop_with_type(ValueType::Slice);
                         let valb = b.as_slice();
-                        result.append(&valb.0);
+                        result.append(&valb.0, &objs.metas, gcv);
 
-                        stack.set(index, GosValue::slice_with_obj(result, gcv));
+                        let result = GosValue::slice_with_obj(result, gcv);
+                        stack.set(
+                            index,
+                            match named {
+                                Some(meta) => GosValue::Named(Box::new((result, meta))),
+                                None => result,
+                            },
+                        );
                     }
                     Opcode::COPY => {
                         let t2 = match inst.t2() {
@@ -1478,12 +1904,64 @@ impl<'a> Fiber<'a> {
                     }
                     Opcode::DELETE => {
                         let key = &stack.pop_with_type(inst.t1());
-                        let map = &stack.pop_with_type(inst.t0()).unwrap_named();
-                        map.as_map().0.delete(key);
+                        match &stack.pop_with_type(inst.t0()).unwrap_named_ref() {
+                            // a missing key or a nil map is a no-op
+                            GosValue::Map(map) => map.0.delete(key),
+                            // a nil map converted from untyped nil
+                            GosValue::Nil(_) => {}
+                            _ => unreachable!(),
+                        }
+                    }
+                    Opcode::CLEAR => {
+                        let target = &stack.pop_with_type(inst.t0()).unwrap_named();
+                        match target {
+                            GosValue::Map(map) => map.0.clear(),
+                            GosValue::Nil(_) => {}
+                            GosValue::Slice(slice) => {
+                                let elem = match &objs.metas[slice.0.meta.as_non_ptr()] {
+                                    MetadataType::SliceOrArray(elem, _) => elem,
+                                    _ => unreachable!(),
+                                };
+                                let zero = zero_val!(elem, objs, gcv);
+                                for i in 0..slice.0.len() {
+                                    slice.0.set(i, zero.copy_semantic(gcv));
+                                }
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                    Opcode::MIN | Opcode::MAX => {
+                        let count = inst.imm() as usize;
+                        let mut vals: Vec<GosValue> =
+                            (0..count).map(|_| stack.pop_with_type(inst.t0())).collect();
+                        vals.reverse();
+                        let ord = match inst.op() {
+                            Opcode::MIN => std::cmp::Ordering::Less,
+                            _ => std::cmp::Ordering::Greater,
+                        };
+                        // a NaN wins over any other value
+                        let is_nan = |v: &GosValue| match v.unwrap_named_ref() {
+                            GosValue::Float32(f) => f.is_nan(),
+                            GosValue::Float64(f) => f.is_nan(),
+                            _ => false,
+                        };
+                        let mut result = vals.remove(0);
+                        for v in vals.into_iter() {
+                            if !is_nan(&result) && (is_nan(&v) || v.cmp(&result) == ord) {
+                                result = v;
+                            }
+                        }
+                        stack.push(result);
                     }
                     Opcode::CLOSE => {
-                        let chan = stack.pop_with_type(ValueType::Channel);
-                        chan.as_channel().close();
+                        let chan = stack.pop_rc();
+                        let re = match chan.is_nil() {
+                            true => Err("close of nil channel".to_owned()),
+                            false => chan.as_channel().close(),
+                        };
+                        if let Err(e) = re {
+                            go_panic_str!(panic, metadata, e, frame, code);
+                        }
                     }
                     Opcode::PANIC => {
                         let val = stack.pop_rc();
@@ -1495,8 +1973,19 @@ impl<'a> Fiber<'a> {
                         stack.push(val);
                     }
                     Opcode::ASSERT => {
+                        let detail = match inst.imm() {
+                            2 => format!(
+                                ": {}",
+                                stack.pop_with_type(ValueType::Str).as_str().as_str()
+                            ),
+                            _ => String::new(),
+                        };
                         if !stack.pop_bool() {
-                            let msg = "Opcode::ASSERT: not true!".to_owned();
+                            let at = match (self.context.fs, func.pos()[frame.pc - 1]) {
+                                (Some(fs), Some(p)) => format!(" at {}", fs.position(p)),
+                                _ => String::new(),
+                            };
+                            let msg = format!("assertion failed{}{}", at, detail);
                             go_panic_str!(panic, metadata, msg, frame, code);
                         }
                     }
@@ -1537,10 +2026,28 @@ impl<'a> Fiber<'a> {
                 };
                 //dbg!(inst_op, stack.len());
             } //yield unit
+            charge_budget!(self, total_inst, unit_start);
             match result {
                 Result::End => {
                     if let Some(p) = panic {
-                        println!("panic: {}", p.msg);
+                        // errors and Stringers print with their methods, which
+                        // run as goroutines before the program is aborted
+                        let mut msg = None;
+                        if let Some(cls) = panic_msg_method(&p.msg, stack, objs, self.context.gcv) {
+                            drop(stack_mut_ref);
+                            if let Ok(call) = self.context.call_go(cls, vec![]) {
+                                if let Ok(r) = call.results().await {
+                                    msg = Some(r[0].as_str().as_str().to_string());
+                                }
+                            }
+                            restore_stack_ref!(self, stack, stack_mut_ref);
+                        }
+                        let msg = msg.unwrap_or_else(|| p.msg.to_string());
+                        self.context.aborted.set(true);
+                        self.context
+                            .error
+                            .replace(Some(ScriptError::Panic(msg.clone())));
+                        println!("panic: {}", msg);
                         if let Some(files) = self.context.fs {
                             for (fkey, pc) in p.call_stack.iter() {
                                 let func = &objs.functions[*fkey];
@@ -1551,15 +2058,6 @@ impl<'a> Fiber<'a> {
                                 }
                             }
                         }
-
-                        // a hack to make the test case fail
-                        if let GosValue::Str(s) =
-                            p.msg.as_interface().borrow().underlying_value().unwrap()
-                        {
-                            if s.as_str().starts_with("Opcode::ASSERT") {
-                                panic!("ASSERT");
-                            }
-                        }
                     }
                     break;
                 }
@@ -1577,36 +2075,350 @@ impl<'a> Fiber<'a> {
 }
 
 pub struct GosVM<'a> {
-    code: ByteCode,
-    gcv: GcoVec,
+    code: &'a ByteCode,
+    gcv: &'a GcoVec,
     ffi: &'a FfiFactory,
     fs: Option<&'a FileSet>,
+    // the number of instructions a run or a call can execute
+    budget: Option<u64>,
+    // the number of heap objects that can be alive at the same time
+    max_objects: Option<usize>,
+    // the number of frames a goroutine's call stack can hold
+    max_depth: Option<usize>,
 }
 
 impl<'a> GosVM<'a> {
-    pub fn new(bc: ByteCode, ffi: &'a FfiFactory, fs: Option<&'a FileSet>) -> GosVM<'a> {
+    pub fn new(
+        bc: &'a ByteCode,
+        gcv: &'a GcoVec,
+        ffi: &'a FfiFactory,
+        fs: Option<&'a FileSet>,
+        budget: Option<u64>,
+        max_objects: Option<usize>,
+        max_depth: Option<usize>,
+    ) -> GosVM<'a> {
         GosVM {
             code: bc,
-            gcv: GcoVec::new(),
+            gcv: gcv,
             ffi: ffi,
             fs: fs,
+            budget: budget,
+            max_objects: max_objects,
+            max_depth: max_depth,
         }
     }
 
-    pub fn run(&self) {
+    /// Runs the program. If wait_for_goroutines is false, it returns as soon as
+    /// the main goroutine returns, like Go does, other goroutines are abandoned.
+    /// Otherwise it keeps running until there is no runnable goroutine left.
+    /// In both cases, an unrecovered panic in any goroutine, running out of the
+    /// instruction budget or exceeding the heap object limit aborts the program.
+    pub fn run(&self, wait_for_goroutines: bool) -> result::Result<(), ScriptError> {
+        let cls = GosValue::new_static_closure(self.code.entry, &self.code.objects.functions);
+        let entry = CallFrame::with_closure(cls.as_closure().clone(), 0);
+        self.run_fiber(Stack::new(), entry, wait_for_goroutines)
+            .map(|_| ())
+    }
+
+    /// Calls the function `name` of the package `pkg` and returns its results.
+    /// The package is initialized first if it has not been, the same way an
+    /// import does. Other goroutines are abandoned when the function returns.
+    /// The instruction budget applies to the initialization and the call
+    /// separately.
+    pub fn call_function(
+        &self,
+        pkg: &str,
+        name: &str,
+        args: Vec<GosValue>,
+    ) -> result::Result<Vec<GosValue>, ScriptError> {
+        let (cls, args) = self.function(pkg, name, args)?;
+        self.call_closure(cls, args)
+    }
+
+    /// Starts calling the function `name` of the package `pkg` as a coroutine
+    /// driven by the host: it runs when resumed, until a goroutine yields a
+    /// value to the host with FfiCallCtx::yield_to_host or the function
+    /// returns. The package is initialized right away like by call_function,
+    /// and cannot yield. The instruction budget applies to the whole call.
+    pub fn start_function(
+        &self,
+        pkg: &str,
+        name: &str,
+        args: Vec<GosValue>,
+    ) -> result::Result<Coroutine<'a>, ScriptError> {
+        let (cls, args) = self.function(pkg, name, args)?;
+        let (stack, frame, ret_types) =
+            closure_fiber(cls, args, &self.code.objects).map_err(ScriptError::Call)?;
+        let host = Rc::new(RefCell::new(HostLink::new()));
+        Ok(Coroutine {
+            runner: self.runner(stack, frame, Some(host.clone())),
+            host: host,
+            ret_types: ret_types,
+            pending: None,
+            returned: false,
+        })
+    }
+
+    /// Looks up the function and checks the arguments against it, and
+    /// initializes its package
+    fn function(
+        &self,
+        pkg: &str,
+        name: &str,
+        args: Vec<GosValue>,
+    ) -> result::Result<(Rc<(RefCell<ClosureObj>, RCount)>, Vec<GosValue>), ScriptError> {
+        let objs = &self.code.objects;
+        let pkey = *self
+            .code
+            .packages
+            .iter()
+            .find(|k| objs.packages[**k].name() == pkg)
+            .ok_or_else(|| ScriptError::Call(format!("package not found: {}", pkg)))?;
+        let pkg_val = &objs.packages[pkey];
+        let member = pkg_val
+            .get_member_index(name)
+            .map(|i| pkg_val.member(*i).clone());
+        let cls = match member {
+            Some(GosValue::Closure(c)) if c.0.borrow().func.is_some() => c,
+            _ => {
+                let msg = format!("{}.{} is not a function", pkg, name);
+                return Err(ScriptError::Call(msg));
+            }
+        };
+        let args = check_args(&cls.0.borrow(), args, objs, self.gcv)
+            .map_err(|e| ScriptError::Call(format!("{}.{}: {}", pkg, name, e)))?;
+
+        self.init_package(pkey)?;
+        Ok((cls, args))
+    }
+
+    /// Runs the constructor and the init functions of the package
+    fn init_package(&self, pkey: PackageKey) -> result::Result<(), ScriptError> {
+        let pkg = &self.code.objects.packages[pkey];
+        if pkg.inited() {
+            return Ok(());
+        }
+        pkg.set_inited();
+        // the 0th member is the constructor
+        let ctor = pkg.member(0).as_closure().clone();
+        self.call_closure(ctor, vec![])?;
+        let mut i = 0;
+        while let Some(f) = pkg.init_func(i) {
+            self.call_closure(f.as_closure().clone(), vec![])?;
+            i += 1;
+        }
+        Ok(())
+    }
+
+    /// Runs the closure in a new fiber until it returns, and returns its results
+    fn call_closure(
+        &self,
+        cls: Rc<(RefCell<ClosureObj>, RCount)>,
+        args: Vec<GosValue>,
+    ) -> result::Result<Vec<GosValue>, ScriptError> {
+        let (stack, frame, types) =
+            closure_fiber(cls, args, &self.code.objects).map_err(ScriptError::Call)?;
+        let mut stack = self.run_fiber(stack, frame, false)?;
+        Ok(stack.pop_with_type_n(&types))
+    }
+
+    /// Runs a fiber starting with the frame, returns its stack when it's done
+    fn run_fiber(
+        &self,
+        stack: Stack,
+        frame: CallFrame,
+        wait_for_goroutines: bool,
+    ) -> result::Result<Stack, ScriptError> {
+        let runner = self.runner(stack, frame, None);
+        runner
+            .run(wait_for_goroutines)?
+            .ok_or(ScriptError::Deadlock)
+    }
+
+    /// Creates an executor with a fiber starting with the frame
+    fn runner(
+        &self,
+        stack: Stack,
+        frame: CallFrame,
+        host: Option<Rc<RefCell<HostLink>>>,
+    ) -> Runner<'a> {
         let exec = Rc::new(LocalExecutor::new());
-        let ctx = Context::new(exec.clone(), &self.code, &self.gcv, self.ffi, self.fs);
-        let entry = ctx.new_entry_frame(self.code.entry);
-        ctx.spawn_fiber(Stack::new(), entry);
+        let ctx = Context::new(
+            exec.clone(),
+            self.code,
+            self.gcv,
+            self.ffi,
+            self.fs,
+            self.budget,
+            self.max_objects,
+            self.max_depth,
+            host,
+        );
+        let done = Rc::new(RefCell::new(None));
+        ctx.spawn_fiber_with_done(stack, frame, Some(done.clone()));
+        Runner {
+            exec: exec,
+            ctx: ctx,
+            done: done,
+        }
+    }
+}
+
+/// The executor running the goroutines of a call from the embedder, and the
+/// first fiber's stack once it's done
+struct Runner<'a> {
+    exec: Rc<LocalExecutor<'a>>,
+    ctx: Context<'a>,
+    done: Rc<RefCell<Option<Stack>>>,
+}
 
+impl<'a> Runner<'a> {
+    /// Runs the goroutines until the first fiber is done, or all of them are
+    /// if wait_for_goroutines, or a goroutine yields to the host. Returns the
+    /// stack of the first fiber if it's done.
+    fn run(&self, wait_for_goroutines: bool) -> result::Result<Option<Stack>, ScriptError> {
+        let ctx = &self.ctx;
         future::block_on(async {
             loop {
-                if !exec.try_tick() {
+                let ticked = self.exec.try_tick();
+                // the goroutines busy waiting for channels don't let the
+                // executor go idle, so the sleeps are checked after every tick
+                ctx.timers.borrow_mut().wake_expired();
+                if !ticked {
+                    // nothing can run, wait for the earliest sleeping goroutine
+                    // unless the run is over or the host has to resume one first
+                    let yielded = ctx
+                        .host
+                        .as_ref()
+                        .map_or(false, |h| h.borrow().has_yielded());
+                    let over = !wait_for_goroutines && self.done.borrow().is_some();
+                    let timeout = ctx.timers.borrow_mut().next_timeout();
+                    match timeout {
+                        Some(d) if !yielded && !over && !ctx.aborted.get() => {
+                            std::thread::sleep(d);
+                            ctx.timers.borrow_mut().wake_expired();
+                            continue;
+                        }
+                        _ => break,
+                    }
+                }
+                if ctx.aborted.get() || (!wait_for_goroutines && self.done.borrow().is_some()) {
                     break;
                 }
+                if let Some(host) = &ctx.host {
+                    if host.borrow().has_yielded() {
+                        break;
+                    }
+                }
             }
         });
+        let rust_panic = ctx.rust_panic.borrow_mut().take();
+        if let Some(e) = rust_panic {
+            std::panic::resume_unwind(e);
+        }
+        if let Some(e) = ctx.error.borrow_mut().take() {
+            return Err(e);
+        }
+        Ok(self.done.borrow_mut().take())
+    }
+}
+
+/// What a coroutine did when it was resumed
+#[derive(Clone, Debug, PartialEq)]
+pub enum CoroutineState {
+    /// A goroutine yielded the value to the host, it waits to be resumed
+    Yielded(GosValue),
+    /// The function returned the results, the coroutine is done
+    Returned(Vec<GosValue>),
+}
+
+/// A function call driven by the host, created by GosVM::start_function.
+/// Other goroutines are abandoned when the function returns.
+pub struct Coroutine<'a> {
+    runner: Runner<'a>,
+    host: Rc<RefCell<HostLink>>,
+    ret_types: Vec<ValueType>,
+    // the id of the yield to answer by the next resume
+    pending: Option<usize>,
+    returned: bool,
+}
+
+impl<'a> Coroutine<'a> {
+    /// Runs the coroutine until a goroutine yields a value to the host or the
+    /// function returns. The yield the coroutine is suspended at returns v,
+    /// the first resume starts the call and ignores it.
+    pub fn resume(&mut self, v: GosValue) -> result::Result<CoroutineState, ScriptError> {
+        if self.returned {
+            return Err(ScriptError::Call("the coroutine has returned".to_owned()));
+        }
+        if let Some(id) = self.pending.take() {
+            self.host.borrow_mut().resume(id, v);
+        }
+        // the other goroutines are abandoned when the function returns, even
+        // if one of them has just yielded
+        if let Some(mut stack) = self.runner.run(false)? {
+            self.returned = true;
+            return Ok(CoroutineState::Returned(
+                stack.pop_with_type_n(&self.ret_types),
+            ));
+        }
+        match self.host.borrow_mut().take_yielded() {
+            Some((id, v)) => {
+                self.pending = Some(id);
+                Ok(CoroutineState::Yielded(v))
+            }
+            None => Err(ScriptError::Deadlock),
+        }
+    }
+}
+
+/// Checks the arguments against the signature of the closure, and converts
+/// the ones passed to empty interface parameters
+fn check_args(
+    cls: &ClosureObj,
+    args: Vec<GosValue>,
+    objs: &VMObjects,
+    gcv: &GcoVec,
+) -> RuntimeResult<Vec<GosValue>> {
+    let sig = objs.metas[cls.meta.as_non_ptr()].as_signature();
+    if args.len() != sig.params.len() {
+        return Err(format!(
+            "expected {} arguments, got {}",
+            sig.params.len(),
+            args.len()
+        ));
+    }
+    args.into_iter()
+        .zip(sig.params.iter())
+        .enumerate()
+        .map(|(i, (arg, meta))| {
+            arg.assign_to(meta, objs, gcv)
+                .map_err(|e| format!("argument {}: {}", i, e))
+        })
+        .collect()
+}
+
+/// The stack and the first frame of a fiber that calls the closure, and the
+/// types of the return values left on the stack when the fiber returns
+fn closure_fiber(
+    cls: Rc<(RefCell<ClosureObj>, RCount)>,
+    args: Vec<GosValue>,
+    objs: &VMObjects,
+) -> RuntimeResult<(Stack, CallFrame, Vec<ValueType>)> {
+    let fkey = cls
+        .0
+        .borrow()
+        .func
+        .ok_or_else(|| "cannot call ffi functions".to_owned())?;
+    let func = &objs.functions[fkey];
+    let mut stack = Stack::new();
+    stack.append(func.ret_zeros.clone());
+    if let Some(r) = &cls.0.borrow().recv {
+        stack.push(r.clone());
     }
+    stack.append(args);
+    let types = func.ret_zeros.iter().map(|x| x.typ()).collect();
+    Ok((stack, CallFrame::with_closure(cls, 0), types))
 }
 
 #[cfg(test)]